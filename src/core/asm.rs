@@ -0,0 +1,608 @@
+use crate::core::{
+    reader::Reader,
+    bytecode::{self, Instruction, Opcode, DataType, ComparisonKind, VariableType, ChainLink, Operand},
+    chunks::{ChunkOutput, code::ChunkCODE, strg::ChunkSTRG, vari::ChunkVARI, func::ChunkFUNC, objt::ChunkOBJT},
+    disasm::{opcode_name, type_name, comparison_name, instance_name, variable_type_prefix, EXTENDED_OPCODES},
+    models::{code_entry::CodeEntry, function::{Function, CodeLocals, LocalVariable}, variable::{Variable, InstanceType}},
+    references::{self, CodeLocation},
+};
+use bstr::{BString, ByteVec};
+use std::{collections::HashMap, io::{Error, ErrorKind, Read, Result, Seek}};
+
+const OPCODES: [Opcode; 33] = [
+    Opcode::Conv, Opcode::Mul, Opcode::Div, Opcode::Rem, Opcode::Mod, Opcode::Add, Opcode::Sub, Opcode::And,
+    Opcode::Or, Opcode::Xor, Opcode::Neg, Opcode::Not, Opcode::Shl, Opcode::Shr, Opcode::Cmp, Opcode::Pop,
+    Opcode::PushI, Opcode::Dup, Opcode::CallV, Opcode::Ret, Opcode::Exit, Opcode::Popz, Opcode::B, Opcode::Bt,
+    Opcode::Bf, Opcode::PushEnv, Opcode::PopEnv, Opcode::Push, Opcode::PushLoc, Opcode::PushGlb, Opcode::PushBltn,
+    Opcode::Call, Opcode::Break,
+];
+
+const DATA_TYPES: [DataType; 12] = [
+    DataType::Double, DataType::Float, DataType::Int32, DataType::Int64, DataType::Boolean, DataType::Variable,
+    DataType::String, DataType::Instance, DataType::Delete, DataType::Undefined, DataType::UnsignedInt, DataType::Int16,
+];
+
+const COMPARISONS: [ComparisonKind; 6] = [
+    ComparisonKind::Less, ComparisonKind::LessEqual, ComparisonKind::Equal,
+    ComparisonKind::NotEqual, ComparisonKind::GreaterEqual, ComparisonKind::Greater,
+];
+
+const VARIABLE_TYPES: [VariableType; 5] = [
+    VariableType::Array, VariableType::StackTop, VariableType::Instance, VariableType::MultiPush, VariableType::MultiPushPop,
+];
+
+const INSTANCES: [i16; 10] = [-1, -2, -3, -4, -5, -6, -7, -9, -15, -16];
+
+fn error(line: usize, message: impl std::fmt::Display) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Line {}: {}", line, message))
+}
+
+fn parse_number<T: std::str::FromStr>(line: usize, text: &str) -> Result<T> {
+    text.trim().parse().map_err(|_| error(line, format!("Invalid number \"{}\"", text.trim())))
+}
+
+// Strips a trailing comment, ignoring semicolons inside of strings
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+// Parses a quoted string followed by an optional "@id"
+fn parse_string(line: usize, text: &str) -> Result<(BString, Option<u32>)> {
+    let mut chars = text.trim().char_indices();
+    if chars.next().map(|(_, c)| c) != Some('"') {
+        return Err(error(line, "Expected a string"));
+    }
+    let mut string = BString::default();
+    let mut end = None;
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => {
+                end = Some(index + 1);
+                break;
+            }
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('n') => string.push(b'\n'),
+                Some('r') => string.push(b'\r'),
+                Some('t') => string.push(b'\t'),
+                // Raw bytes that aren't valid UTF-8
+                Some('x') => {
+                    let digits = chars.by_ref().take(2).map(|(_, c)| c).collect::<String>();
+                    let byte = u8::from_str_radix(&digits, 16).ok()
+                        .filter(|_| digits.len() == 2 && digits.chars().all(|c| c.is_ascii_hexdigit()))
+                        .ok_or_else(|| error(line, format!("Invalid byte escape \"\\x{}\"", digits)))?;
+                    string.push(byte);
+                }
+                Some(c) => string.push_char(c),
+                None => return Err(error(line, "Unterminated string")),
+            },
+            _ => string.push_char(c),
+        }
+    }
+    let end = end.ok_or_else(|| error(line, "Unterminated string"))?;
+    let id = match text.trim()[end..].trim().strip_prefix('@') {
+        Some(id) => Some(parse_number(line, id)?),
+        None => None,
+    };
+
+    Ok((string, id))
+}
+
+struct ChildMarker {
+    name: BString,
+    offset: u32,
+    locals_count: u16,
+    arguments_count: u16,
+}
+
+#[derive(Default)]
+struct Parsed {
+    instructions: Vec<Instruction>,
+    branches: Vec<(usize, usize, String)>, // (Line, instruction index, label)
+    labels: HashMap<String, u32>,
+    children: Vec<ChildMarker>,
+    locals: Vec<LocalVariable>,
+}
+
+pub struct Assembler<'a> {
+    pub code: &'a mut ChunkCODE,
+    pub strings: &'a mut ChunkSTRG,
+    pub variables: &'a mut ChunkVARI,
+    pub functions: &'a mut ChunkFUNC,
+    pub objects: Option<&'a ChunkOBJT>,
+    pub format_id: i8,
+}
+
+// Assembles `text` into the code entry at `index` of a loaded data file
+pub fn assemble<R>(reader: &mut Reader<R>, index: usize, text: &str) -> Result<()>
+    where R: Read + Seek,
+{
+    let mut code = match reader.chunks.remove(b"CODE".as_slice()) {
+        Some(ChunkOutput::ChunkCode(chunk)) => chunk,
+        _ => return Err(Error::new(ErrorKind::NotFound, "The CODE chunk was not loaded")),
+    };
+    let mut strings = match reader.chunks.remove(b"STRG".as_slice()) {
+        Some(ChunkOutput::ChunkStrg(chunk)) => chunk,
+        _ => ChunkSTRG::default(),
+    };
+    let mut variables = match reader.chunks.remove(b"VARI".as_slice()) {
+        Some(ChunkOutput::ChunkVari(chunk)) => chunk,
+        _ => ChunkVARI::default(),
+    };
+    let mut functions = match reader.chunks.remove(b"FUNC".as_slice()) {
+        Some(ChunkOutput::ChunkFunc(chunk)) => chunk,
+        _ => ChunkFUNC::default(),
+    };
+    let result = Assembler {
+        code: &mut code,
+        strings: &mut strings,
+        variables: &mut variables,
+        functions: &mut functions,
+        objects: reader.objects(),
+        format_id: reader.version_info.format_id,
+    }.assemble(index, text);
+    reader.chunks.insert(BString::from("CODE"), code.into());
+    reader.chunks.insert(BString::from("STRG"), strings.into());
+    reader.chunks.insert(BString::from("VARI"), variables.into());
+    reader.chunks.insert(BString::from("FUNC"), functions.into());
+
+    result
+}
+
+impl Assembler<'_> {
+    // Replaces the bytecode of a code entry, then rebuilds every reference chain so they account for it
+    pub fn assemble(&mut self, index: usize, text: &str) -> Result<()> {
+        let entry = self.code.entries.values.get(index)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("Code entry {} does not exist", index)))?;
+        if entry.parent.is_some() {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("{} is a child function, assemble its parent instead", entry.name)));
+        }
+        let name = entry.name.clone();
+
+        // Everything is decoded under the current chains before any table changes
+        let variable_map = references::reference_map(&self.variables.references(self.code)?);
+        let function_map = references::reference_map(&self.functions.references(self.code)?);
+        let mut blobs = Vec::with_capacity(self.code.entries.len());
+        for entry in self.code.entries.values.iter() {
+            if entry.parent.is_some() {
+                blobs.push(Vec::new());
+                continue;
+            }
+            let mut instructions = bytecode::decode(&entry.bytecode, 0, self.format_id)?;
+            bytecode::resolve_references(&mut instructions, entry.bytecode_address, &variable_map, &function_map);
+            blobs.push(instructions);
+        }
+
+        let existing_locals = self.functions.code_locals.values.iter()
+            .find(|locals| locals.name == name)
+            .map(|locals| locals.locals.clone());
+        let parsed = self.parse(text, existing_locals.unwrap_or_default())?;
+        blobs[index] = parsed.instructions;
+
+        // Child functions keep their entries, only moving to wherever their marker ended up
+        for (child, entry) in self.code.entries.values.iter().enumerate() {
+            if entry.parent == Some(index) && !parsed.children.iter().any(|marker| marker.name == entry.name) {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Child function {} is missing from the assembly of entry {}", entry.name, child)));
+            }
+        }
+        for marker in parsed.children {
+            match self.code.entries.values.iter_mut().find(|entry| entry.parent == Some(index) && entry.name == marker.name) {
+                Some(entry) => {
+                    entry.offset = marker.offset;
+                    entry.locals_count = marker.locals_count;
+                    entry.arguments_count = marker.arguments_count;
+                }
+                None => {
+                    self.strings.find_or_push(&marker.name);
+                    self.code.entries.push(CodeEntry {
+                        name: marker.name,
+                        locals_count: marker.locals_count,
+                        arguments_count: marker.arguments_count,
+                        offset: marker.offset,
+                        parent: Some(index),
+                        ..Default::default()
+                    });
+                    blobs.push(Vec::new());
+                }
+            }
+        }
+
+        if !parsed.locals.is_empty() || self.functions.code_locals.values.iter().any(|locals| locals.name == name) {
+            self.code.entries.values[index].locals_count = parsed.locals.len() as _;
+            self.variables.max_local_var_count = self.variables.max_local_var_count.max(parsed.locals.len() as _);
+            match self.functions.code_locals.values.iter_mut().find(|locals| locals.name == name) {
+                Some(locals) => locals.locals = parsed.locals,
+                None => self.functions.code_locals.push(CodeLocals {
+                    name,
+                    locals: parsed.locals,
+                }),
+            }
+        }
+
+        self.rebuild(blobs)
+    }
+
+    // Lays the bytecode out again, rewrites every reference chain and encodes the result
+    fn rebuild(&mut self, mut blobs: Vec<Vec<Instruction>>) -> Result<()> {
+        let base = self.code.entries.values.iter()
+            .filter(|entry| entry.parent.is_none())
+            .map(|entry| entry.bytecode_address)
+            .min()
+            .unwrap_or_default();
+        let mut address = base;
+        for (index, entry) in self.code.entries.values.iter_mut().enumerate() {
+            if entry.parent.is_some() {
+                continue;
+            }
+            // Bytecode 14<= keeps it inline, right after the name and length of each entry
+            if self.format_id <= 14 && address != base {
+                address += 8;
+            }
+            entry.bytecode_address = address;
+            address += blobs[index].iter().map(|instruction| instruction.size() as u64).sum::<u64>();
+        }
+
+        let mut variable_chains = vec![Vec::new(); self.variables.variables.len()];
+        let mut function_chains = vec![Vec::new(); self.functions.functions.len()];
+        for (index, instructions) in blobs.iter().enumerate() {
+            for (position, instruction) in instructions.iter().enumerate() {
+                let (chains, link) = match instruction.operand {
+                    Operand::Variable(link) => (&mut variable_chains, link),
+                    Operand::Function(link) => (&mut function_chains, link),
+                    _ => continue,
+                };
+                let chain = link.index.and_then(|chain| chains.get_mut(chain)).ok_or_else(|| {
+                    Error::new(ErrorKind::InvalidData, format!("Unresolved reference in {} at {:#x}", self.code.entries[index].name, instruction.offset))
+                })?;
+                chain.push((index, position));
+            }
+        }
+
+        // Each link holds the distance to the next occurrence, except the last one which holds the ID of the name
+        // Distances only depend on the sizes of the bytecode in between, the first occurrence is resolved once CODE is written
+        let address_of = |code: &ChunkCODE, blobs: &[Vec<Instruction>], (index, position): (usize, usize)| {
+            code.entries[index].bytecode_address + blobs[index][position].offset as u64
+        };
+        let location_of = |blobs: &[Vec<Instruction>], (index, position): (usize, usize)| CodeLocation {
+            code_id: index,
+            offset: blobs[index][position].offset,
+        };
+        for (variable, chain) in self.variables.variables.iter_mut().zip(variable_chains) {
+            let name_id = self.strings.find_or_push(&variable.name) as u32;
+            variable.occurrences = chain.len() as _;
+            variable.first_occurrence = chain.first().map(|location| location_of(&blobs, *location));
+            for (occurrence, location) in chain.iter().enumerate() {
+                let next = match chain.get(occurrence + 1) {
+                    Some(next) => (address_of(self.code, &blobs, *next) - address_of(self.code, &blobs, *location)) as u32,
+                    None => name_id,
+                };
+                if let Operand::Variable(link) = &mut blobs[location.0][location.1].operand {
+                    link.raw = (next & 0x07ffffff) | (link.kind.bits() as u32) << 24;
+                }
+            }
+        }
+        for (function, chain) in self.functions.functions.values.iter_mut().zip(function_chains) {
+            let name_id = self.strings.find_or_push(&function.name) as u32;
+            function.occurrences = chain.len() as _;
+            function.first_occurrence = chain.first().map(|location| location_of(&blobs, *location));
+            for (occurrence, location) in chain.iter().enumerate() {
+                let next = match chain.get(occurrence + 1) {
+                    Some(next) => (address_of(self.code, &blobs, *next) - address_of(self.code, &blobs, *location)) as u32,
+                    None => name_id,
+                };
+                if let Operand::Function(link) = &mut blobs[location.0][location.1].operand {
+                    link.raw = next & 0x07ffffff;
+                }
+            }
+        }
+
+        for (entry, instructions) in self.code.entries.values.iter_mut().zip(blobs) {
+            if entry.parent.is_some() {
+                continue;
+            }
+            entry.bytecode = bytecode::encode(&instructions, self.format_id)?;
+            entry.length = entry.bytecode.len() as _;
+        }
+
+        Ok(())
+    }
+
+    fn parse(&mut self, text: &str, locals: Vec<LocalVariable>) -> Result<Parsed> {
+        let mut parsed = Parsed {
+            locals,
+            ..Default::default()
+        };
+        let mut offset = 0;
+        let mut declared_locals = false;
+        for (line, content) in text.lines().enumerate() {
+            let line = line + 1;
+            let content = strip_comment(content).trim();
+            if content.is_empty() {
+                continue;
+            }
+            if let Some(local) = content.strip_prefix(".localvar ") {
+                // Declared locals replace the ones already known for the entry
+                if !declared_locals {
+                    parsed.locals.clear();
+                    declared_locals = true;
+                }
+                let (index, name) = local.trim().split_once(char::is_whitespace).ok_or_else(|| error(line, "Expected an index and a name"))?;
+                self.strings.find_or_push(name.trim().as_bytes());
+                parsed.locals.push(LocalVariable {
+                    index: parse_number(line, index)?,
+                    name: BString::from(name.trim()),
+                });
+            } else if let Some(marker) = content.strip_prefix('>') {
+                parsed.children.push(self.parse_child(line, marker, offset)?);
+            } else if let Some(label) = content.strip_prefix(':') {
+                if parsed.labels.insert(label.trim().to_string(), offset).is_some() {
+                    return Err(error(line, format!("Label {} is defined twice", label.trim())));
+                }
+            } else {
+                let (instruction, label) = self.parse_instruction(line, content, offset, &mut parsed.locals)?;
+                if let Some(label) = label {
+                    parsed.branches.push((line, parsed.instructions.len(), label));
+                }
+                offset += instruction.size();
+                parsed.instructions.push(instruction);
+            }
+        }
+
+        parsed.labels.entry(String::from("[end]")).or_insert(offset);
+        for (line, index, label) in parsed.branches.iter() {
+            let target = parsed.labels.get(label).ok_or_else(|| error(*line, format!("Label {} is not defined", label)))?;
+            let instruction = &mut parsed.instructions[*index];
+            instruction.jump = *target as i32 - instruction.offset as i32;
+        }
+
+        Ok(parsed)
+    }
+
+    // "> name (locals=N, argc=N)"
+    fn parse_child(&self, line: usize, marker: &str, offset: u32) -> Result<ChildMarker> {
+        let (name, counts) = marker.trim().split_once('(').ok_or_else(|| error(line, "Expected the counts of the child function"))?;
+        let mut child = ChildMarker {
+            name: BString::from(name.trim()),
+            offset,
+            locals_count: 0,
+            arguments_count: 0,
+        };
+        for count in counts.trim_end_matches(')').split(',') {
+            match count.trim().split_once('=') {
+                Some(("locals", value)) => child.locals_count = parse_number(line, value)?,
+                Some(("argc", value)) => child.arguments_count = parse_number(line, value)?,
+                _ => return Err(error(line, format!("Unknown child function property \"{}\"", count.trim()))),
+            }
+        }
+
+        Ok(child)
+    }
+
+    fn parse_instruction(&mut self, line: usize, content: &str, offset: u32, locals: &mut Vec<LocalVariable>) -> Result<(Instruction, Option<String>)> {
+        let (mnemonic, arguments) = match content.split_once(char::is_whitespace) {
+            Some((mnemonic, arguments)) => (mnemonic, arguments.trim()),
+            None => (content, ""),
+        };
+        let mut parts = mnemonic.split('.');
+        let name = parts.next().unwrap_or_default();
+        let mut types = [DataType::default(); 2];
+        for data_type in types.iter_mut() {
+            if let Some(part) = parts.next() {
+                *data_type = DATA_TYPES.iter().copied().find(|data_type| type_name(*data_type) == part)
+                    .ok_or_else(|| error(line, format!("Unknown type \"{}\"", part)))?;
+            }
+        }
+        let mut instruction = Instruction {
+            offset,
+            type1: types[0],
+            type2: types[1],
+            ..Default::default()
+        };
+        let extended = EXTENDED_OPCODES.iter().find(|(_, extended)| *extended == name);
+        match extended {
+            Some((value, _)) => {
+                instruction.opcode = Opcode::Break;
+                instruction.value = *value;
+            }
+            None => {
+                instruction.opcode = OPCODES.iter().copied().find(|opcode| opcode_name(*opcode) == name)
+                    .ok_or_else(|| error(line, format!("Unknown opcode \"{}\"", name)))?;
+            }
+        }
+
+        let mut label = None;
+        match instruction.opcode {
+            Opcode::Cmp => {
+                instruction.comparison = COMPARISONS.iter().copied().find(|comparison| comparison_name(*comparison) == arguments)
+                    .ok_or_else(|| error(line, format!("Unknown comparison \"{}\"", arguments)))?;
+            }
+            Opcode::Dup => {
+                let mut arguments = arguments.split_whitespace();
+                instruction.value = parse_number(line, arguments.next().unwrap_or_default())?;
+                if let Some(mode) = arguments.next() {
+                    instruction.dup_mode = parse_number(line, mode)?;
+                }
+            }
+            Opcode::CallV => instruction.value = parse_number(line, arguments)?,
+            Opcode::B | Opcode::Bt | Opcode::Bf | Opcode::PushEnv | Opcode::PopEnv => {
+                if instruction.opcode == Opcode::PopEnv && arguments == "[drop]" {
+                    instruction.popenv_drop = true;
+                } else if arguments.starts_with('[') && arguments.ends_with(']') {
+                    label = Some(arguments.to_string());
+                } else {
+                    return Err(error(line, format!("Expected a label, got \"{}\"", arguments)));
+                }
+            }
+            Opcode::Pop if instruction.type1 == DataType::Int16 => instruction.value = parse_number(line, arguments)?,
+            Opcode::Pop => {
+                let (instance, link) = self.parse_variable(line, arguments, locals)?;
+                instruction.value = instance;
+                instruction.operand = Operand::Variable(link);
+            }
+            Opcode::Push | Opcode::PushLoc | Opcode::PushGlb | Opcode::PushBltn | Opcode::PushI => {
+                match instruction.type1 {
+                    DataType::Int16 => instruction.value = parse_number(line, arguments)?,
+                    DataType::Variable => {
+                        let (instance, link) = self.parse_variable(line, arguments, locals)?;
+                        instruction.value = instance;
+                        instruction.operand = Operand::Variable(link);
+                    }
+                    DataType::String => {
+                        let (string, id) = parse_string(line, arguments)?;
+                        let id = match id {
+                            Some(id) if self.strings.get(id as usize) == Some(&string) => id as usize,
+                            _ => self.strings.find_or_push(&string),
+                        };
+                        instruction.operand = Operand::String(id as u32);
+                    }
+                    _ => instruction.operand = self.parse_constant(line, instruction.type1, arguments)?,
+                }
+            }
+            Opcode::Call => {
+                let (name, argc) = arguments.rsplit_once('(').ok_or_else(|| error(line, "Expected a function call"))?;
+                let argc = argc.trim_end_matches(')').trim().strip_prefix("argc=").ok_or_else(|| error(line, "Expected an argument count"))?;
+                instruction.value = parse_number::<u16>(line, argc)? as i16;
+                instruction.operand = Operand::Function(self.function_link(name.trim()));
+            }
+            Opcode::Break => {
+                let mut arguments = arguments;
+                if extended.is_none() {
+                    let (value, rest) = arguments.split_once(char::is_whitespace).unwrap_or((arguments, ""));
+                    instruction.value = parse_number(line, value)?;
+                    arguments = rest.trim();
+                }
+                if instruction.type1 == DataType::Int32 {
+                    instruction.operand = self.parse_constant(line, DataType::Int32, arguments)?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok((instruction, label))
+    }
+
+    fn parse_constant(&mut self, line: usize, data_type: DataType, text: &str) -> Result<Operand> {
+        Ok(match data_type {
+            DataType::Double => Operand::Double(parse_number(line, text)?),
+            DataType::Float => Operand::Float(parse_number(line, text)?),
+            DataType::Int32 => match text.strip_prefix("[function]") {
+                Some(name) => Operand::Function(self.function_link(name.trim())),
+                None => Operand::Int32(parse_number(line, text)?),
+            },
+            DataType::Int64 => Operand::Int64(parse_number(line, text)?),
+            DataType::Boolean => Operand::Boolean(match text {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => return Err(error(line, format!("Invalid boolean \"{}\"", text))),
+            }),
+            _ => return Err(error(line, format!("Cannot push a constant of type {}", type_name(data_type)))),
+        })
+    }
+
+    // "[prefix]instance.name"
+    fn parse_variable(&mut self, line: usize, text: &str, locals: &mut Vec<LocalVariable>) -> Result<(i16, ChainLink)> {
+        let mut kind = VariableType::Normal;
+        let mut text = text;
+        if text.starts_with('[') {
+            let end = text.find(']').ok_or_else(|| error(line, "Unterminated variable type"))?;
+            kind = VARIABLE_TYPES.iter().copied().find(|kind| variable_type_prefix(*kind) == &text[..=end])
+                .ok_or_else(|| error(line, format!("Unknown variable type \"{}\"", &text[..=end])))?;
+            text = &text[end + 1..];
+        }
+        let (instance, name) = text.split_once('.').ok_or_else(|| error(line, format!("Expected instance.name, got \"{}\"", text)))?;
+        let instance = match INSTANCES.iter().copied().find(|value| instance_name(*value) == Some(instance)) {
+            Some(value) => value,
+            None => match instance.parse::<i16>() {
+                Ok(value) => value,
+                Err(_) => self.objects
+                    .and_then(|objects| objects.objects.values.iter().position(|object| object.name == instance))
+                    .ok_or_else(|| error(line, format!("Unknown instance \"{}\"", instance)))? as i16,
+            },
+        };
+
+        let index = self.variable_index(name.trim(), instance, locals);
+        Ok((instance, ChainLink {
+            kind,
+            index: Some(index),
+            raw: 0,
+        }))
+    }
+
+    // Finds the VARI entry a reference should use, adding one when it's missing
+    fn variable_index(&mut self, name: &str, instance: i16, locals: &mut Vec<LocalVariable>) -> usize {
+        let instance_type = match instance {
+            _ if self.format_id <= 14 => InstanceType::Undefined,
+            -7 => InstanceType::Local,
+            -5 => InstanceType::Global,
+            -16 => InstanceType::Static,
+            _ => InstanceType::Self_,
+        };
+        let existing = self.variables.variables.iter().position(|variable| {
+            variable.name == name && (self.format_id <= 14 || variable.instance_type == instance_type)
+        });
+        let local_index = match locals.iter().find(|local| local.name == name) {
+            Some(local) => local.index,
+            None if instance_type == InstanceType::Local => {
+                locals.push(LocalVariable {
+                    index: locals.len() as _,
+                    name: BString::from(name),
+                });
+                self.strings.find_or_push(name.as_bytes());
+                locals.len() as u32 - 1
+            }
+            None => 0,
+        };
+        if let Some(index) = existing {
+            return index;
+        }
+
+        self.strings.find_or_push(name.as_bytes());
+        let var_id = match instance {
+            _ if self.format_id <= 14 => 0,
+            _ if instance_type == InstanceType::Local => local_index as i32,
+            -6 => -6,
+            _ => {
+                self.variables.instance_var_count += 1;
+                self.variables.instance_var_count_again += 1;
+                self.variables.instance_var_count as i32 - 1
+            }
+        };
+        self.variables.variables.push(Variable {
+            name: BString::from(name),
+            instance_type,
+            var_id,
+            ..Default::default()
+        });
+        self.variables.variables.len() - 1
+    }
+
+    fn function_link(&mut self, name: &str) -> ChainLink {
+        let index = match self.functions.functions.values.iter().position(|function| function.name == name) {
+            Some(index) => index,
+            None => {
+                self.strings.find_or_push(name.as_bytes());
+                self.functions.functions.push(Function {
+                    name: BString::from(name),
+                    ..Default::default()
+                });
+                self.functions.functions.len() - 1
+            }
+        };
+
+        ChainLink {
+            kind: VariableType::Normal,
+            index: Some(index),
+            raw: 0,
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::core::{reader::Reader, models::{sound::{Sound, AudioEntryFlags}, embedded_audio::EmbeddedAudio}};
+use std::{collections::HashSet, fs, io::{Read, Result, Seek}, path::{Path, PathBuf}};
+
+pub const WAV_MAGIC: [u8; 4] = *b"RIFF";
+pub const OGG_MAGIC: [u8; 4] = *b"OggS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Wav,
+    Ogg,
+    Unknown,
+}
+
+impl AudioFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Ogg => "ogg",
+            Self::Unknown => "bin",
+        }
+    }
+}
+
+pub fn detect_format(blob: &[u8]) -> AudioFormat {
+    if blob.starts_with(&WAV_MAGIC) {
+        AudioFormat::Wav
+    } else if blob.starts_with(&OGG_MAGIC) {
+        AudioFormat::Ogg
+    } else {
+        AudioFormat::Unknown
+    }
+}
+
+// Sounds of the builtin group are stored in the data file, every other group has its own audiogroupN.dat
+// Sounds that are neither embedded nor compressed are streamed from a file next to the data file, and have no entry
+pub fn find<'a, R>(reader: &'a Reader<R>, sound: &Sound) -> Option<&'a EmbeddedAudio>
+    where R: Read + Seek,
+{
+    if !sound.flags.intersects(AudioEntryFlags::IsEmbedded | AudioEntryFlags::IsCompressed) {
+        return None;
+    }
+    if sound.group_id < 0 || sound.group_id == reader.version_info.builtin_audio_group_id {
+        return reader.audio()?.get(sound.audio_id);
+    }
+    reader.audio_groups()?
+        .audio_data.get(&(sound.group_id as usize))?
+        .audio()?
+        .get(sound.audio_id)
+}
+
+// Turns a resource name into a single file name, so names like "../sfx" can't leave the export directory
+fn file_name(name: &[u8]) -> String {
+    let name = String::from_utf8_lossy(name)
+        .chars()
+        .map(|c| if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect::<String>();
+    if name.trim_matches('.').is_empty() {
+        return format!("_{}", name);
+    }
+    name
+}
+
+// Writes every embedded sound to `directory` as <name>.wav or <name>.ogg, returning the paths written
+// Names that end up the same once sanitized, such as "a/b" and "a_b", get the audio ID appended from the second one on
+pub fn export<R>(reader: &Reader<R>, directory: &Path) -> Result<Vec<PathBuf>>
+    where R: Read + Seek,
+{
+    let mut paths = Vec::new();
+    let Some(sounds) = reader.sounds() else {
+        return Ok(paths);
+    };
+    fs::create_dir_all(directory)?;
+    // Lowercase, as file names don't tell case apart on every system
+    let mut taken = HashSet::new();
+    for sound in sounds.sounds.values.iter() {
+        // Streamed sounds live next to the data file instead
+        let Some(audio) = find(reader, sound) else {
+            continue;
+        };
+        let (name, extension) = (file_name(&sound.name), audio.format().extension());
+        let mut file = format!("{}.{}", name, extension);
+        let mut attempt = 0;
+        while !taken.insert(file.to_lowercase()) {
+            attempt += 1;
+            file = match attempt {
+                1 => format!("{}_{}.{}", name, sound.audio_id, extension),
+                _ => format!("{}_{}_{}.{}", name, sound.audio_id, attempt, extension),
+            };
+        }
+        let path = directory.join(file);
+        fs::write(&path, &audio.data)?;
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        chunks::{sond::ChunkSOND, audo::ChunkAUDO, strg::ChunkSTRG},
+        verify::tests::{fixture, gen8, optn, version},
+    };
+    use std::io::Cursor;
+
+    #[test]
+    fn exports_colliding_names_once() {
+        let version_info = version(2, 0, 0, 0, 17);
+        let mut sond = ChunkSOND::default();
+        let mut audo = ChunkAUDO::default();
+        for (audio_id, name) in ["a/b", "a_b", "A_B"].into_iter().enumerate() {
+            sond.sounds.push(Sound {
+                name: name.into(),
+                flags: AudioEntryFlags::IsEmbedded | AudioEntryFlags::Regular,
+                kind: ".wav".into(),
+                file: "sound.wav".into(),
+                volume: 1.0,
+                pitch: 1.0,
+                audio_id: audio_id as _,
+                ..Default::default()
+            });
+            audo.audio.push(EmbeddedAudio {
+                data: format!("RIFF{}", audio_id).into_bytes(),
+            });
+        }
+        let data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("SOND", sond.into()),
+            ("STRG", ChunkSTRG::default().into()),
+            ("AUDO", audo.into()),
+        ]);
+        let mut reader = Reader::new(Cursor::new(data.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        reader.deserialize().unwrap();
+
+        let directory = std::env::temp_dir().join(format!("clovy-audio-export-{}", std::process::id()));
+        let paths = export(&reader, &directory).unwrap();
+        let names = paths.iter().map(|path| path.file_name().unwrap().to_string_lossy().into_owned()).collect::<Vec<_>>();
+        assert_eq!(names, ["a_b.wav", "a_b_1.wav", "A_B_2.wav"]);
+        for (audio_id, path) in paths.iter().enumerate() {
+            assert_eq!(fs::read(path).unwrap(), format!("RIFF{}", audio_id).into_bytes());
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
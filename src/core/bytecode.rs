@@ -0,0 +1,382 @@
+use bitflags::bitflags;
+use integer_hasher::IntMap;
+use std::io::{Error, ErrorKind, Result};
+
+bitflags! {
+    // Uses the bytecode 15>= numbering, older opcodes are converted when decoding
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Opcode: u8 {
+        const Conv = 0x07;
+        const Mul = 0x08;
+        const Div = 0x09;
+        const Rem = 0x0a;
+        const Mod = 0x0b;
+        const Add = 0x0c;
+        const Sub = 0x0d;
+        const And = 0x0e;
+        const Or = 0x0f;
+        const Xor = 0x10;
+        const Neg = 0x11;
+        const Not = 0x12;
+        const Shl = 0x13;
+        const Shr = 0x14;
+        const Cmp = 0x15;
+        const Pop = 0x45;
+        const PushI = 0x84;
+        const Dup = 0x86;
+        const CallV = 0x99;
+        const Ret = 0x9c;
+        const Exit = 0x9d;
+        const Popz = 0x9e;
+        const B = 0xb6;
+        const Bt = 0xb7;
+        const Bf = 0xb8;
+        const PushEnv = 0xba;
+        const PopEnv = 0xbb;
+        const Push = 0xc0;
+        const PushLoc = 0xc1;
+        const PushGlb = 0xc2;
+        const PushBltn = 0xc3;
+        const Call = 0xd9;
+        const Break = 0xff;
+    }
+}
+
+impl Default for Opcode {
+    fn default() -> Self {
+        Self::Break
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct DataType: u8 {
+        const Double = 0;
+        const Float = 1;
+        const Int32 = 2;
+        const Int64 = 3;
+        const Boolean = 4;
+        const Variable = 5;
+        const String = 6;
+        const Instance = 7;
+        const Delete = 8;
+        const Undefined = 9;
+        const UnsignedInt = 10;
+        const Int16 = 15;
+    }
+}
+
+impl Default for DataType {
+    fn default() -> Self {
+        Self::Double
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct ComparisonKind: u8 {
+        const None = 0;
+        const Less = 1;
+        const LessEqual = 2;
+        const Equal = 3;
+        const NotEqual = 4;
+        const GreaterEqual = 5;
+        const Greater = 6;
+    }
+}
+
+impl Default for ComparisonKind {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+bitflags! {
+    // Stored in the upper 5 bits of a variable reference
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct VariableType: u8 {
+        const Array = 0x00;
+        const MultiPush = 0x10;
+        const StackTop = 0x80;
+        const MultiPushPop = 0x90;
+        const Normal = 0xa0;
+        const Instance = 0xe0;
+    }
+}
+
+impl Default for VariableType {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+// Value of the 24 bit branch field marking a popenv that exits the with loop early
+pub const POPENV_DROP_MAGIC: u32 = 0xf00000;
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainLink {
+    pub kind: VariableType, // Variables only
+    pub index: Option<usize>, // Index into VARI or FUNC, once the reference chains are resolved
+    pub raw: u32, // The operand as stored, which is either the offset to the next occurrence or the ID on the last one
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum Operand {
+    #[default]
+    None,
+    Double(f64),
+    Float(f32),
+    Int32(i32),
+    Int64(i64),
+    Boolean(bool),
+    String(u32), // Index into STRG
+    Variable(ChainLink),
+    Function(ChainLink),
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct Instruction {
+    pub offset: u32, // Byte offset from the start of the bytecode blob
+    pub opcode: Opcode,
+    pub type1: DataType,
+    pub type2: DataType,
+    pub value: i16, // Instance type, argument count, dup size, extended opcode or short constant depending on the opcode
+    pub comparison: ComparisonKind, // Cmp only
+    pub dup_mode: u8, // From bytecode 15>=, non-zero on the swapping form of dup
+    pub jump: i32, // Branches only, in bytes relative to this instruction
+    pub popenv_drop: bool, // From bytecode 15>=
+    pub operand: Operand,
+}
+
+impl Instruction {
+    pub fn size(&self) -> u32 {
+        4 + match self.operand {
+            Operand::None => 0,
+            Operand::Double(_) | Operand::Int64(_) => 8,
+            _ => 4,
+        }
+    }
+
+    pub fn is_branch(&self) -> bool {
+        matches!(self.opcode, Opcode::B | Opcode::Bt | Opcode::Bf | Opcode::PushEnv | Opcode::PopEnv)
+    }
+
+    pub fn is_push(&self) -> bool {
+        matches!(self.opcode, Opcode::Push | Opcode::PushLoc | Opcode::PushGlb | Opcode::PushBltn | Opcode::PushI)
+    }
+
+    // Absolute target of a branch, as a byte offset from the start of the bytecode blob
+    pub fn target(&self) -> Option<u32> {
+        if !self.is_branch() || self.popenv_drop {
+            return None;
+        }
+        Some((self.offset as i64 + self.jump as i64) as u32)
+    }
+}
+
+// Bytecode 14<= numbers opcodes differently and has one opcode per comparison kind
+pub fn convert_old_opcode(opcode: u8) -> (Opcode, ComparisonKind) {
+    match opcode {
+        0x03..=0x10 => (Opcode::from_bits_retain(opcode + 4), ComparisonKind::None),
+        0x11..=0x16 => (Opcode::Cmp, ComparisonKind::from_bits_retain(opcode - 0x10)),
+        0x41 => (Opcode::Pop, ComparisonKind::None),
+        0x82 => (Opcode::Dup, ComparisonKind::None),
+        0x9d => (Opcode::Ret, ComparisonKind::None),
+        0x9e => (Opcode::Exit, ComparisonKind::None),
+        0x9f => (Opcode::Popz, ComparisonKind::None),
+        0xb7 => (Opcode::B, ComparisonKind::None),
+        0xb8 => (Opcode::Bt, ComparisonKind::None),
+        0xb9 => (Opcode::Bf, ComparisonKind::None),
+        0xbb => (Opcode::PushEnv, ComparisonKind::None),
+        0xbc => (Opcode::PopEnv, ComparisonKind::None),
+        0xda => (Opcode::Call, ComparisonKind::None),
+        _ => (Opcode::from_bits_retain(opcode), ComparisonKind::None),
+    }
+}
+
+fn read_bytes<const S: usize>(bytecode: &[u8], offset: usize) -> Result<[u8; S]> {
+    bytecode.get(offset..offset + S)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, format!("Truncated instruction at {:#x}", offset)))
+}
+
+pub fn decode_instruction(bytecode: &[u8], offset: u32, format_id: i8) -> Result<Instruction> {
+    let word = u32::from_le_bytes(read_bytes(bytecode, offset as usize)?);
+    let operand_offset = offset as usize + 4;
+    let mut instruction = Instruction {
+        offset,
+        type1: DataType::from_bits_retain(((word >> 16) & 0xf) as u8),
+        type2: DataType::from_bits_retain(((word >> 20) & 0xf) as u8),
+        value: word as u16 as i16,
+        ..Default::default()
+    };
+    if format_id <= 14 {
+        (instruction.opcode, instruction.comparison) = convert_old_opcode((word >> 24) as u8);
+    } else {
+        instruction.opcode = Opcode::from_bits_retain((word >> 24) as u8);
+        if instruction.opcode == Opcode::Cmp {
+            instruction.comparison = ComparisonKind::from_bits_retain((word >> 8) as u8);
+        }
+    }
+
+    let link = |raw: u32, kind: bool| ChainLink {
+        kind: if kind { VariableType::from_bits_retain(((raw >> 24) & 0xf8) as u8) } else { VariableType::Normal },
+        index: None,
+        raw,
+    };
+    match instruction.opcode {
+        Opcode::Dup => {
+            instruction.value = (word & 0xff) as i16;
+            if format_id > 14 {
+                instruction.dup_mode = (word >> 8) as u8;
+            }
+        }
+        Opcode::B | Opcode::Bt | Opcode::Bf | Opcode::PushEnv | Opcode::PopEnv => {
+            instruction.value = 0;
+            let jump = if format_id <= 14 {
+                ((word << 8) as i32) >> 8
+            } else if instruction.opcode == Opcode::PopEnv && word & 0xffffff == POPENV_DROP_MAGIC {
+                instruction.popenv_drop = true;
+                0
+            } else {
+                ((word << 9) as i32) >> 9
+            };
+            instruction.jump = jump * 4;
+        }
+        // The swapping form of pop has no variable, its value is the amount of items to swap
+        Opcode::Pop if instruction.type1 != DataType::Int16 => {
+            instruction.operand = Operand::Variable(link(u32::from_le_bytes(read_bytes(bytecode, operand_offset)?), true));
+        }
+        Opcode::Push | Opcode::PushLoc | Opcode::PushGlb | Opcode::PushBltn | Opcode::PushI => {
+            instruction.operand = match instruction.type1 {
+                DataType::Double => Operand::Double(f64::from_le_bytes(read_bytes(bytecode, operand_offset)?)),
+                DataType::Float => Operand::Float(f32::from_le_bytes(read_bytes(bytecode, operand_offset)?)),
+                DataType::Int32 => Operand::Int32(i32::from_le_bytes(read_bytes(bytecode, operand_offset)?)),
+                DataType::Int64 => Operand::Int64(i64::from_le_bytes(read_bytes(bytecode, operand_offset)?)),
+                DataType::Boolean => Operand::Boolean(u32::from_le_bytes(read_bytes(bytecode, operand_offset)?) != 0),
+                DataType::Variable => Operand::Variable(link(u32::from_le_bytes(read_bytes(bytecode, operand_offset)?), true)),
+                DataType::String => Operand::String(u32::from_le_bytes(read_bytes(bytecode, operand_offset)?)),
+                DataType::Int16 => Operand::None,
+                _ => {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("Invalid push type {} at {:#x}", instruction.type1.bits(), offset)));
+                }
+            };
+        }
+        Opcode::Call => {
+            instruction.operand = Operand::Function(link(u32::from_le_bytes(read_bytes(bytecode, operand_offset)?), false));
+        }
+        // Only some extended opcodes, like pushref, carry an argument
+        Opcode::Break if instruction.type1 == DataType::Int32 => {
+            instruction.operand = Operand::Int32(i32::from_le_bytes(read_bytes(bytecode, operand_offset)?));
+        }
+        _ => {}
+    }
+
+    Ok(instruction)
+}
+
+// Decodes every instruction from `start` up to the end of the bytecode
+pub fn decode(bytecode: &[u8], start: u32, format_id: i8) -> Result<Vec<Instruction>> {
+    let mut instructions = Vec::new();
+    let mut offset = start;
+    while (offset as usize) < bytecode.len() {
+        let instruction = decode_instruction(bytecode, offset, format_id)?;
+        offset += instruction.size();
+        instructions.push(instruction);
+    }
+
+    Ok(instructions)
+}
+
+// Fills in which variable or function every reference points to, `base` being the absolute offset of the bytecode blob
+pub fn resolve_references(instructions: &mut [Instruction], base: u64, variables: &IntMap<u64, usize>, functions: &IntMap<u64, usize>) {
+    for instruction in instructions.iter_mut() {
+        let address = base + instruction.offset as u64;
+        match &mut instruction.operand {
+            Operand::Variable(link) => link.index = variables.get(&address).copied(),
+            Operand::Function(link) => link.index = functions.get(&address).copied(),
+            // Function references also show up as plain integers, for example on method pushes from GMS 2.3>=
+            Operand::Int32(value) => {
+                if let Some(&index) = functions.get(&address) {
+                    instruction.operand = Operand::Function(ChainLink {
+                        kind: VariableType::Normal,
+                        index: Some(index),
+                        raw: *value as u32,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn convert_to_old_opcode(opcode: Opcode, comparison: ComparisonKind) -> u8 {
+    match opcode {
+        Opcode::Conv | Opcode::Mul | Opcode::Div | Opcode::Rem | Opcode::Mod | Opcode::Add | Opcode::Sub |
+        Opcode::And | Opcode::Or | Opcode::Xor | Opcode::Neg | Opcode::Not | Opcode::Shl | Opcode::Shr => opcode.bits() - 4,
+        Opcode::Cmp => 0x10 + comparison.bits(),
+        Opcode::Pop => 0x41,
+        Opcode::Dup => 0x82,
+        Opcode::Ret => 0x9d,
+        Opcode::Exit => 0x9e,
+        Opcode::Popz => 0x9f,
+        Opcode::B => 0xb7,
+        Opcode::Bt => 0xb8,
+        Opcode::Bf => 0xb9,
+        Opcode::PushEnv => 0xbb,
+        Opcode::PopEnv => 0xbc,
+        Opcode::Call => 0xda,
+        _ => opcode.bits(),
+    }
+}
+
+pub fn encode_instruction(instruction: &Instruction, format_id: i8, output: &mut Vec<u8>) -> Result<()> {
+    let opcode = if format_id <= 14 {
+        convert_to_old_opcode(instruction.opcode, instruction.comparison)
+    } else {
+        instruction.opcode.bits()
+    };
+    let mut word = (opcode as u32) << 24;
+    if instruction.jump % 4 != 0 {
+        return Err(Error::new(ErrorKind::InvalidData, format!("Branch at {:#x} is not aligned to an instruction", instruction.offset)));
+    }
+    if instruction.is_branch() {
+        word |= if format_id <= 14 {
+            (instruction.jump / 4) as u32 & 0xffffff
+        } else if instruction.popenv_drop {
+            POPENV_DROP_MAGIC
+        } else {
+            (instruction.jump / 4) as u32 & 0x7fffff
+        };
+    } else {
+        word |= (instruction.type2.bits() as u32 & 0xf) << 20 | (instruction.type1.bits() as u32 & 0xf) << 16;
+        word |= match instruction.opcode {
+            Opcode::Dup => (instruction.value as u32 & 0xff) | (instruction.dup_mode as u32) << 8,
+            Opcode::Cmp if format_id > 14 => (instruction.value as u32 & 0xff) | (instruction.comparison.bits() as u32) << 8,
+            _ => instruction.value as u16 as u32,
+        };
+    }
+    output.extend_from_slice(&word.to_le_bytes());
+
+    match instruction.operand {
+        Operand::None => {}
+        Operand::Double(value) => output.extend_from_slice(&value.to_le_bytes()),
+        Operand::Float(value) => output.extend_from_slice(&value.to_le_bytes()),
+        Operand::Int32(value) => output.extend_from_slice(&value.to_le_bytes()),
+        Operand::Int64(value) => output.extend_from_slice(&value.to_le_bytes()),
+        Operand::Boolean(value) => output.extend_from_slice(&(value as u32).to_le_bytes()),
+        Operand::String(id) => output.extend_from_slice(&id.to_le_bytes()),
+        Operand::Variable(link) | Operand::Function(link) => output.extend_from_slice(&link.raw.to_le_bytes()),
+    }
+
+    Ok(())
+}
+
+pub fn encode(instructions: &[Instruction], format_id: i8) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    for instruction in instructions.iter() {
+        encode_instruction(instruction, format_id, &mut output)?;
+    }
+
+    Ok(output)
+}
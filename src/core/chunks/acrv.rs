@@ -1,6 +1,6 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList, models::animation_curve::AnimationCurve};
+use crate::core::{reader::Reader, serializing::{Serialize, FormatCheck}, writer::Writer, lists::GMPointerList, models::animation_curve::AnimationCurve};
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, SeekFrom, Write};
 
 #[derive(Default, Clone)]
 pub struct ChunkACRV {
@@ -8,6 +8,50 @@ pub struct ChunkACRV {
     pub version: i32,
 }
 
+impl FormatCheck for ChunkACRV {
+    fn format_check<R>(reader: &mut Reader<R>) -> Result<()>
+        where R: Read + Seek,
+    {
+        if reader.version_info.is_version_at_least(2, 3, 0, 0) && !reader.version_info.is_version_at_least(2, 3, 1, 0) {
+            let return_to = reader.stream_position()?;
+            reader.seek_relative(4)?; // Version
+            let mut curve_ptrs = Vec::new();
+            for _ in 0..reader.read_u32()? {
+                curve_ptrs.push(reader.read_u32()? as u64);
+            }
+            // Points grow from 12 bytes to 24 with the bezier handles, so walk the first curve with points assuming they did
+            for (index, curve_ptr) in curve_ptrs.iter().enumerate() {
+                let end = curve_ptrs.get(index + 1).copied().unwrap_or(reader.current_chunk.end_offset);
+                reader.seek(SeekFrom::Start(curve_ptr + 8))?;
+                let mut point_count = 0;
+                let mut fits = true;
+                for _ in 0..reader.read_u32()? {
+                    if reader.stream_position()? + 16 > end {
+                        fits = false;
+                        break;
+                    }
+                    reader.seek_relative(12)?; // Name, function type and iterations
+                    let count = reader.read_u32()? as i64;
+                    point_count += count;
+                    reader.seek_relative(count * 24)?;
+                }
+                if point_count == 0 {
+                    continue;
+                }
+                let position = reader.stream_position()?;
+                // The last curve may be followed by the chunk padding
+                if fits && position <= end && (position == end || (index + 1 == curve_ptrs.len() && end - position < 16)) {
+                    reader.raise_version(2, 3, 1, 0, format!("Animation curve at {:#x} has 24 byte points", curve_ptr));
+                }
+                break;
+            }
+            reader.seek(SeekFrom::Start(return_to))?;
+        }
+
+        Ok(())
+    }
+}
+
 impl Serialize for ChunkACRV {
     fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
         where R: Read + Seek,
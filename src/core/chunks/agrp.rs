@@ -1,6 +1,6 @@
 use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList, models::audio_group::AudioGroup};
 use byteorder::WriteBytesExt;
-use std::{collections::HashMap, fmt::Write, fs::File, io::{BufReader, BufWriter, Cursor, Read, Result, Seek}};
+use std::{collections::HashMap, fs::File, io::{BufReader, BufWriter, Cursor, Read, Result, Seek, Write}};
 
 #[derive(Default, Clone)]
 pub struct ChunkAGRP {
@@ -26,8 +26,8 @@ impl Serialize for ChunkAGRP {
                 f.read_to_end(&mut v)?;
                 drop(f);
                 let mut r = Reader::new(Cursor::new(v), Some(filepath));
-                r.deserialize_chunks().unwrap();
-                r.deserialize().unwrap();
+                r.deserialize_chunks()?;
+                r.deserialize()?;
                 chunk.audio_data.insert(i, r);
             }
         }
@@ -0,0 +1,42 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList, models::embedded_audio::EmbeddedAudio};
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkAUDO {
+    pub audio: GMPointerList<EmbeddedAudio>,
+}
+
+impl ChunkAUDO {
+    pub fn get(&self, index: i32) -> Option<&EmbeddedAudio> {
+        usize::try_from(index).ok().and_then(|index| self.audio.values.get(index))
+    }
+}
+
+impl Serialize for ChunkAUDO {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.audio.deserialize(reader, None, None)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        // Every entry but the last one is padded to 4 bytes
+        chunk.audio.serialize(writer, None, Some(Box::new(|writer, index, size| {
+            if index + 1 < size {
+                writer.pad_check_byte(4, 0)?;
+            }
+            Ok(())
+        })))?;
+
+        Ok(())
+    }
+}
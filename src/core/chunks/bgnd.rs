@@ -1,6 +1,6 @@
 use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList, models::background::Background};
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 
 #[derive(Default, Clone)]
 pub struct ChunkBGND {
@@ -17,7 +17,7 @@ impl Serialize for ChunkBGND {
 
         reader.version_info.align_backgrounds_to_8 = reader.version_info.is_version_at_least(2, 3, 0, 0);
         chunk.backgrounds.deserialize(reader, Some(Box::new(|reader: &mut Reader<R>, ptr: u64, _index: usize, _size: usize| {
-            reader.version_info.align_backgrounds_to_8 &= ptr % 8 == 0;
+            reader.version_info.align_backgrounds_to_8 &= ptr.is_multiple_of(8);
 
             Ok(())
         })), None)?;
@@ -25,11 +25,18 @@ impl Serialize for ChunkBGND {
         Ok(chunk)
     }
 
-    fn serialize<W>(_chunk: &Self, _writer: &mut Writer<W>) -> Result<()>
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
         where W: Write + WriteBytesExt + Seek,
     {
-        todo!("Not implemented")
+        let align = writer.version_info.align_backgrounds_to_8;
+        chunk.backgrounds.serialize(writer, Some(Box::new(move |writer: &mut Writer<W>, _index, _count| {
+            if align {
+                writer.pad_check_byte(8, 0)?;
+            }
 
-        //Ok(())
+            Ok(())
+        })), None)?;
+
+        Ok(())
     }
 }
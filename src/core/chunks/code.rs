@@ -0,0 +1,110 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList, models::code_entry::CodeEntry};
+use byteorder::WriteBytesExt;
+use integer_hasher::IntMap;
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkCODE {
+    pub entries: GMPointerList<CodeEntry>,
+}
+
+impl ChunkCODE {
+    // The entry owning the bytecode of `index`, which is itself unless it's a child function
+    pub fn root(&self, index: usize) -> &CodeEntry {
+        let entry = &self.entries[index];
+        match entry.parent {
+            Some(parent) => &self.entries[parent],
+            None => entry,
+        }
+    }
+
+    // Bytecode of the entry, starting from the instruction where it begins
+    pub fn bytecode(&self, index: usize) -> &[u8] {
+        let bytecode = &self.root(index).bytecode;
+        bytecode.get(self.entries[index].offset as usize..).unwrap_or_default()
+    }
+
+    pub fn children(&self, index: usize) -> impl Iterator<Item = (usize, &CodeEntry)> {
+        self.entries.values.iter().enumerate().filter(move |(_, entry)| entry.parent == Some(index))
+    }
+}
+
+impl Serialize for ChunkCODE {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        // Games compiled with YYC keep the chunk but leave it empty
+        if reader.current_chunk.length == 0 {
+            return Ok(chunk);
+        }
+        chunk.entries.deserialize(reader, None, None)?;
+        if reader.version_info.format_id > 14 {
+            // Entries sharing a bytecode address are the child functions of the one starting at offset 0
+            let mut roots: IntMap<u64, usize> = IntMap::default();
+            for (index, entry) in chunk.entries.values.iter().enumerate() {
+                match roots.get(&entry.bytecode_address) {
+                    Some(&root) if chunk.entries[root].offset == 0 || entry.offset != 0 => {}
+                    _ => { roots.insert(entry.bytecode_address, index); }
+                }
+            }
+            for index in 0..chunk.entries.len() {
+                let address = chunk.entries[index].bytecode_address;
+                let root = roots[&address];
+                if root != index {
+                    chunk.entries.values[index].parent = Some(root);
+                    continue;
+                }
+                reader.seek(SeekFrom::Start(address))?;
+                let bytecode = reader.read_vec(chunk.entries[index].length as usize)?;
+                chunk.entries.values[index].bytecode = bytecode;
+            }
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        if writer.version_info.format_id <= 14 {
+            // The bytecode follows the name and length of each entry
+            chunk.entries.serialize(writer, Some(Box::new(|writer: &mut Writer<W>, index, _count| {
+                let offset = writer.stream_position()? + 8;
+                writer.register_bytecode(index, offset);
+                Ok(())
+            })), None)?;
+            return Ok(());
+        }
+
+        // The shared bytecode goes right after the pointer list, followed by the entries themselves
+        let size = chunk.entries.len();
+        writer.write_u32(size as _)?;
+        let offset = writer.stream_position()?;
+        for _ in 0..size {
+            writer.write_u32(0)?;
+        }
+        let mut addresses = vec![0; size];
+        for (index, entry) in chunk.entries.values.iter().enumerate() {
+            if entry.parent.is_none() {
+                addresses[index] = writer.stream_position()?;
+                writer.register_bytecode(index, addresses[index]);
+                writer.write_bytes(&entry.bytecode)?;
+            }
+        }
+        for (index, entry) in chunk.entries.values.iter().enumerate() {
+            let current_offset = writer.stream_position()?;
+            writer.seek(SeekFrom::Start(offset + (index * 4) as u64))?;
+            writer.write_u32(current_offset as _)?;
+            writer.seek(SeekFrom::Start(current_offset))?;
+            let mut entry = entry.clone();
+            entry.bytecode_address = addresses[entry.parent.unwrap_or(index)];
+            CodeEntry::serialize(&entry, writer)?;
+        }
+
+        Ok(())
+    }
+}
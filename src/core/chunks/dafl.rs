@@ -0,0 +1,30 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkDAFL {
+    pub data: Vec<u8>, // Empty in every known data file, kept as is so nothing is lost on write
+}
+
+impl Serialize for ChunkDAFL {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.data = reader.read_vec(reader.current_chunk.length as usize)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_bytes(&chunk.data)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMSimpleList, models::embedded_image::EmbeddedImage};
+use byteorder::WriteBytesExt;
+use std::io::{Error, ErrorKind, Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkEMBI {
+    pub images: GMSimpleList<EmbeddedImage>,
+}
+
+impl Serialize for ChunkEMBI {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        if reader.read_u32()? != 1 {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown EMBI chunk version"));
+        }
+        chunk.images.deserialize(reader, None, None)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_u32(1)?; // Version
+        chunk.images.serialize(writer, None, None)?;
+
+        Ok(())
+    }
+}
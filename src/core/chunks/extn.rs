@@ -1,6 +1,6 @@
 use crate::core::{reader::Reader, serializing::{Serialize, FormatCheck}, writer::Writer, lists::GMPointerList, models::extension::Extension};
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek, SeekFrom}};
+use std::io::{Read, Result, Seek, SeekFrom, Write};
 
 #[derive(Default, Clone)]
 pub struct ChunkEXTN {
@@ -11,6 +11,20 @@ impl FormatCheck for ChunkEXTN {
     fn format_check<R>(reader: &mut Reader<R>) -> Result<()>
         where R: Read + Seek,
     {
+        if reader.version_info.is_version_at_least(2, 3, 0, 0) && !reader.version_info.is_version_at_least(2023, 4, 0, 0) {
+            // The version string pushes the class name back, so the files pointer ends up at 16 instead of 12
+            let return_to = reader.stream_position()?;
+            if reader.read_u32()? > 0 {
+                let first_extn_ptr = reader.read_u32()?;
+                reader.seek(SeekFrom::Start((first_extn_ptr + 12) as _))?;
+                let class_name_ptr = reader.read_u32()?;
+                let files_ptr = reader.read_u32()?;
+                if files_ptr == first_extn_ptr + 24 && class_name_ptr != first_extn_ptr + 20 {
+                    reader.raise_version(2023, 4, 0, 0, "Extensions have a version before the class name");
+                }
+            }
+            reader.seek(SeekFrom::Start(return_to))?;
+        }
         if reader.version_info.is_version_at_least(2, 3, 0, 0) && !reader.version_info.is_version_at_least(2022, 6, 0, 0) {
             let mut definitively_2022_6 = true;
             let return_to = reader.stream_position()?;
@@ -64,7 +78,7 @@ impl FormatCheck for ChunkEXTN {
             }
             reader.seek(SeekFrom::Start(return_to))?;
             if definitively_2022_6 {
-                reader.version_info.set_version(2022, 6, 0, 0);
+                reader.raise_version(2022, 6, 0, 0, "Extension files and options pointers follow the class name");
             }
         }
 
@@ -0,0 +1,44 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use bstr::BString;
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkFEAT {
+    pub features: Vec<BString>, // Feature flags the game was built with, from 2022.8>=
+}
+
+impl ChunkFEAT {
+    pub fn has(&self, feature: &[u8]) -> bool {
+        self.features.iter().any(|value| value.as_slice() == feature)
+    }
+}
+
+impl Serialize for ChunkFEAT {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        reader.pad_check_byte(4, 0)?;
+        for _ in 0..reader.read_u32()? {
+            chunk.features.push(reader.read_pointer_string()?);
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.pad_check_byte(4, 0)?;
+        writer.write_u32(chunk.features.len() as u32)?;
+        for feature in chunk.features.iter() {
+            writer.write_pointer_string(feature)?;
+        }
+
+        Ok(())
+    }
+}
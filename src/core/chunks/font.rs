@@ -1,6 +1,6 @@
 use crate::core::{reader::Reader, serializing::{Serialize, FormatCheck}, writer::Writer, lists::GMPointerList, models::font::Font};
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek, SeekFrom}};
+use std::io::{Read, Result, Seek, SeekFrom, Write};
 
 #[derive(Default, Clone)]
 pub struct ChunkFONT {
@@ -17,7 +17,7 @@ impl FormatCheck for ChunkFONT {
             let font_count = reader.read_u32()?;
             if font_count > 0 {
                 let lower_bound = reader.stream_position()?;
-                let upper_bound = reader.current_chunk.end_offset - 512;
+                let upper_bound = reader.current_chunk.end_offset.saturating_sub(512);
                 let first_font_ptr = reader.read_i32()?;
                 let end_ptr = if font_count >= 2 {
                     reader.read_u32()?
@@ -30,14 +30,14 @@ impl FormatCheck for ChunkFONT {
                 if glyph_count > 0 {
                     let glyph_ptr_offset = reader.stream_position()?;
                     if glyph_count >= 2 {
-                        let first_glyph = reader.read_u32()? + (7 * 2);
+                        let first_glyph = reader.read_u32()? as u64 + (7 * 2);
                         let second_glyph = reader.read_u32()?;
-                        if (first_glyph as u64) < lower_bound || (first_glyph as u64) > upper_bound || (second_glyph as u64) < lower_bound || (second_glyph as u64) > upper_bound {
+                        if first_glyph < lower_bound || first_glyph > upper_bound || (second_glyph as u64) < lower_bound || (second_glyph as u64) > upper_bound {
                             invalid_format = true;
                         }
                         if !invalid_format {
                             reader.seek(SeekFrom::Start(first_glyph as _))?;
-                            let kerning_length = reader.read_u16()? * 4;
+                            let kerning_length = reader.read_u16()? as i64 * 4;
                             reader.seek_relative(kerning_length as _)?;
                             if reader.stream_position()? != second_glyph as _ {
                                 invalid_format = true;
@@ -51,8 +51,8 @@ impl FormatCheck for ChunkFONT {
                             invalid_format = true;
                         }
                         if !invalid_format {
-                            reader.seek(SeekFrom::Start(last_glyph as _))?;
-                            let kerning_length = reader.read_u16()? * 4;
+                            reader.seek(SeekFrom::Start(last_glyph as u64 + (7 * 2)))?;
+                            let kerning_length = reader.read_u16()? as i64 * 4;
                             reader.seek_relative(kerning_length as _)?;
                             if font_count == 1 && reader.version_info.align_chunks_to_16 {
                                 reader.pad(16)?;
@@ -61,7 +61,7 @@ impl FormatCheck for ChunkFONT {
                     }
                 }
                 if invalid_format || reader.stream_position()? != end_ptr as _ {
-                    reader.version_info.set_version(2022, 2, 0, 0);
+                    reader.raise_version(2022, 2, 0, 0, "Glyphs don't line up with the pre-2022.2 font layout");
                 }
             }
             reader.seek(SeekFrom::Start(return_to))?;
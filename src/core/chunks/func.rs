@@ -0,0 +1,90 @@
+use crate::core::{reader::Reader, serializing::{Serialize, FormatCheck}, writer::Writer, lists::GMSimpleList, models::function::{Function, CodeLocals}, chunks::code::ChunkCODE, references::{self, Reference}};
+use byteorder::WriteBytesExt;
+use std::{any::type_name, io::{Read, Result, Seek, SeekFrom, Write}};
+
+#[derive(Default, Clone)]
+pub struct ChunkFUNC {
+    pub functions: GMSimpleList<Function>,
+    pub code_locals: GMSimpleList<CodeLocals>, // From bytecode 15>=, no longer present from 2024.8>=
+}
+
+impl ChunkFUNC {
+    // Every instruction calling each function, in the same order as `functions`
+    pub fn references(&self, code: &ChunkCODE) -> Result<Vec<Vec<Reference>>> {
+        references::resolve_chains(code, self.functions.values.iter().map(|function| (function.first_occurrence, function.occurrences)))
+    }
+}
+
+impl FormatCheck for ChunkFUNC {
+    fn format_check<R>(reader: &mut Reader<R>) -> Result<()>
+        where R: Read + Seek,
+    {
+        if reader.version_info.format_id >= 15 && reader.current_chunk.length > 0 && !reader.version_info.is_version_at_least(2024, 8, 0, 0) {
+            let return_to = reader.stream_position()?;
+            let code_entry_count = match reader.chunk_data.get(b"CODE".as_slice()).cloned() {
+                Some(code) if code.length > 0 => {
+                    reader.seek(SeekFrom::Start(code.start_offset))?;
+                    reader.read_u32()?
+                }
+                _ => 0,
+            };
+            reader.seek(SeekFrom::Start(return_to))?;
+            let function_count = reader.read_u32()?;
+            reader.seek_relative(function_count as i64 * 12)?;
+            // Older versions list the locals of every code entry after the functions
+            if reader.stream_position()? + 4 > reader.current_chunk.end_offset || (reader.read_u32()? == 0 && code_entry_count > 0) {
+                reader.raise_version(2024, 8, 0, 0, "Code locals are missing after the functions");
+            }
+            reader.seek(SeekFrom::Start(return_to))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for ChunkFUNC {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        // Games compiled with YYC keep the chunk but leave it empty
+        if reader.current_chunk.length == 0 {
+            return Ok(chunk);
+        }
+        if reader.version_info.format_id <= 14 {
+            // There's no count, entries simply fill the whole chunk
+            while reader.stream_position()? + 12 <= reader.current_chunk.end_offset {
+                let (index, offset) = (chunk.functions.len(), reader.stream_position()?);
+                let function = Function::deserialize(reader).map_err(|e| reader.locate_error(e, type_name::<Function>(), Some(index), offset))?;
+                chunk.functions.push(function);
+            }
+        } else {
+            chunk.functions.deserialize(reader, None, None)?;
+            if reader.stream_position()? < reader.current_chunk.end_offset {
+                chunk.code_locals.deserialize(reader, None, None)?;
+            }
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        if writer.version_info.format_id <= 14 {
+            for function in chunk.functions.values.iter() {
+                Function::serialize(function, writer)?;
+            }
+        } else {
+            chunk.functions.serialize(writer, None, None)?;
+            if !writer.version_info.is_version_at_least(2024, 8, 0, 0) {
+                chunk.code_locals.serialize(writer, None, None)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 use bitflags::bitflags;
 use bstr::BString;
 use byteorder::WriteBytesExt;
@@ -179,6 +179,7 @@ impl Serialize for ChunkGEN8 {
         chunk.disable_debug = reader.read_bool()?;
         chunk.format_id = reader.read_i8()?;
         reader.version_info.format_id = chunk.format_id;
+        reader.version_info.room_object_pre_create = chunk.format_id >= 16;
         chunk.unknown = reader.read_i16()?;
         chunk.filename = reader.read_pointer_string()?;
         chunk.config = reader.read_pointer_string()?;
@@ -1,5 +1,5 @@
 use self::{
-    dummy::DummyChunk,
+    raw::RawChunk,
     gen8::ChunkGEN8,
     optn::ChunkOPTN,
     lang::ChunkLANG,
@@ -16,9 +16,11 @@ use self::{
     tmln::ChunkTMLN,
     objt::ChunkOBJT,
     feds::ChunkFEDS, acrv::ChunkACRV, seqn::ChunkSEQN,
+    room::ChunkROOM, tpag::ChunkTPAG, txtr::ChunkTXTR, strg::ChunkSTRG, code::ChunkCODE,
+    vari::ChunkVARI, func::ChunkFUNC, audo::ChunkAUDO, tgin::ChunkTGIN, embi::ChunkEMBI, dafl::ChunkDAFL, feat::ChunkFEAT, tags::ChunkTAGS, psem::ChunkPSEM, psys::ChunkPSYS,
 };
 
-pub mod dummy;
+pub mod raw;
 
 pub mod gen8;
 pub mod optn;
@@ -38,11 +40,26 @@ pub mod objt;
 pub mod feds;
 pub mod acrv;
 pub mod seqn;
+pub mod room;
+pub mod tpag;
+pub mod txtr;
+pub mod strg;
+pub mod code;
+pub mod vari;
+pub mod func;
+pub mod audo;
+pub mod tgin;
+pub mod embi;
+pub mod dafl;
+pub mod feat;
+pub mod tags;
+pub mod psem;
+pub mod psys;
 
 #[derive(Clone)]
 #[allow(clippy::large_enum_variant)]
 pub enum ChunkOutput {
-    DummyChunk(DummyChunk),
+    RawChunk(RawChunk),
 
     ChunkGen8(ChunkGEN8),
     ChunkOptn(ChunkOPTN),
@@ -62,11 +79,26 @@ pub enum ChunkOutput {
     ChunkFeds(ChunkFEDS),
     ChunkAcrv(ChunkACRV),
     ChunkSeqn(ChunkSEQN),
+    ChunkRoom(ChunkROOM),
+    ChunkTpag(ChunkTPAG),
+    ChunkTxtr(ChunkTXTR),
+    ChunkStrg(ChunkSTRG),
+    ChunkCode(ChunkCODE),
+    ChunkVari(ChunkVARI),
+    ChunkFunc(ChunkFUNC),
+    ChunkAudo(ChunkAUDO),
+    ChunkTgin(ChunkTGIN),
+    ChunkEmbi(ChunkEMBI),
+    ChunkDafl(ChunkDAFL),
+    ChunkFeat(ChunkFEAT),
+    ChunkTags(ChunkTAGS),
+    ChunkPsem(ChunkPSEM),
+    ChunkPsys(ChunkPSYS),
 }
 
-impl From<DummyChunk> for ChunkOutput {
-    fn from(value: DummyChunk) -> Self {
-        Self::DummyChunk(value)
+impl From<RawChunk> for ChunkOutput {
+    fn from(value: RawChunk) -> Self {
+        Self::RawChunk(value)
     }
 }
 
@@ -177,3 +209,93 @@ impl From<ChunkSEQN> for ChunkOutput {
         Self::ChunkSeqn(value)
     }
 }
+
+impl From<ChunkROOM> for ChunkOutput {
+    fn from(value: ChunkROOM) -> Self {
+        Self::ChunkRoom(value)
+    }
+}
+
+impl From<ChunkTPAG> for ChunkOutput {
+    fn from(value: ChunkTPAG) -> Self {
+        Self::ChunkTpag(value)
+    }
+}
+
+impl From<ChunkTXTR> for ChunkOutput {
+    fn from(value: ChunkTXTR) -> Self {
+        Self::ChunkTxtr(value)
+    }
+}
+
+impl From<ChunkSTRG> for ChunkOutput {
+    fn from(value: ChunkSTRG) -> Self {
+        Self::ChunkStrg(value)
+    }
+}
+
+impl From<ChunkCODE> for ChunkOutput {
+    fn from(value: ChunkCODE) -> Self {
+        Self::ChunkCode(value)
+    }
+}
+
+impl From<ChunkVARI> for ChunkOutput {
+    fn from(value: ChunkVARI) -> Self {
+        Self::ChunkVari(value)
+    }
+}
+
+impl From<ChunkFUNC> for ChunkOutput {
+    fn from(value: ChunkFUNC) -> Self {
+        Self::ChunkFunc(value)
+    }
+}
+
+impl From<ChunkAUDO> for ChunkOutput {
+    fn from(value: ChunkAUDO) -> Self {
+        Self::ChunkAudo(value)
+    }
+}
+
+impl From<ChunkTGIN> for ChunkOutput {
+    fn from(value: ChunkTGIN) -> Self {
+        Self::ChunkTgin(value)
+    }
+}
+
+impl From<ChunkEMBI> for ChunkOutput {
+    fn from(value: ChunkEMBI) -> Self {
+        Self::ChunkEmbi(value)
+    }
+}
+
+impl From<ChunkDAFL> for ChunkOutput {
+    fn from(value: ChunkDAFL) -> Self {
+        Self::ChunkDafl(value)
+    }
+}
+
+impl From<ChunkFEAT> for ChunkOutput {
+    fn from(value: ChunkFEAT) -> Self {
+        Self::ChunkFeat(value)
+    }
+}
+
+impl From<ChunkTAGS> for ChunkOutput {
+    fn from(value: ChunkTAGS) -> Self {
+        Self::ChunkTags(value)
+    }
+}
+
+impl From<ChunkPSEM> for ChunkOutput {
+    fn from(value: ChunkPSEM) -> Self {
+        Self::ChunkPsem(value)
+    }
+}
+
+impl From<ChunkPSYS> for ChunkOutput {
+    fn from(value: ChunkPSYS) -> Self {
+        Self::ChunkPsys(value)
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 use bitflags::bitflags;
 use byteorder::WriteBytesExt;
 use crate::core::{lists::GMSimpleList, reader::Reader, serializing::Serialize, writer::Writer, models::option::Constant};
@@ -92,7 +92,7 @@ impl Serialize for ChunkOPTN {
 
         if reader.version_info.option_bit_flag {
             chunk.unknown = reader.read_u64()?;
-            chunk.options = OptionsFlags::from_bits_truncate(
+            chunk.options = OptionsFlags::from_bits_retain(
                 reader.read_u64()?,
             );
             chunk.scale = reader.read_i32()?;
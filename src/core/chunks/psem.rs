@@ -0,0 +1,75 @@
+use crate::core::{reader::Reader, serializing::{Serialize, FormatCheck}, writer::Writer, lists::GMPointerList, models::particle_system::ParticleEmitter};
+use byteorder::WriteBytesExt;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkPSEM {
+    pub emitters: GMPointerList<ParticleEmitter>,
+}
+
+impl FormatCheck for ChunkPSEM {
+    fn format_check<R>(reader: &mut Reader<R>) -> Result<()>
+        where R: Read + Seek,
+    {
+        if reader.version_info.is_version_at_least(2023, 8, 0, 0) {
+            return Ok(());
+        }
+        let return_to = reader.stream_position()?;
+        reader.pad(4)?;
+        reader.seek_relative(4)?; // Version
+        let count = reader.read_u32()?;
+        if count > 0 {
+            // Emitters grew with almost every release, so the distance between two of them tells the version apart
+            let first_ptr = reader.read_u32()? as u64;
+            let entry_size = if count >= 2 {
+                (reader.read_u32()? as u64).saturating_sub(first_ptr)
+            } else {
+                // A lone emitter runs to the end of the chunk, which may be padded
+                let available = reader.current_chunk.end_offset.saturating_sub(first_ptr);
+                [ParticleEmitter::SIZE_2023_8, ParticleEmitter::SIZE_2023_6, ParticleEmitter::SIZE_2023_4, ParticleEmitter::SIZE_2023_2].into_iter()
+                    .find(|size| *size <= available)
+                    .unwrap_or(available)
+            };
+            match entry_size {
+                ParticleEmitter::SIZE_2023_2 => {}
+                ParticleEmitter::SIZE_2023_4 => reader.raise_version(2023, 4, 0, 0, format!("Particle emitters are {:#x} bytes", entry_size)),
+                ParticleEmitter::SIZE_2023_6 => reader.raise_version(2023, 6, 0, 0, format!("Particle emitters are {:#x} bytes", entry_size)),
+                ParticleEmitter::SIZE_2023_8 => reader.raise_version(2023, 8, 0, 0, format!("Particle emitters are {:#x} bytes", entry_size)),
+                _ => {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("Unknown particle emitter size {:#x}", entry_size)));
+                }
+            }
+        }
+        reader.seek(SeekFrom::Start(return_to))?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for ChunkPSEM {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        reader.pad(4)?;
+        if reader.read_u32()? != 1 {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown PSEM chunk version"));
+        }
+        chunk.emitters.deserialize(reader, None, None)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.pad_check_byte(4, 0)?;
+        writer.write_u32(1)?; // Version
+        chunk.emitters.serialize(writer, None, None)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList, models::particle_system::ParticleSystem};
+use byteorder::WriteBytesExt;
+use std::io::{Error, ErrorKind, Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkPSYS {
+    pub systems: GMPointerList<ParticleSystem>,
+}
+
+impl Serialize for ChunkPSYS {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        reader.pad(4)?;
+        if reader.read_u32()? != 1 {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown PSYS chunk version"));
+        }
+        chunk.systems.deserialize(reader, None, None)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.pad_check_byte(4, 0)?;
+        writer.write_u32(1)?; // Version
+        chunk.systems.serialize(writer, None, None)?;
+
+        Ok(())
+    }
+}
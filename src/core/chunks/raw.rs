@@ -0,0 +1,32 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+// A chunk kept as the bytes it was read as, for chunks there's no model for yet
+// Pointers inside it are absolute, so they only hold up while the chunks before it keep their size
+#[derive(Default, Clone)]
+pub struct RawChunk {
+    pub data: Vec<u8>, // Includes the padding up to the next chunk, so it's written back with the same alignment
+}
+
+impl Serialize for RawChunk {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.data = reader.read_vec(reader.current_chunk.length as usize)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_bytes(&chunk.data)?;
+
+        Ok(())
+    }
+}
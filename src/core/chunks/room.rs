@@ -0,0 +1,121 @@
+use crate::core::{reader::Reader, serializing::{Serialize, FormatCheck}, writer::Writer, lists::GMPointerList, models::room::{Room, RoomEntryFlags, LayerType}};
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkROOM {
+    pub rooms: GMPointerList<Room>,
+}
+
+impl FormatCheck for ChunkROOM {
+    fn format_check<R>(reader: &mut Reader<R>) -> Result<()>
+        where R: Read + Seek,
+    {
+        if reader.version_info.major < 2 {
+            return Ok(());
+        }
+        let return_to = reader.stream_position()?;
+        let mut room_ptrs = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            room_ptrs.push(reader.read_u32()?);
+        }
+        if !reader.version_info.is_version_at_least(2, 2, 2, 302) {
+            // Game objects without image speed and index are 36 bytes (plus 4 for Pre-Create code)
+            let legacy_size = if reader.version_info.room_object_pre_create { 40 } else { 36 };
+            for room_ptr in room_ptrs.iter() {
+                reader.seek(SeekFrom::Start(*room_ptr as u64 + (12 * 4)))?; // Game objects pointer
+                let game_objects_ptr = reader.read_u32()?;
+                reader.seek(SeekFrom::Start(game_objects_ptr as _))?;
+                if reader.read_u32()? >= 2 {
+                    let first_ptr = reader.read_u32()?;
+                    let second_ptr = reader.read_u32()?;
+                    if second_ptr.wrapping_sub(first_ptr) == legacy_size + 8 {
+                        reader.raise_version(2, 2, 2, 302, "Game objects are spaced for image speed and index");
+                    }
+                    break;
+                }
+            }
+        }
+        if !reader.version_info.is_version_at_least(2, 3, 0, 0) {
+            for room_ptr in room_ptrs.iter() {
+                reader.seek(SeekFrom::Start(*room_ptr as u64 + (9 * 4)))?; // Flags
+                if RoomEntryFlags::from_bits_retain(reader.read_u32()?).contains(RoomEntryFlags::IsGMS2_3) {
+                    reader.raise_version(2, 3, 0, 0, format!("Room at {:#x} is flagged as GMS2.3", room_ptr));
+                    break;
+                }
+            }
+        }
+        if reader.version_info.is_version_at_least(2, 3, 0, 0) && !reader.version_info.is_version_at_least(2024, 2, 0, 0) {
+            // Tile layers shrink once their tiles are run-length compressed, which shows against the layer after them
+            'rooms: for room_ptr in room_ptrs.iter() {
+                reader.seek(SeekFrom::Start(*room_ptr as u64 + (22 * 4)))?; // Layers pointer
+                let layers_ptr = reader.read_u32()?;
+                reader.seek(SeekFrom::Start(layers_ptr as _))?;
+                let mut layer_ptrs = Vec::new();
+                for _ in 0..reader.read_u32()? {
+                    layer_ptrs.push(reader.read_u32()? as u64);
+                }
+                for layer in layer_ptrs.windows(2) {
+                    reader.seek(SeekFrom::Start(layer[0] + 8))?;
+                    if LayerType::from_bits_retain(reader.read_i32()?) != LayerType::Tiles {
+                        continue;
+                    }
+                    let mut data_ptr = layer[0] + (9 * 4);
+                    if reader.version_info.is_version_at_least(2022, 1, 0, 0) {
+                        reader.seek(SeekFrom::Start(data_ptr + 8))?;
+                        data_ptr += 12 + reader.read_u32()? as u64 * 12; // Effect, plus its properties
+                    }
+                    reader.seek(SeekFrom::Start(data_ptr + 4))?;
+                    let tile_count = reader.read_u32()? as u64 * reader.read_u32()? as u64;
+                    if tile_count > 0 && layer[1] < data_ptr + 12 + (tile_count * 4) {
+                        reader.raise_version(2024, 2, 0, 0, format!("Tile layer at {:#x} is too short for {} uncompressed tiles", layer[0], tile_count));
+                        break 'rooms;
+                    }
+                }
+            }
+        }
+        if !reader.version_info.is_version_at_least(2024, 13, 0, 0) {
+            'rooms: for room_ptr in room_ptrs.iter() {
+                reader.seek(SeekFrom::Start(*room_ptr as u64 + (22 * 4)))?; // Layers pointer
+                let layers_ptr = reader.read_u32()?;
+                reader.seek(SeekFrom::Start(layers_ptr as _))?;
+                let mut layer_ptrs = Vec::new();
+                for _ in 0..reader.read_u32()? {
+                    layer_ptrs.push(reader.read_u32()? as u64);
+                }
+                for layer_ptr in layer_ptrs {
+                    reader.seek(SeekFrom::Start(layer_ptr + 8))?;
+                    if LayerType::from_bits_retain(reader.read_i32()?) == LayerType::Path2 {
+                        reader.raise_version(2024, 13, 0, 0, format!("Layer at {:#x} is a new style path layer", layer_ptr));
+                        break 'rooms;
+                    }
+                }
+            }
+        }
+        reader.seek(SeekFrom::Start(return_to))?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for ChunkROOM {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.rooms.deserialize(reader, None, None)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        chunk.rooms.serialize(writer, None, None)?;
+
+        Ok(())
+    }
+}
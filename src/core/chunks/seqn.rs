@@ -1,6 +1,6 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList, models::sequence::Sequence};
+use crate::core::{reader::Reader, serializing::{Serialize, FormatCheck}, writer::Writer, lists::GMPointerList, models::sequence::Sequence};
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Seek, Result}};
+use std::io::{Read, Seek, SeekFrom, Result, Write};
 
 #[derive(Default, Clone)]
 pub struct ChunkSEQN {
@@ -8,6 +8,32 @@ pub struct ChunkSEQN {
     pub version: i32,
 }
 
+impl FormatCheck for ChunkSEQN {
+    fn format_check<R>(reader: &mut Reader<R>) -> Result<()>
+        where R: Read + Seek,
+    {
+        // Tracks nest into each other, so their model names are looked up in the string table instead
+        let Some(strg) = reader.chunk_data.get(b"STRG".as_slice()).cloned() else {
+            return Ok(());
+        };
+        let return_to = reader.stream_position()?;
+        reader.seek(SeekFrom::Start(strg.start_offset))?;
+        let mut strings = vec![0; strg.length as usize];
+        reader.read_exact(&mut strings)?;
+        for (model_name, (major, minor, release, build)) in [("GMTextTrack", (2022, 2, 0, 0)), ("GMParticleTrack", (2023, 2, 0, 0))] {
+            let mut string = (model_name.len() as u32).to_le_bytes().to_vec();
+            string.extend_from_slice(model_name.as_bytes());
+            string.push(0);
+            if strings.windows(string.len()).any(|window| window == string) {
+                reader.raise_version(major, minor, release, build, format!("The string table holds the {} track model", model_name));
+            }
+        }
+        reader.seek(SeekFrom::Start(return_to))?;
+
+        Ok(())
+    }
+}
+
 impl Serialize for ChunkSEQN {
     fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
         where R: Read + Seek,
@@ -1,27 +1,30 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList, models::shader::Shader};
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 
 #[derive(Default, Clone)]
 pub struct ChunkSHDR {
-    // Nothing.
+    pub shaders: GMPointerList<Shader>,
 }
 
 impl Serialize for ChunkSHDR {
-    fn deserialize<R>(_reader: &mut Reader<R>) -> Result<Self>
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
         where R: Read + Seek,
     {
-        /*let chunk = Self {
-        };*/
+        let mut chunk = Self {
+            ..Default::default()
+        };
 
-        //todo!("Not implemented");
+        chunk.shaders.deserialize(reader, None, None)?;
 
-        Ok(Self {})
+        Ok(chunk)
     }
 
-    fn serialize<W>(_chunk: &Self, _writer: &mut Writer<W>) -> Result<()>
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
         where W: Write + WriteBytesExt + Seek,
     {
-        todo!("Not implemented");
+        chunk.shaders.serialize(writer, None, None)?;
+
+        Ok(())
     }
 }
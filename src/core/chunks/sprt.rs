@@ -1,12 +1,90 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList, models::sprite::Sprite};
+use crate::core::{reader::Reader, serializing::{Serialize, FormatCheck}, writer::Writer, lists::GMPointerList, models::sprite::{Sprite, SpriteType}};
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::{any::type_name, io::{Read, Result, Seek, SeekFrom, Write}};
 
 #[derive(Default, Clone)]
 pub struct ChunkSPRT {
     pub sprites: GMPointerList<Sprite>,
 }
 
+impl FormatCheck for ChunkSPRT {
+    fn format_check<R>(reader: &mut Reader<R>) -> Result<()>
+        where R: Read + Seek,
+    {
+        if reader.version_info.major < 2 {
+            return Ok(());
+        }
+        let return_to = reader.stream_position()?;
+        let mut sprite_ptrs = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            sprite_ptrs.push(reader.read_u32()? as u64);
+        }
+        if !reader.version_info.is_version_at_least(2, 3, 2, 0) {
+            for sprite_ptr in sprite_ptrs.iter() {
+                // Special sprites put -1 right after the origin, followed by their version
+                reader.seek(SeekFrom::Start(sprite_ptr + (14 * 4)))?;
+                if reader.read_i32()? != -1 {
+                    continue;
+                }
+                match reader.read_i32()? {
+                    2 => reader.raise_version(2, 3, 0, 0, format!("Sprite at {:#x} points at a sequence", sprite_ptr)),
+                    3.. => reader.raise_version(2, 3, 2, 0, format!("Sprite at {:#x} points at a nine slice", sprite_ptr)),
+                    _ => {}
+                }
+            }
+        }
+        if !reader.version_info.is_version_at_least(2024, 6, 0, 0) {
+            // Collision masks shrink to the bounding box, which shows against whatever follows the sprite's body
+            let mut starts = sprite_ptrs.clone();
+            starts.sort();
+            for sprite_ptr in sprite_ptrs.iter() {
+                reader.seek(SeekFrom::Start(sprite_ptr + 4))?;
+                let (width, height) = (reader.read_i32()? as i64, reader.read_i32()? as i64);
+                let (left, right, bottom, top) = (reader.read_i32()? as i64, reader.read_i32()? as i64, reader.read_i32()? as i64, reader.read_i32()? as i64);
+                reader.seek(SeekFrom::Start(sprite_ptr + (14 * 4)))?;
+                if reader.read_i32()? != -1 {
+                    continue;
+                }
+                let special_version = reader.read_i32()?;
+                if SpriteType::from_bits_retain(reader.read_i32()?) != SpriteType::Normal {
+                    continue;
+                }
+                reader.seek_relative(8)?; // Playback speed and its type
+                let mut next = starts.iter().copied().find(|start| start > sprite_ptr);
+                for _ in 2..=special_version.min(3) {
+                    // Sequence and nine slice
+                    let ptr = reader.read_u32()? as u64;
+                    if ptr != 0 {
+                        next = Some(next.map_or(ptr, |next| next.min(ptr)));
+                    }
+                }
+                let Some(next) = next else {
+                    continue;
+                };
+                let texture_count = reader.read_u32()?;
+                reader.seek_relative(texture_count as i64 * 4)?;
+                let mask_count = reader.read_u32()? as u64;
+                let masks_start = reader.stream_position()?;
+                let masks_end = |width: i64, height: i64| {
+                    let size = (width.max(0) as u64).div_ceil(8).saturating_mul(height.max(0) as u64);
+                    masks_start.saturating_add(mask_count.saturating_mul(size)).next_multiple_of(4)
+                };
+                let (full, bounding_box) = (masks_end(width, height), masks_end(right - left + 1, bottom - top + 1));
+                if mask_count == 0 || full == bounding_box {
+                    continue;
+                }
+                if bounding_box == next && full != next {
+                    reader.raise_version(2024, 6, 0, 0, format!("Collision masks of sprite at {:#x} only cover its bounding box", sprite_ptr));
+                }
+                break;
+            }
+        }
+        reader.seek(SeekFrom::Start(return_to))?;
+
+        Ok(())
+    }
+}
+
 impl Serialize for ChunkSPRT {
     fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
         where R: Read + Seek,
@@ -15,7 +93,20 @@ impl Serialize for ChunkSPRT {
             ..Default::default()
         };
 
-        chunk.sprites.deserialize(reader, None, None)?;
+        // Sprites are read by hand since each one needs to know where the next one in the file starts
+        let mut ptrs = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            ptrs.push(reader.read_u32()? as u64);
+        }
+        // Sorted, since the order of the pointers needn't be the order of the sprites in the file
+        let mut starts = ptrs.clone();
+        starts.sort();
+        for (index, ptr) in ptrs.iter().enumerate() {
+            let end = starts.iter().copied().find(|start| start > ptr).unwrap_or(reader.current_chunk.end_offset);
+            reader.seek(SeekFrom::Start(*ptr))?;
+            let sprite = Sprite::deserialize_until(reader, end).map_err(|e| reader.locate_error(e, type_name::<Sprite>(), Some(index), *ptr))?;
+            chunk.sprites.push(sprite);
+        }
 
         Ok(chunk)
     }
@@ -0,0 +1,114 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use bstr::BString;
+use byteorder::WriteBytesExt;
+use integer_hasher::IntMap;
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkSTRG {
+    pub strings: Vec<BString>,
+    // Absolute offset of each string's characters in the file the table was read from, which is what string pointers reference.
+    // Strings pushed afterwards have none, as they only get an offset once the table is written out
+    pub offsets: Vec<Option<u64>>,
+    pub index_by_offset: IntMap<u64, usize>,
+    pub alignment: Option<u64>, // Alignment the chunk was padded to after the last string, if it was
+}
+
+impl ChunkSTRG {
+    pub fn index_of(&self, offset: u64) -> Option<usize> {
+        self.index_by_offset.get(&offset).copied()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&BString> {
+        self.strings.get(index)
+    }
+
+    pub fn get_by_offset(&self, offset: u64) -> Option<&BString> {
+        self.index_of(offset).and_then(|index| self.strings.get(index))
+    }
+
+    pub fn find(&self, string: &[u8]) -> Option<usize> {
+        self.strings.iter().position(|entry| entry.as_slice() == string)
+    }
+
+    // Index of `string`, adding it to the end of the table when missing
+    pub fn find_or_push(&mut self, string: &[u8]) -> usize {
+        match self.find(string) {
+            Some(index) => index,
+            None => self.push(BString::from(string)),
+        }
+    }
+
+    pub fn push(&mut self, string: BString) -> usize {
+        self.strings.push(string);
+        self.offsets.push(None);
+        self.strings.len() - 1
+    }
+}
+
+impl Serialize for ChunkSTRG {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        let mut ptrs = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            ptrs.push(reader.read_u32()?);
+        }
+        reader.version_info.align_strings_to_4 = true;
+        let mut strings_end = reader.stream_position()?;
+        for (index, ptr) in ptrs.into_iter().enumerate() {
+            reader.version_info.align_strings_to_4 &= ptr.is_multiple_of(4);
+            reader.seek(SeekFrom::Start(ptr as _))?;
+            let length = reader.read_u32()?;
+            let offset = reader.stream_position()?;
+            let string = reader.read_vec(length as usize)?;
+            chunk.strings.push(BString::new(string));
+            chunk.offsets.push(Some(offset));
+            chunk.index_by_offset.insert(offset, index);
+            strings_end = strings_end.max(offset + length as u64 + 1);
+        }
+
+        // Zeroes after the last string are padding up to the smallest alignment that explains them
+        let end = reader.current_chunk.end_offset;
+        if end > strings_end {
+            reader.seek(SeekFrom::Start(strings_end))?;
+            if reader.read_vec((end - strings_end) as usize)?.iter().all(|byte| *byte == 0) {
+                chunk.alignment = (2..=12).map(|shift| 1u64 << shift).find(|alignment| strings_end.next_multiple_of(*alignment) == end);
+            }
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_u32(chunk.strings.len() as _)?;
+        let offset = writer.stream_position()?;
+        for _ in 0..chunk.strings.len() {
+            writer.write_u32(0)?;
+        }
+        for (index, string) in chunk.strings.iter().enumerate() {
+            if writer.version_info.align_strings_to_4 {
+                writer.pad_check_byte(4, 0)?;
+            }
+            let current_offset = writer.stream_position()?;
+            writer.seek(SeekFrom::Start(offset + (index * 4) as u64))?;
+            writer.write_u32(current_offset as _)?;
+            writer.seek(SeekFrom::Start(current_offset))?;
+            writer.write_u32(string.len() as _)?;
+            writer.string_offsets.entry(string.clone()).or_insert(current_offset + 4);
+            writer.write_bytes(string)?;
+            writer.write_u8(0)?;
+        }
+        if let Some(alignment) = chunk.alignment {
+            writer.pad_check_byte(alignment as _, 0)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList, models::asset_tags::AssetTags};
+use bstr::BString;
+use byteorder::WriteBytesExt;
+use std::io::{Error, ErrorKind, Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkTAGS {
+    pub tags: Vec<BString>, // Every tag used in the project
+    pub asset_tags: GMPointerList<AssetTags>,
+}
+
+impl ChunkTAGS {
+    pub fn tags_of(&self, asset_id: i32) -> &[BString] {
+        self.asset_tags.values.iter()
+            .find(|asset| asset.asset_id == asset_id)
+            .map(|asset| asset.tags.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn assets_with(&self, tag: &[u8]) -> Vec<i32> {
+        self.asset_tags.values.iter()
+            .filter(|asset| asset.tags.iter().any(|value| value.as_slice() == tag))
+            .map(|asset| asset.asset_id)
+            .collect()
+    }
+}
+
+impl Serialize for ChunkTAGS {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        reader.pad_check_byte(4, 0)?;
+        if reader.read_u32()? != 1 {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown TAGS chunk version"));
+        }
+        for _ in 0..reader.read_u32()? {
+            chunk.tags.push(reader.read_pointer_string()?);
+        }
+        chunk.asset_tags.deserialize(reader, None, None)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.pad_check_byte(4, 0)?;
+        writer.write_u32(1)?; // Version
+        writer.write_u32(chunk.tags.len() as u32)?;
+        for tag in chunk.tags.iter() {
+            writer.write_pointer_string(tag)?;
+        }
+        chunk.asset_tags.serialize(writer, None, None)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use crate::core::{reader::Reader, serializing::{Serialize, FormatCheck}, writer::Writer, lists::GMPointerList, models::texture_group_info::TextureGroupInfo};
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkTGIN {
+    pub version: i32, // Always 1
+    pub groups: GMPointerList<TextureGroupInfo>,
+}
+
+impl ChunkTGIN {
+    // Group owning an embedded texture, by its index in TXTR
+    pub fn group_of_texture(&self, texture: usize) -> Option<&TextureGroupInfo> {
+        self.groups.values.iter().find(|group| group.texture_pages.iter().any(|page| *page as usize == texture))
+    }
+
+    pub fn group_of_sprite(&self, sprite: usize) -> Option<&TextureGroupInfo> {
+        self.groups.values.iter().find(|group| group.sprites.iter().chain(group.spine_sprites.iter()).any(|id| *id as usize == sprite))
+    }
+
+    pub fn group_of_font(&self, font: usize) -> Option<&TextureGroupInfo> {
+        self.groups.values.iter().find(|group| group.fonts.iter().any(|id| *id as usize == font))
+    }
+
+    pub fn group_of_tileset(&self, tileset: usize) -> Option<&TextureGroupInfo> {
+        self.groups.values.iter().find(|group| group.tilesets.iter().any(|id| *id as usize == tileset))
+    }
+}
+
+impl FormatCheck for ChunkTGIN {
+    fn format_check<R>(reader: &mut Reader<R>) -> Result<()>
+        where R: Read + Seek,
+    {
+        if reader.version_info.is_version_at_least(2, 3, 0, 0) && !reader.version_info.is_version_at_least(2022, 9, 0, 0) {
+            let return_to = reader.stream_position()?;
+            reader.seek_relative(4)?; // Version
+            if reader.read_u32()? > 0 {
+                // The texture page list comes right after the entry, unless the directory, extension and load type are in the way
+                let first_group_ptr = reader.read_u32()?;
+                reader.seek(SeekFrom::Start((first_group_ptr + 4) as _))?;
+                if reader.read_u32()? != first_group_ptr + 24 {
+                    reader.raise_version(2022, 9, 0, 0, "Texture groups have a directory, extension and load type");
+                }
+            }
+            reader.seek(SeekFrom::Start(return_to))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for ChunkTGIN {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.version = reader.read_i32()?;
+        chunk.groups.deserialize(reader, None, None)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_i32(chunk.version)?;
+        chunk.groups.serialize(writer, None, None)?;
+
+        Ok(())
+    }
+}
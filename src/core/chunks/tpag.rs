@@ -0,0 +1,54 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList, models::texture_page_item::TexturePageItem, shared::Shared};
+use byteorder::WriteBytesExt;
+use std::{cell::Cell, io::{Read, Result, Seek, Write}, rc::Rc};
+
+#[derive(Clone)]
+pub struct ChunkTPAG {
+    pub texture_page_items: GMPointerList<Shared<TexturePageItem>>, // Shared with the sprites, fonts and backgrounds using them
+    pub align_items_to_4: bool,
+}
+
+impl Default for ChunkTPAG {
+    fn default() -> Self {
+        Self {
+            texture_page_items: GMPointerList::default(),
+            align_items_to_4: true,
+        }
+    }
+}
+
+impl Serialize for ChunkTPAG {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        let aligned = Rc::new(Cell::new(true));
+        let aligned_check = aligned.clone();
+        chunk.texture_page_items.deserialize(reader, Some(Box::new(move |_reader: &mut Reader<R>, ptr: u64, _index: usize, _size: usize| {
+            aligned_check.set(aligned_check.get() && ptr.is_multiple_of(4));
+
+            Ok(())
+        })), None)?;
+        chunk.align_items_to_4 = aligned.get();
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        let align_items_to_4 = chunk.align_items_to_4;
+        chunk.texture_page_items.serialize(writer, Some(Box::new(move |writer: &mut Writer<W>, _index, _count| {
+            if align_items_to_4 {
+                writer.pad(4)?;
+            }
+
+            Ok(())
+        })), None)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use crate::core::{reader::Reader, serializing::{Serialize, FormatCheck}, writer::Writer, lists::GMPointerList, models::embedded_texture::EmbeddedTexture, image::{self, ImageFormat, BZ2_MAGIC}, chunks::tgin::ChunkTGIN};
+use byteorder::WriteBytesExt;
+use std::{fs, io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write}, path::Path};
+
+#[derive(Default, Clone)]
+pub struct ChunkTXTR {
+    pub textures: GMPointerList<EmbeddedTexture>,
+}
+
+impl ChunkTXTR {
+    // Loads the pages stored next to the data file, `directory` being the one holding it
+    pub fn load_external(&mut self, groups: &ChunkTGIN, directory: &Path) -> Result<()> {
+        for (index, texture) in self.textures.values.iter_mut().enumerate() {
+            if !texture.external || !texture.data.is_empty() {
+                continue;
+            }
+            let group = groups.group_of_texture(index)
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("External texture {} does not belong to any texture group", index)))?;
+            let path = directory.join(group.directory.to_string()).join(group.external_file_name(texture.index_in_group));
+            texture.data = fs::read(&path)
+                .map_err(|e| Error::new(e.kind(), format!("Unable to load external texture {} from {}: {}", index, path.display(), e)))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FormatCheck for ChunkTXTR {
+    fn format_check<R>(reader: &mut Reader<R>) -> Result<()>
+        where R: Read + Seek,
+    {
+        if reader.version_info.is_version_at_least(2, 3, 0, 0) {
+            let return_to = reader.stream_position()?;
+            let mut texture_ptrs = Vec::new();
+            for _ in 0..reader.read_u32()? {
+                texture_ptrs.push(reader.read_u32()? as u64);
+            }
+            if !texture_ptrs.is_empty() {
+                // Entries grow from 12 bytes to 16 in 2022.3 (block size) and 28 in 2022.9 (size and index in group)
+                let entry_size = if texture_ptrs.len() >= 2 {
+                    texture_ptrs[1].saturating_sub(texture_ptrs[0])
+                } else {
+                    let mut entry_size = 12;
+                    for candidate in [28, 16] {
+                        reader.seek(SeekFrom::Start((texture_ptrs[0] + candidate - 4) as _))?;
+                        let data_ptr = reader.read_u32()?;
+                        if data_ptr as u64 >= texture_ptrs[0] + candidate && (data_ptr as u64) < reader.current_chunk.end_offset {
+                            entry_size = candidate;
+                            break;
+                        }
+                    }
+                    entry_size
+                };
+                if entry_size >= 16 {
+                    reader.raise_version(2022, 3, 0, 0, format!("Texture entries are {} bytes, with a block size", entry_size));
+                }
+                if entry_size >= 28 {
+                    reader.raise_version(2022, 9, 0, 0, format!("Texture entries are {} bytes, with a size and index in group", entry_size));
+                }
+
+                for texture_ptr in texture_ptrs {
+                    reader.seek(SeekFrom::Start((texture_ptr + entry_size).saturating_sub(4)))?;
+                    let data_ptr = reader.read_u32()?;
+                    if data_ptr == 0 || data_ptr as u64 >= reader.current_chunk.end_offset {
+                        continue;
+                    }
+                    reader.seek(SeekFrom::Start(data_ptr as _))?;
+                    match image::detect_format(&reader.read_bytes::<4>()?) {
+                        ImageFormat::Qoi => {
+                            reader.raise_version(2022, 1, 0, 0, format!("Texture at {:#x} is QOI", data_ptr));
+                        }
+                        ImageFormat::Bz2Qoi => {
+                            reader.raise_version(2022, 1, 0, 0, format!("Texture at {:#x} is BZip2 compressed QOI", data_ptr));
+                            reader.seek_relative(4)?; // Width and height
+                            if reader.read_bytes::<3>()? != BZ2_MAGIC {
+                                reader.raise_version(2022, 5, 0, 0, format!("Texture at {:#x} stores its uncompressed size", data_ptr));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            reader.seek(SeekFrom::Start(return_to))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for ChunkTXTR {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.textures.deserialize(reader, None, None)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        chunk.textures.serialize(writer, None, None)?;
+        for texture in chunk.textures.values.iter() {
+            if !texture.external && !texture.data.is_empty() {
+                writer.pad_check_byte(0x80, 0)?;
+                writer.register_pointer(&texture.data)?;
+                writer.write_bytes(&texture.data)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, models::variable::Variable, chunks::code::ChunkCODE, references::{self, Reference}};
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct ChunkVARI {
+    pub variables: Vec<Variable>,
+    pub instance_var_count: u32, // From bytecode 15>=
+    pub instance_var_count_again: u32, // From bytecode 15>=, usually the same as instance_var_count
+    pub max_local_var_count: u32, // From bytecode 15>=
+}
+
+impl ChunkVARI {
+    // Every instruction referencing each variable, in the same order as `variables`
+    pub fn references(&self, code: &ChunkCODE) -> Result<Vec<Vec<Reference>>> {
+        references::resolve_chains(code, self.variables.iter().map(|variable| (variable.first_occurrence, variable.occurrences)))
+    }
+}
+
+impl Serialize for ChunkVARI {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        // Games compiled with YYC keep the chunk but leave it empty
+        if reader.current_chunk.length == 0 {
+            return Ok(chunk);
+        }
+        let mut entry_size = 12;
+        if reader.version_info.format_id >= 15 {
+            chunk.instance_var_count = reader.read_u32()?;
+            chunk.instance_var_count_again = reader.read_u32()?;
+            chunk.max_local_var_count = reader.read_u32()?;
+            reader.version_info.different_var_counts = chunk.instance_var_count != chunk.instance_var_count_again;
+            entry_size = 20;
+        }
+        // There's no count, entries simply fill the rest of the chunk
+        while reader.stream_position()? + entry_size <= reader.current_chunk.end_offset {
+            chunk.variables.push(Variable::deserialize(reader)?);
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        if writer.version_info.format_id >= 15 {
+            writer.write_u32(chunk.instance_var_count)?;
+            writer.write_u32(chunk.instance_var_count_again)?;
+            writer.write_u32(chunk.max_local_var_count)?;
+        }
+        for variable in chunk.variables.iter() {
+            Variable::serialize(variable, writer)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,380 @@
+use crate::core::disasm::escape_string;
+use bstr::{BString, ByteSlice};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Mul,
+    Div,
+    IntDiv,
+    Mod,
+    Add,
+    Sub,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    And,
+    Or,
+    Xor,
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+    GreaterEqual,
+    Greater,
+    Nullish,
+}
+
+impl BinaryOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::IntDiv => "div",
+            Self::Mod => "%",
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::BitAnd => "&",
+            Self::BitOr => "|",
+            Self::BitXor => "^",
+            Self::Shl => "<<",
+            Self::Shr => ">>",
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::Xor => "^^",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::GreaterEqual => ">=",
+            Self::Greater => ">",
+            Self::Nullish => "??",
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Nullish => 2,
+            Self::Or => 3,
+            Self::Xor => 4,
+            Self::And => 5,
+            Self::Less | Self::LessEqual | Self::Equal | Self::NotEqual | Self::GreaterEqual | Self::Greater => 6,
+            Self::BitOr => 7,
+            Self::BitXor => 8,
+            Self::BitAnd => 9,
+            Self::Shl | Self::Shr => 10,
+            Self::Add | Self::Sub => 11,
+            Self::Mul | Self::Div | Self::IntDiv | Self::Mod => 12,
+        }
+    }
+
+    // Operators that have a compound assignment form
+    fn compound(&self) -> bool {
+        matches!(self, Self::Mul | Self::Div | Self::Mod | Self::Add | Self::Sub | Self::BitAnd | Self::BitOr | Self::BitXor | Self::Nullish)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+    BitNot,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDef {
+    pub name: BString, // Name of the code entry holding the body
+    pub arguments: u16,
+    pub constructor: bool,
+    pub locals: Vec<BString>,
+    pub body: Vec<Stmt>,
+}
+
+impl FunctionDef {
+    // The name as written in GML, without the prefix and the suffix naming its parent entry
+    pub fn short_name(&self) -> String {
+        let name = self.name.to_str_lossy();
+        let name = name.strip_prefix("gml_Script_").unwrap_or(&name);
+        match name.find("_gml_") {
+            Some(index) => name[..index].to_string(),
+            None => name.to_string(),
+        }
+    }
+
+    // Struct literals are compiled into a function assigning each argument to a field
+    pub fn is_struct(&self) -> bool {
+        self.short_name().starts_with("___struct___")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+    String(BString),
+    Variable {
+        instance: Option<Box<Expr>>, // None when the instance is implied, like self or local
+        name: String,
+        indices: Vec<Expr>,
+    },
+    Instance(String), // Named instances, like self, global or an object
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Ternary(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    CallValue(Box<Expr>, Vec<Expr>), // Calling a method stored in a variable
+    FunctionRef(String),
+    Function(Box<FunctionDef>),
+    New(Box<Expr>, Vec<Expr>),
+    Struct(Vec<(String, Expr)>),
+    Array(Vec<Expr>),
+    Raw(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    pub values: Vec<Option<Expr>>, // None is the default case
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Expr(Expr),
+    Assign(Expr, Expr),
+    Return(Expr),
+    Exit,
+    Break,
+    Continue,
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    DoUntil(Vec<Stmt>, Expr),
+    For(Box<Stmt>, Expr, Box<Stmt>, Vec<Stmt>),
+    Repeat(Expr, Vec<Stmt>),
+    Switch(Expr, Vec<Case>),
+    With(Expr, Vec<Stmt>),
+    Raw(String),
+}
+
+fn indent(level: usize) -> String {
+    "    ".repeat(level)
+}
+
+fn format_double(value: f64) -> String {
+    let text = value.to_string();
+    if value.is_finite() && !text.contains(['.', 'e']) {
+        format!("{}.0", text)
+    } else {
+        text
+    }
+}
+
+fn format_list(values: &[Expr], level: usize) -> String {
+    values.iter().map(|value| format_expr(value, level)).collect::<Vec<_>>().join(", ")
+}
+
+fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Ternary(..) => 1,
+        Expr::Binary(op, ..) => op.precedence(),
+        Expr::Unary(..) => 13,
+        Expr::Function(..) => 0,
+        _ => 14,
+    }
+}
+
+fn format_operand(expr: &Expr, parent: u8, level: usize) -> String {
+    if precedence(expr) < parent {
+        format!("({})", format_expr(expr, level))
+    } else {
+        format_expr(expr, level)
+    }
+}
+
+pub fn format_function(function: &FunctionDef, name: Option<&str>, level: usize) -> String {
+    let arguments = (0..function.arguments).map(|index| format!("argument{}", index)).collect::<Vec<_>>().join(", ");
+    let mut output = String::from("function");
+    if let Some(name) = name {
+        output.push(' ');
+        output.push_str(name);
+    }
+    output.push_str(&format!("({})", arguments));
+    if function.constructor {
+        output.push_str(" constructor");
+    }
+    output.push_str(" {\n");
+    output.push_str(&format_body(&function.locals, &function.body, level + 1));
+    output.push_str(&indent(level));
+    output.push('}');
+    output
+}
+
+pub fn format_expr(expr: &Expr, level: usize) -> String {
+    match expr {
+        Expr::Int(value) => value.to_string(),
+        Expr::Double(value) => format_double(*value),
+        Expr::Bool(value) => value.to_string(),
+        Expr::String(value) => escape_string(value.as_bstr()),
+        Expr::Variable { instance, name, indices } => {
+            let mut output = match instance {
+                Some(instance) => format!("{}.{}", format_operand(instance, 14, level), name),
+                None => name.clone(),
+            };
+            for index in indices.iter() {
+                output.push_str(&format!("[{}]", format_expr(index, level)));
+            }
+            output
+        }
+        Expr::Instance(name) => name.clone(),
+        Expr::Binary(op, left, right) => {
+            // Operators are left associative, so only the right side needs parentheses on equal precedence
+            format!("{} {} {}", format_operand(left, op.precedence(), level), op.symbol(), format_operand(right, op.precedence() + 1, level))
+        }
+        Expr::Unary(op, value) => {
+            let symbol = match op {
+                UnaryOp::Neg => "-",
+                UnaryOp::Not => "!",
+                UnaryOp::BitNot => "~",
+            };
+            format!("{}{}", symbol, format_operand(value, 13, level))
+        }
+        Expr::Ternary(condition, then, otherwise) => {
+            format!("{} ? {} : {}", format_operand(condition, 2, level), format_operand(then, 2, level), format_operand(otherwise, 1, level))
+        }
+        Expr::Call(name, arguments) => format!("{}({})", name, format_list(arguments, level)),
+        Expr::CallValue(function, arguments) => format!("{}({})", format_operand(function, 14, level), format_list(arguments, level)),
+        Expr::FunctionRef(name) => name.clone(),
+        Expr::Function(function) => format_function(function, None, level),
+        Expr::New(function, arguments) => format!("new {}({})", format_operand(function, 14, level), format_list(arguments, level)),
+        Expr::Struct(fields) => {
+            if fields.is_empty() {
+                return String::from("{}");
+            }
+            let fields = fields.iter().map(|(name, value)| format!("{}: {}", name, format_expr(value, level))).collect::<Vec<_>>();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        Expr::Array(values) => format!("[{}]", format_list(values, level)),
+        Expr::Raw(text) => text.clone(),
+    }
+}
+
+fn format_assign(target: &Expr, value: &Expr, level: usize) -> String {
+    let target_text = format_expr(target, level);
+    // Named function declarations are stored like any other variable
+    if let Expr::Function(function) = value {
+        if let Expr::Variable { indices, .. } = target {
+            if indices.is_empty() && !function.is_struct() && target_text.rsplit('.').next() == Some(function.short_name().as_str()) {
+                return format_function(function, Some(&target_text), level);
+            }
+        }
+    }
+    if let Expr::Binary(op, left, right) = value {
+        if op.compound() && **left == *target {
+            return match (op, right.as_ref()) {
+                (BinaryOp::Add, Expr::Int(1)) => format!("{}++", target_text),
+                (BinaryOp::Sub, Expr::Int(1)) => format!("{}--", target_text),
+                _ => format!("{} {}= {}", target_text, op.symbol(), format_expr(right, level)),
+            };
+        }
+    }
+    format!("{} = {}", target_text, format_expr(value, level))
+}
+
+fn format_simple(stmt: &Stmt, level: usize) -> String {
+    match stmt {
+        Stmt::Assign(target, value) => format_assign(target, value, level),
+        Stmt::Expr(expr) => format_expr(expr, level),
+        _ => String::new(),
+    }
+}
+
+pub fn format_block(stmts: &[Stmt], level: usize) -> String {
+    let mut output = String::new();
+    for stmt in stmts.iter() {
+        format_stmt(stmt, level, &mut output);
+    }
+    output
+}
+
+// A function body, starting with the declaration of its locals
+pub fn format_body(locals: &[BString], stmts: &[Stmt], level: usize) -> String {
+    let mut output = String::new();
+    let locals = locals.iter().filter(|local| local.as_slice() != b"arguments").map(|local| local.to_string()).collect::<Vec<_>>();
+    if !locals.is_empty() {
+        output.push_str(&format!("{}var {};\n", indent(level), locals.join(", ")));
+    }
+    output.push_str(&format_block(stmts, level));
+    output
+}
+
+fn format_stmt(stmt: &Stmt, level: usize, output: &mut String) {
+    let pad = indent(level);
+    match stmt {
+        Stmt::Expr(_) | Stmt::Assign(..) => {
+            let text = format_simple(stmt, level);
+            // Function declarations are blocks, so they don't take a semicolon
+            let terminator = if text.starts_with("function ") { "" } else { ";" };
+            output.push_str(&format!("{}{}{}\n", pad, text, terminator));
+        }
+        Stmt::Return(value) => output.push_str(&format!("{}return {};\n", pad, format_expr(value, level))),
+        Stmt::Exit => output.push_str(&format!("{}exit;\n", pad)),
+        Stmt::Break => output.push_str(&format!("{}break;\n", pad)),
+        Stmt::Continue => output.push_str(&format!("{}continue;\n", pad)),
+        Stmt::If(condition, then, otherwise) => {
+            output.push_str(&format!("{}if ({}) {{\n", pad, format_expr(condition, level)));
+            output.push_str(&format_block(then, level + 1));
+            let mut otherwise = otherwise;
+            // Chains of else if are nested ifs as far as the bytecode is concerned
+            while let [Stmt::If(condition, then, next)] = otherwise.as_slice() {
+                output.push_str(&format!("{}}} else if ({}) {{\n", pad, format_expr(condition, level)));
+                output.push_str(&format_block(then, level + 1));
+                otherwise = next;
+            }
+            if !otherwise.is_empty() {
+                output.push_str(&format!("{}}} else {{\n", pad));
+                output.push_str(&format_block(otherwise, level + 1));
+            }
+            output.push_str(&format!("{}}}\n", pad));
+        }
+        Stmt::While(condition, body) => {
+            output.push_str(&format!("{}while ({}) {{\n", pad, format_expr(condition, level)));
+            output.push_str(&format_block(body, level + 1));
+            output.push_str(&format!("{}}}\n", pad));
+        }
+        Stmt::DoUntil(body, condition) => {
+            output.push_str(&format!("{}do {{\n", pad));
+            output.push_str(&format_block(body, level + 1));
+            output.push_str(&format!("{}}} until ({});\n", pad, format_expr(condition, level)));
+        }
+        Stmt::For(init, condition, increment, body) => {
+            output.push_str(&format!("{}for ({}; {}; {}) {{\n", pad, format_simple(init, level), format_expr(condition, level), format_simple(increment, level)));
+            output.push_str(&format_block(body, level + 1));
+            output.push_str(&format!("{}}}\n", pad));
+        }
+        Stmt::Repeat(count, body) => {
+            output.push_str(&format!("{}repeat ({}) {{\n", pad, format_expr(count, level)));
+            output.push_str(&format_block(body, level + 1));
+            output.push_str(&format!("{}}}\n", pad));
+        }
+        Stmt::Switch(subject, cases) => {
+            output.push_str(&format!("{}switch ({}) {{\n", pad, format_expr(subject, level)));
+            for case in cases.iter() {
+                for value in case.values.iter() {
+                    match value {
+                        Some(value) => output.push_str(&format!("{}case {}:\n", indent(level + 1), format_expr(value, level + 1))),
+                        None => output.push_str(&format!("{}default:\n", indent(level + 1))),
+                    }
+                }
+                output.push_str(&format_block(&case.body, level + 2));
+            }
+            output.push_str(&format!("{}}}\n", pad));
+        }
+        Stmt::With(target, body) => {
+            output.push_str(&format!("{}with ({}) {{\n", pad, format_expr(target, level)));
+            output.push_str(&format_block(body, level + 1));
+            output.push_str(&format!("{}}}\n", pad));
+        }
+        Stmt::Raw(text) => output.push_str(&format!("{}{}\n", pad, text)),
+    }
+}
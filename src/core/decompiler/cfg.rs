@@ -0,0 +1,97 @@
+use crate::core::bytecode::{Instruction, Opcode};
+use integer_hasher::IntMap;
+
+#[derive(Default, Clone, Debug)]
+pub struct Block {
+    pub start: usize, // Index of the first instruction
+    pub end: usize, // Index past the last instruction
+    pub successors: Vec<usize>,
+    pub predecessors: Vec<usize>,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Cfg {
+    pub blocks: Vec<Block>,
+    pub block_of: Vec<usize>, // Block containing each instruction
+    pub index_of_offset: IntMap<u32, usize>, // Instruction starting at each byte offset
+    pub end_offset: u32, // Offset right past the last instruction, which branches may target
+}
+
+impl Cfg {
+    pub fn build(instructions: &[Instruction]) -> Self {
+        let mut cfg = Self {
+            end_offset: instructions.last().map(|instruction| instruction.offset + instruction.size()).unwrap_or_default(),
+            ..Default::default()
+        };
+        for (index, instruction) in instructions.iter().enumerate() {
+            cfg.index_of_offset.insert(instruction.offset, index);
+        }
+
+        // Blocks start at the entry, at every branch target and right after every branch or return
+        let mut leaders = vec![false; instructions.len()];
+        if let Some(leader) = leaders.first_mut() {
+            *leader = true;
+        }
+        for (index, instruction) in instructions.iter().enumerate() {
+            if let Some(target) = instruction.target().and_then(|target| cfg.index_of_offset.get(&target)) {
+                leaders[*target] = true;
+            }
+            let terminates = instruction.is_branch() || matches!(instruction.opcode, Opcode::Ret | Opcode::Exit);
+            if terminates && index + 1 < instructions.len() {
+                leaders[index + 1] = true;
+            }
+        }
+        for (index, leader) in leaders.iter().enumerate() {
+            if *leader {
+                if let Some(block) = cfg.blocks.last_mut() {
+                    block.end = index;
+                }
+                cfg.blocks.push(Block {
+                    start: index,
+                    ..Default::default()
+                });
+            }
+            cfg.block_of.push(cfg.blocks.len() - 1);
+        }
+        if let Some(block) = cfg.blocks.last_mut() {
+            block.end = instructions.len();
+        }
+
+        for block in 0..cfg.blocks.len() {
+            let last = &instructions[cfg.blocks[block].end - 1];
+            let mut successors = Vec::new();
+            let falls_through = !matches!(last.opcode, Opcode::B | Opcode::Ret | Opcode::Exit) && (last.opcode != Opcode::PopEnv || last.popenv_drop);
+            if let Some(target) = last.target().and_then(|target| cfg.index_of_offset.get(&target)) {
+                successors.push(cfg.block_of[*target]);
+            }
+            // Popenv loops back into the body, but also falls through once every instance was visited
+            if (falls_through || last.opcode == Opcode::PopEnv) && block + 1 < cfg.blocks.len() {
+                successors.push(block + 1);
+            }
+            for successor in successors.iter() {
+                cfg.blocks[*successor].predecessors.push(block);
+            }
+            cfg.blocks[block].successors = successors;
+        }
+
+        cfg
+    }
+
+    pub fn index_of(&self, offset: u32) -> Option<usize> {
+        self.index_of_offset.get(&offset).copied()
+    }
+
+    // Branch instructions jumping back to the instruction at `index`, which makes it a loop header
+    pub fn back_edges(&self, index: usize) -> Vec<usize> {
+        let Some(&block) = self.block_of.get(index) else {
+            return Vec::new();
+        };
+        if self.blocks[block].start != index {
+            return Vec::new();
+        }
+        self.blocks[block].predecessors.iter()
+            .filter(|predecessor| **predecessor >= block)
+            .map(|predecessor| self.blocks[*predecessor].end - 1)
+            .collect()
+    }
+}
@@ -0,0 +1,958 @@
+pub mod ast;
+pub mod cfg;
+
+use crate::core::{
+    reader::Reader,
+    bytecode::{Instruction, Opcode, DataType, ComparisonKind, VariableType, ChainLink, Operand},
+    chunks::scpt::ChunkSCPT,
+    disasm::{Disassembler, instance_name},
+};
+use self::{
+    ast::{BinaryOp, UnaryOp, Case, Expr, FunctionDef, Stmt, format_body, format_function},
+    cfg::Cfg,
+};
+use bstr::BString;
+use std::{cell::RefCell, collections::HashMap, io::{Error, ErrorKind, Read, Result, Seek}};
+
+// A value on the simulated VM stack
+#[derive(Clone, Debug)]
+struct Item {
+    expr: Expr,
+    data_type: DataType,
+    nullish: bool, // Pushed by chknullish, the checked value stays right below it
+}
+
+impl Item {
+    fn new(expr: Expr, data_type: DataType) -> Self {
+        Self {
+            expr,
+            data_type,
+            nullish: false,
+        }
+    }
+}
+
+// Where the branches of the enclosing statements lead to
+#[derive(Clone, Copy, Default)]
+struct Context {
+    break_target: Option<u32>,
+    continue_target: Option<u32>,
+    switch_depth: Option<usize>, // Stack length while the subject of the enclosing switch is on top
+}
+
+fn type_size(data_type: DataType) -> usize {
+    match data_type {
+        DataType::Double | DataType::Int64 => 8,
+        DataType::Float | DataType::Int32 | DataType::Boolean | DataType::Int16 => 4,
+        _ => 16,
+    }
+}
+
+fn negate(expr: Expr) -> Expr {
+    match expr {
+        Expr::Unary(UnaryOp::Not, value) => *value,
+        Expr::Binary(op, left, right) => {
+            let inverse = match op {
+                BinaryOp::Less => BinaryOp::GreaterEqual,
+                BinaryOp::LessEqual => BinaryOp::Greater,
+                BinaryOp::Equal => BinaryOp::NotEqual,
+                BinaryOp::NotEqual => BinaryOp::Equal,
+                BinaryOp::GreaterEqual => BinaryOp::Less,
+                BinaryOp::Greater => BinaryOp::LessEqual,
+                _ => return Expr::Unary(UnaryOp::Not, Box::new(Expr::Binary(op, left, right))),
+            };
+            Expr::Binary(inverse, left, right)
+        }
+        _ => Expr::Unary(UnaryOp::Not, Box::new(expr)),
+    }
+}
+
+fn contains(expr: &Expr, needle: &Expr) -> bool {
+    if expr == needle {
+        return true;
+    }
+    match expr {
+        Expr::Variable { instance, indices, .. } => instance.as_deref().is_some_and(|instance| contains(instance, needle)) || indices.iter().any(|index| contains(index, needle)),
+        Expr::Binary(_, left, right) => contains(left, needle) || contains(right, needle),
+        Expr::Unary(_, value) => contains(value, needle),
+        Expr::Ternary(condition, then, otherwise) => contains(condition, needle) || contains(then, needle) || contains(otherwise, needle),
+        Expr::Call(_, arguments) | Expr::Array(arguments) => arguments.iter().any(|argument| contains(argument, needle)),
+        Expr::CallValue(function, arguments) | Expr::New(function, arguments) => contains(function, needle) || arguments.iter().any(|argument| contains(argument, needle)),
+        _ => false,
+    }
+}
+
+// Compilers always end code with an exit, which is implied in GML
+fn trim_exit(mut body: Vec<Stmt>) -> Vec<Stmt> {
+    if matches!(body.last(), Some(Stmt::Exit)) {
+        body.pop();
+    }
+    body
+}
+
+fn comparison_op(comparison: ComparisonKind) -> BinaryOp {
+    match comparison {
+        ComparisonKind::Less => BinaryOp::Less,
+        ComparisonKind::LessEqual => BinaryOp::LessEqual,
+        ComparisonKind::NotEqual => BinaryOp::NotEqual,
+        ComparisonKind::GreaterEqual => BinaryOp::GreaterEqual,
+        ComparisonKind::Greater => BinaryOp::Greater,
+        _ => BinaryOp::Equal,
+    }
+}
+
+fn script_name(name: &str) -> String {
+    name.strip_prefix("gml_Script_").unwrap_or(name).to_string()
+}
+
+pub struct Decompiler<'a> {
+    pub disassembler: Disassembler<'a>,
+    pub scripts: Option<&'a ChunkSCPT>,
+    pub short_circuit: bool, // Logical and/or operations skip their right side, see GMVersionInfo
+}
+
+impl<'a> Decompiler<'a> {
+    pub fn new<R>(reader: &'a Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        Ok(Self {
+            disassembler: Disassembler::new(reader)?,
+            scripts: reader.scripts(),
+            short_circuit: reader.version_info.short_circuit,
+        })
+    }
+
+    // GML source of a code entry, child entries being printed as a function declaration
+    pub fn decompile(&self, index: usize) -> Result<String> {
+        let code = self.disassembler.code;
+        let entry = code.entries.values.get(index)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("Code entry {} does not exist", index)))?;
+        let root = entry.parent.unwrap_or(index);
+        let instructions = self.disassembler.instructions(root)?;
+        let state = State {
+            decompiler: self,
+            cfg: Cfg::build(&instructions),
+            children: code.children(root).map(|(child, entry)| (entry.offset, child)).collect(),
+            instructions,
+            functions: RefCell::new(HashMap::new()),
+        };
+
+        match entry.parent {
+            Some(_) => {
+                let start = state.index_at(entry.offset)
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Code entry {} does not start on an instruction", index)))?;
+                let end = state.function_end(start);
+                let function = state.function(index, start, end)?;
+                Ok(format!("{}\n", format_function(&function, Some(&function.short_name()), 0)))
+            }
+            None => {
+                let mut stack = Vec::new();
+                let (body, _) = state.region(0, state.instructions.len(), &mut stack, Context::default())?;
+                Ok(format_body(&self.locals(index), &trim_exit(body), 0))
+            }
+        }
+    }
+
+    fn locals(&self, index: usize) -> Vec<BString> {
+        self.disassembler.locals(index).iter().map(|local| local.name.clone()).collect()
+    }
+
+    fn is_constructor(&self, index: usize) -> bool {
+        self.scripts.is_some_and(|scripts| scripts.scripts.values.iter().any(|script| script.code_id >= 0 && script.code_id as usize == index && script.constructor))
+    }
+}
+
+// Decompilation of the bytecode shared by a root entry and its children
+struct State<'d, 'a> {
+    decompiler: &'d Decompiler<'a>,
+    instructions: Vec<Instruction>,
+    cfg: Cfg,
+    children: HashMap<u32, usize>, // Offset of each child entry to its index
+    functions: RefCell<HashMap<String, FunctionDef>>, // Decompiled children waiting for the instruction referencing them
+}
+
+impl State<'_, '_> {
+    fn index_at(&self, offset: u32) -> Option<usize> {
+        if offset == self.cfg.end_offset {
+            return Some(self.instructions.len());
+        }
+        self.cfg.index_of(offset)
+    }
+
+    fn offset_at(&self, index: usize) -> u32 {
+        self.instructions.get(index).map(|instruction| instruction.offset).unwrap_or(self.cfg.end_offset)
+    }
+
+    fn error(&self, index: usize, message: &str) -> Error {
+        Error::new(ErrorKind::InvalidData, format!("{} at {:#x}", message, self.offset_at(index)))
+    }
+
+    // Child bodies are skipped over by a branch placed right before them
+    fn function_end(&self, start: usize) -> usize {
+        start.checked_sub(1)
+            .and_then(|previous| self.instructions.get(previous))
+            .filter(|previous| previous.opcode == Opcode::B)
+            .and_then(|previous| previous.target())
+            .and_then(|target| self.index_at(target))
+            .unwrap_or(self.instructions.len())
+    }
+
+    fn function(&self, index: usize, start: usize, end: usize) -> Result<FunctionDef> {
+        let entry = &self.decompiler.disassembler.code.entries[index];
+        let mut stack = Vec::new();
+        let (body, _) = self.region(start, end, &mut stack, Context::default())?;
+
+        Ok(FunctionDef {
+            name: entry.name.clone(),
+            arguments: entry.arguments_count,
+            constructor: self.decompiler.is_constructor(index),
+            locals: self.decompiler.locals(index),
+            body: trim_exit(body),
+        })
+    }
+
+    fn is_exit(&self, target: u32, context: Context) -> bool {
+        context.break_target == Some(target) || context.continue_target == Some(target) || (context.switch_depth.is_some() && self.is_switch_end(target))
+    }
+
+    fn is_switch_end(&self, target: u32) -> bool {
+        self.index_at(target).and_then(|index| self.instructions.get(index)).is_some_and(|instruction| instruction.opcode == Opcode::Popz)
+    }
+
+    // Decompiles the instructions from `start` to `end`, returning where it stopped
+    fn region(&self, start: usize, end: usize, stack: &mut Vec<Item>, context: Context) -> Result<(Vec<Stmt>, usize)> {
+        let mut output = Vec::new();
+        let mut index = start;
+        while index < end {
+            let instruction = &self.instructions[index];
+            if let Some(latch) = self.cfg.back_edges(index).into_iter().filter(|latch| *latch < end).max() {
+                index = self.structure_loop(index, latch, stack, &mut output)?;
+                continue;
+            }
+            match instruction.opcode {
+                Opcode::Dup if instruction.dup_mode == 0 && instruction.value == 0 => {
+                    if let Some(next) = self.structure_repeat(index, end, stack, &mut output)? {
+                        index = next;
+                        continue;
+                    }
+                    if let Some(next) = self.structure_switch(index, end, stack, context, &mut output)? {
+                        index = next;
+                        continue;
+                    }
+                }
+                Opcode::PushEnv => {
+                    index = self.structure_with(index, stack, &mut output)?;
+                    continue;
+                }
+                Opcode::Bt | Opcode::Bf => {
+                    index = self.structure_if(index, end, stack, context, &mut output)?;
+                    continue;
+                }
+                Opcode::B => {
+                    index = self.structure_jump(index, end, context, &mut output)?;
+                    continue;
+                }
+                Opcode::Popz if context.switch_depth == Some(stack.len()) => {
+                    // Returning from inside a switch discards its subject first
+                    if matches!(self.instructions.get(index + 1).map(|next| next.opcode), Some(Opcode::Exit | Opcode::Ret)) {
+                        stack.pop();
+                        index += 1;
+                        continue;
+                    }
+                    return Ok((output, index));
+                }
+                _ => {}
+            }
+            self.step(index, stack, &mut output)?;
+            index += 1;
+        }
+
+        Ok((output, end))
+    }
+
+    fn pop(&self, index: usize, stack: &mut Vec<Item>) -> Result<Item> {
+        stack.pop().ok_or_else(|| self.error(index, "Stack underflow"))
+    }
+
+    // Single region evaluating to one value, like a loop condition or a case value
+    fn value(&self, start: usize, end: usize, stack: &[Item], context: Context) -> Result<Option<Item>> {
+        let mut values = stack.to_vec();
+        let (output, _) = self.region(start, end, &mut values, context)?;
+        if !output.is_empty() || values.len() != stack.len() + 1 {
+            return Ok(None);
+        }
+
+        Ok(values.pop())
+    }
+
+    fn structure_loop(&self, header: usize, latch: usize, stack: &mut [Item], output: &mut Vec<Stmt>) -> Result<usize> {
+        let exit = self.offset_at(latch + 1);
+        let head = self.offset_at(header);
+        let context = Context {
+            break_target: Some(exit),
+            continue_target: Some(head),
+            switch_depth: None,
+        };
+        match self.instructions[latch].opcode {
+            Opcode::B => {
+                let mut condition = None;
+                let mut body_start = header;
+                let check = (header..latch).find(|index| self.instructions[*index].opcode == Opcode::Bf && self.instructions[*index].target() == Some(exit));
+                if let Some(check) = check {
+                    if let Some(item) = self.value(header, check, stack, context)? {
+                        condition = Some(item.expr);
+                        body_start = check + 1;
+                    }
+                }
+
+                if let Some(condition) = condition.clone() {
+                    if let Some(statement) = self.structure_for(body_start, latch, condition, stack, context, output)? {
+                        output.push(statement);
+                        return Ok(latch + 1);
+                    }
+                }
+
+                let (body, _) = self.region(body_start, latch, &mut stack.to_vec(), context)?;
+                let condition = condition.unwrap_or(Expr::Bool(true));
+                output.push(Stmt::While(condition, body));
+            }
+            Opcode::Bf | Opcode::Bt => {
+                let context = Context {
+                    continue_target: None,
+                    ..context
+                };
+                let mut values = stack.to_vec();
+                let (body, _) = self.region(header, latch, &mut values, context)?;
+                if values.len() != stack.len() + 1 {
+                    return Err(self.error(latch, "Loop condition does not leave a value"));
+                }
+                let condition = self.pop(latch, &mut values)?.expr;
+                let condition = if self.instructions[latch].opcode == Opcode::Bt { negate(condition) } else { condition };
+                output.push(Stmt::DoUntil(body, condition));
+            }
+            _ => return Err(self.error(latch, "Unsupported loop")),
+        }
+
+        Ok(latch + 1)
+    }
+
+    // For loops jump to their increment when continuing, otherwise they look like a while loop ending with an assignment
+    fn structure_for(&self, body_start: usize, latch: usize, condition: Expr, stack: &[Item], context: Context, output: &mut Vec<Stmt>) -> Result<Option<Stmt>> {
+        let mut candidates = (body_start..latch)
+            .filter(|index| self.instructions[*index].opcode == Opcode::B)
+            .filter_map(|index| self.instructions[index].target().and_then(|target| self.index_at(target)).filter(|target| *target > index && *target < latch))
+            .filter(|target| (*target..latch).all(|index| !self.instructions[index].is_branch()))
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+        for increment in candidates {
+            let body_context = Context {
+                continue_target: Some(self.offset_at(increment)),
+                ..context
+            };
+            let (mut step, _) = self.region(increment, latch, &mut stack.to_vec(), body_context)?;
+            if step.len() != 1 || !matches!(step[0], Stmt::Assign(..)) {
+                continue;
+            }
+            let (body, _) = self.region(body_start, increment, &mut stack.to_vec(), body_context)?;
+            let init = match output.last() {
+                Some(Stmt::Assign(target, _)) if contains(&condition, target) => output.pop().unwrap_or(Stmt::Raw(String::new())),
+                _ => Stmt::Raw(String::new()),
+            };
+            return Ok(Some(Stmt::For(Box::new(init), condition, Box::new(step.remove(0)), body)));
+        }
+
+        let (mut body, _) = self.region(body_start, latch, &mut stack.to_vec(), context)?;
+        if body.len() < 2 {
+            return Ok(None);
+        }
+        let (Some(Stmt::Assign(step_target, _)), Some(Stmt::Assign(init_target, _))) = (body.last(), output.last()) else {
+            return Ok(None);
+        };
+        if step_target != init_target || !contains(&condition, step_target) {
+            return Ok(None);
+        }
+        let step = body.pop().unwrap_or(Stmt::Raw(String::new()));
+        let init = output.pop().unwrap_or(Stmt::Raw(String::new()));
+
+        Ok(Some(Stmt::For(Box::new(init), condition, Box::new(step), body)))
+    }
+
+    // dup.i 0, push 0, cmp.LTE, bt end, body, then the counter is decremented and tested before the final popz
+    fn structure_repeat(&self, index: usize, end: usize, stack: &mut Vec<Item>, output: &mut Vec<Stmt>) -> Result<Option<usize>> {
+        let instructions = &self.instructions;
+        if index + 4 > end {
+            return Ok(None);
+        }
+        let zero = &instructions[index + 1];
+        let compare = &instructions[index + 2];
+        let test = &instructions[index + 3];
+        let pushes_zero = zero.is_push() && match zero.operand {
+            Operand::None => zero.value == 0,
+            Operand::Int32(value) => value == 0,
+            _ => false,
+        };
+        if !pushes_zero || compare.opcode != Opcode::Cmp || compare.comparison != ComparisonKind::LessEqual || test.opcode != Opcode::Bt {
+            return Ok(None);
+        }
+        let Some(exit) = test.target().and_then(|target| self.index_at(target)) else {
+            return Ok(None);
+        };
+        if exit >= end || exit < index + 9 || instructions[exit].opcode != Opcode::Popz || instructions[exit - 1].opcode != Opcode::Bt || instructions[exit - 1].target() != Some(self.offset_at(index + 4)) {
+            return Ok(None);
+        }
+
+        let count = self.pop(index, stack)?.expr;
+        let latch = exit - 5;
+        let body_context = Context {
+            break_target: Some(self.offset_at(exit)),
+            continue_target: Some(self.offset_at(latch)),
+            switch_depth: None,
+        };
+        let (body, _) = self.region(index + 4, latch, &mut stack.clone(), body_context)?;
+        output.push(Stmt::Repeat(count, body));
+
+        Ok(Some(exit + 1))
+    }
+
+    // Each case duplicates the subject, compares it and branches to its body, the end of the switch popping the subject
+    fn structure_switch(&self, index: usize, end: usize, stack: &mut Vec<Item>, context: Context, output: &mut Vec<Stmt>) -> Result<Option<usize>> {
+        let mut targets: Vec<(Option<Expr>, u32)> = Vec::new();
+        let mut current = index;
+        let mut fallback = None;
+        while current < end && self.instructions[current].opcode == Opcode::Dup && self.instructions[current].value == 0 {
+            let Some(compare) = (current + 1..end).find(|at| self.instructions[*at].opcode == Opcode::Cmp || self.instructions[*at].is_branch()) else {
+                return Ok(None);
+            };
+            let instruction = &self.instructions[compare];
+            if instruction.opcode != Opcode::Cmp || instruction.comparison != ComparisonKind::Equal || self.instructions.get(compare + 1).map(|next| next.opcode) != Some(Opcode::Bt) {
+                return Ok(None);
+            }
+            let Some(value) = self.value(current + 1, compare, stack, context)? else {
+                return Ok(None);
+            };
+            let Some(target) = self.instructions[compare + 1].target() else {
+                return Ok(None);
+            };
+            targets.push((Some(value.expr), target));
+            current = compare + 2;
+            if let Some(instruction) = self.instructions.get(current).filter(|instruction| instruction.opcode == Opcode::B) {
+                fallback = instruction.target();
+                current += 1;
+                break;
+            }
+        }
+        let Some(fallback) = fallback else {
+            return Ok(None);
+        };
+        if targets.is_empty() || fallback < self.offset_at(current) {
+            return Ok(None);
+        }
+        if !self.is_switch_end(fallback) {
+            targets.push((None, fallback));
+        }
+
+        // Cases sharing a body are listed together
+        let mut starts = targets.iter().map(|(_, target)| *target).collect::<Vec<_>>();
+        starts.sort();
+        starts.dedup();
+        let base = stack.len();
+        let case_context = Context {
+            break_target: None,
+            switch_depth: Some(base),
+            ..context
+        };
+        let mut cases = Vec::new();
+        let mut stop = end;
+        let mut cursor = current;
+        for (position, start) in starts.iter().enumerate() {
+            let Some(start_index) = self.index_at(*start) else {
+                return Ok(None);
+            };
+            // Anything between the tests and the first body is unreachable
+            if start_index < cursor {
+                return Ok(None);
+            }
+            let case_end = match starts.get(position + 1) {
+                Some(next) => self.index_at(*next).unwrap_or(end),
+                None => end,
+            };
+            let (body, stopped) = self.region(start_index, case_end, &mut stack.clone(), case_context)?;
+            cases.push(Case {
+                values: targets.iter().filter(|(_, target)| target == start).map(|(value, _)| value.clone()).collect(),
+                body,
+            });
+            cursor = stopped;
+            if position + 1 == starts.len() {
+                stop = stopped;
+            }
+        }
+        if stop >= end {
+            return Ok(None);
+        }
+
+        let subject = self.pop(index, stack)?.expr;
+        output.push(Stmt::Switch(subject, cases));
+
+        Ok(Some(stop + 1))
+    }
+
+    fn structure_with(&self, index: usize, stack: &mut Vec<Item>, output: &mut Vec<Stmt>) -> Result<usize> {
+        let Some(end) = self.instructions[index].target().and_then(|target| self.index_at(target)) else {
+            return Err(self.error(index, "Pushenv has no matching popenv"));
+        };
+        let target = self.pop(index, stack)?;
+        let context = Context {
+            break_target: Some(self.offset_at(end + 1)),
+            continue_target: Some(self.offset_at(end)),
+            switch_depth: None,
+        };
+        let (body, _) = self.region(index + 1, end, &mut stack.clone(), context)?;
+        let target = match target.expr {
+            Expr::Int(instance) => self.instance(instance as i16).map(|instance| *instance).unwrap_or(Expr::Instance(String::from("self"))),
+            expr => expr,
+        };
+        output.push(Stmt::With(target, body));
+
+        Ok(end + 1)
+    }
+
+    fn structure_if(&self, index: usize, end: usize, stack: &mut Vec<Item>, context: Context, output: &mut Vec<Stmt>) -> Result<usize> {
+        let instruction = &self.instructions[index];
+        let Some(target) = instruction.target() else {
+            return Err(self.error(index, "Branch without a target"));
+        };
+        let jumps_if_true = instruction.opcode == Opcode::Bt;
+        let condition = self.pop(index, stack)?;
+
+        // The checked value stays on the stack, and is only replaced when nullish
+        if condition.nullish {
+            let Some(target_index) = self.index_at(target).filter(|target_index| *target_index <= end && *target_index > index) else {
+                return Err(self.error(index, "Malformed nullish check"));
+            };
+            let left = self.pop(index, stack)?;
+            if self.instructions.get(index + 1).map(|next| next.opcode) != Some(Opcode::Popz) {
+                return Err(self.error(index, "Malformed nullish check"));
+            }
+            let right = self.value(index + 2, target_index, stack, context)?
+                .ok_or_else(|| self.error(index, "Malformed nullish check"))?;
+            stack.push(Item::new(Expr::Binary(BinaryOp::Nullish, Box::new(left.expr), Box::new(right.expr)), DataType::Variable));
+            return Ok(target_index);
+        }
+
+        let condition = condition.expr;
+        let when_taken = |statement: Stmt| Stmt::If(if jumps_if_true { condition.clone() } else { negate(condition.clone()) }, vec![statement], Vec::new());
+        if context.break_target == Some(target) {
+            output.push(when_taken(Stmt::Break));
+            return Ok(index + 1);
+        }
+        if context.continue_target == Some(target) {
+            output.push(when_taken(Stmt::Continue));
+            return Ok(index + 1);
+        }
+        let end_offset = self.offset_at(end);
+        let Some(target_index) = self.index_at(target).filter(|target_index| target > instruction.offset && target <= end_offset && *target_index <= end) else {
+            output.push(Stmt::Raw(format!("// goto {:#x}", target)));
+            return Ok(index + 1);
+        };
+
+        // An if with an else jumps over it at the end of its first branch
+        let mut else_end = None;
+        if target_index > index + 1 {
+            let last = &self.instructions[target_index - 1];
+            if last.opcode == Opcode::B {
+                if let Some(after) = last.target() {
+                    if after > target && after <= end_offset && (after == end_offset || !self.is_exit(after, context)) && !self.children.contains_key(&self.offset_at(target_index)) {
+                        else_end = self.index_at(after);
+                    }
+                }
+            }
+        }
+
+        let then_end = if else_end.is_some() { target_index - 1 } else { target_index };
+        let mut then_stack = stack.clone();
+        let (then_body, _) = self.region(index + 1, then_end, &mut then_stack, context)?;
+        let mut else_stack = stack.clone();
+        let (else_body, next) = match else_end {
+            Some(else_end) => (self.region(target_index, else_end, &mut else_stack, context)?.0, else_end),
+            None => (Vec::new(), target_index),
+        };
+
+        // Both branches leaving a value is a conditional expression
+        if else_end.is_some() && then_body.is_empty() && else_body.is_empty() && then_stack.len() == stack.len() + 1 && else_stack.len() == stack.len() + 1 {
+            let fallthrough = self.pop(index, &mut then_stack)?;
+            let jumped = self.pop(index, &mut else_stack)?;
+            let (when_true, when_false) = if jumps_if_true { (jumped, fallthrough) } else { (fallthrough, jumped) };
+            stack.push(self.conditional(condition, when_true, when_false));
+            return Ok(next);
+        }
+
+        let condition = if jumps_if_true { negate(condition) } else { condition };
+        output.push(Stmt::If(condition, then_body, else_body));
+
+        Ok(next)
+    }
+
+    // Short-circuit evaluation compiles to a conditional with a constant on one side
+    fn conditional(&self, condition: Expr, when_true: Item, when_false: Item) -> Item {
+        if self.decompiler.short_circuit {
+            let is_constant = |item: &Item, value: bool| match item.expr {
+                Expr::Int(constant) => constant == value as i64,
+                Expr::Bool(constant) => constant == value,
+                _ => false,
+            };
+            if is_constant(&when_false, false) && when_true.data_type == DataType::Boolean {
+                return Item::new(Expr::Binary(BinaryOp::And, Box::new(condition), Box::new(when_true.expr)), DataType::Boolean);
+            }
+            if is_constant(&when_true, true) && when_false.data_type == DataType::Boolean {
+                return Item::new(Expr::Binary(BinaryOp::Or, Box::new(condition), Box::new(when_false.expr)), DataType::Boolean);
+            }
+        }
+        let data_type = when_true.data_type;
+
+        Item::new(Expr::Ternary(Box::new(condition), Box::new(when_true.expr), Box::new(when_false.expr)), data_type)
+    }
+
+    // Unconditional branches left over once loops and conditions are structured
+    fn structure_jump(&self, index: usize, end: usize, context: Context, output: &mut Vec<Stmt>) -> Result<usize> {
+        let Some(target) = self.instructions[index].target() else {
+            return Err(self.error(index, "Branch without a target"));
+        };
+        if let Some(child) = self.children.get(&self.offset_at(index + 1)) {
+            let Some(function_end) = self.index_at(target).filter(|function_end| *function_end > index && *function_end <= end) else {
+                return Err(self.error(index, "Child function is not skipped over"));
+            };
+            let function = self.function(*child, index + 1, function_end)?;
+            self.functions.borrow_mut().insert(function.name.to_string(), function);
+            return Ok(function_end);
+        }
+        if context.break_target == Some(target) || (context.switch_depth.is_some() && self.is_switch_end(target)) {
+            output.push(Stmt::Break);
+        } else if context.continue_target == Some(target) {
+            output.push(Stmt::Continue);
+        } else if target != self.offset_at(end) {
+            output.push(Stmt::Raw(format!("// goto {:#x}", target)));
+        }
+
+        Ok(index + 1)
+    }
+
+    fn instance(&self, instance: i16) -> Option<Box<Expr>> {
+        if instance >= 0 {
+            let name = self.decompiler.disassembler.objects
+                .and_then(|objects| objects.objects.values.get(instance as usize))
+                .map(|object| object.name.to_string());
+            return Some(Box::new(match name {
+                Some(name) => Expr::Instance(name),
+                None => Expr::Int(instance as i64),
+            }));
+        }
+        match instance {
+            -5..=-2 => instance_name(instance).map(|name| Box::new(Expr::Instance(name.to_string()))),
+            _ => None,
+        }
+    }
+
+    fn instance_of(&self, item: Item) -> Option<Box<Expr>> {
+        match item.expr {
+            Expr::Int(instance) => self.instance(instance as i16),
+            Expr::Instance(name) if name == "self" => None,
+            expr => Some(Box::new(expr)),
+        }
+    }
+
+    fn variable(&self, index: usize, link: &ChainLink, stack: &mut Vec<Item>) -> Result<Expr> {
+        let instruction = &self.instructions[index];
+        let name = self.decompiler.disassembler.variable_name(link);
+        let mut indices = Vec::new();
+        let instance = match link.kind {
+            VariableType::Array | VariableType::MultiPush | VariableType::MultiPushPop => {
+                indices.push(self.pop(index, stack)?.expr);
+                let instance = self.pop(index, stack)?;
+                self.instance_of(instance)
+            }
+            VariableType::StackTop => {
+                let instance = self.pop(index, stack)?;
+                self.instance_of(instance)
+            }
+            _ if instruction.value == -9 => {
+                let instance = self.pop(index, stack)?;
+                self.instance_of(instance)
+            }
+            _ => self.instance(instruction.value),
+        };
+
+        Ok(Expr::Variable { instance, name, indices })
+    }
+
+    fn function_expr(&self, link: &ChainLink) -> Expr {
+        let name = self.decompiler.disassembler.function_name(link);
+        match self.functions.borrow_mut().remove(&name) {
+            Some(function) => Expr::Function(Box::new(function)),
+            None => Expr::FunctionRef(script_name(&name)),
+        }
+    }
+
+    fn call(&self, index: usize, name: String, arguments: Vec<Expr>) -> Result<Expr> {
+        Ok(match (name.as_str(), arguments.as_slice()) {
+            ("method", [_, function @ (Expr::Function(_) | Expr::FunctionRef(_))]) => function.clone(),
+            ("@@NewGMLArray@@", _) => Expr::Array(arguments),
+            ("@@This@@", []) => Expr::Instance(String::from("self")),
+            ("@@Other@@", []) => Expr::Instance(String::from("other")),
+            ("@@Global@@", []) => Expr::Instance(String::from("global")),
+            ("@@GetInstance@@", [instance]) => instance.clone(),
+            ("@@NewGMLObject@@", [Expr::Function(function), values @ ..]) if function.is_struct() => {
+                // Each field is assigned either a constant or one of the arguments
+                let mut fields = Vec::new();
+                for statement in function.body.iter() {
+                    let Stmt::Assign(Expr::Variable { instance: None, name, indices }, value) = statement else {
+                        return Err(self.error(index, "Unexpected statement in a struct literal"));
+                    };
+                    if !indices.is_empty() {
+                        return Err(self.error(index, "Unexpected statement in a struct literal"));
+                    }
+                    let value = match value {
+                        Expr::Variable { instance: None, name, indices } if name == "argument" => match indices.as_slice() {
+                            [Expr::Int(argument)] => values.get(*argument as usize).cloned().unwrap_or_else(|| value.clone()),
+                            _ => value.clone(),
+                        },
+                        Expr::Variable { instance: None, name, indices } if indices.is_empty() && name.starts_with("argument") => {
+                            match name["argument".len()..].parse::<usize>().ok().and_then(|argument| values.get(argument)) {
+                                Some(argument) => argument.clone(),
+                                None => value.clone(),
+                            }
+                        }
+                        _ => value.clone(),
+                    };
+                    fields.push((name.clone(), value));
+                }
+                Expr::Struct(fields)
+            }
+            ("@@NewGMLObject@@", [function, values @ ..]) => Expr::New(Box::new(function.clone()), values.to_vec()),
+            _ => Expr::Call(script_name(&name), arguments),
+        })
+    }
+
+    fn arguments(&self, index: usize, count: usize, stack: &mut Vec<Item>) -> Result<Vec<Expr>> {
+        (0..count).map(|_| self.pop(index, stack).map(|item| item.expr)).collect()
+    }
+
+    // Simulates a single instruction that isn't part of any control flow
+    fn step(&self, index: usize, stack: &mut Vec<Item>, output: &mut Vec<Stmt>) -> Result<()> {
+        let instruction = &self.instructions[index];
+        match instruction.opcode {
+            Opcode::Push | Opcode::PushLoc | Opcode::PushGlb | Opcode::PushBltn | Opcode::PushI => {
+                let item = match &instruction.operand {
+                    Operand::None => Item::new(Expr::Int(instruction.value as i64), DataType::Int16),
+                    Operand::Double(value) => Item::new(Expr::Double(*value), DataType::Double),
+                    Operand::Float(value) => Item::new(Expr::Double(*value as f64), DataType::Float),
+                    Operand::Int32(value) => Item::new(Expr::Int(*value as i64), DataType::Int32),
+                    Operand::Int64(value) => Item::new(Expr::Int(*value), DataType::Int64),
+                    Operand::Boolean(value) => Item::new(Expr::Bool(*value), DataType::Boolean),
+                    Operand::String(id) => {
+                        let string = self.decompiler.disassembler.strings.and_then(|strings| strings.get(*id as usize)).cloned().unwrap_or_default();
+                        Item::new(Expr::String(string), DataType::String)
+                    }
+                    Operand::Variable(link) => Item::new(self.variable(index, link, stack)?, DataType::Variable),
+                    Operand::Function(link) => Item::new(self.function_expr(link), DataType::Int32),
+                };
+                stack.push(item);
+            }
+            Opcode::Conv => {
+                let top = stack.last_mut().ok_or_else(|| self.error(index, "Stack underflow"))?;
+                top.data_type = instruction.type2;
+                if instruction.type2 == DataType::Boolean {
+                    if let Expr::Int(value @ (0 | 1)) = top.expr {
+                        top.expr = Expr::Bool(value == 1);
+                    }
+                }
+            }
+            Opcode::Mul | Opcode::Div | Opcode::Rem | Opcode::Mod | Opcode::Add | Opcode::Sub | Opcode::And | Opcode::Or | Opcode::Xor | Opcode::Shl | Opcode::Shr | Opcode::Cmp => {
+                let right = self.pop(index, stack)?;
+                let left = self.pop(index, stack)?;
+                let logical = instruction.type1 == DataType::Boolean && instruction.type2 == DataType::Boolean;
+                let op = match instruction.opcode {
+                    Opcode::Mul => BinaryOp::Mul,
+                    Opcode::Div => BinaryOp::Div,
+                    Opcode::Rem => BinaryOp::IntDiv,
+                    Opcode::Mod => BinaryOp::Mod,
+                    Opcode::Add => BinaryOp::Add,
+                    Opcode::Sub => BinaryOp::Sub,
+                    Opcode::And if logical => BinaryOp::And,
+                    Opcode::And => BinaryOp::BitAnd,
+                    Opcode::Or if logical => BinaryOp::Or,
+                    Opcode::Or => BinaryOp::BitOr,
+                    Opcode::Xor if logical => BinaryOp::Xor,
+                    Opcode::Xor => BinaryOp::BitXor,
+                    Opcode::Shl => BinaryOp::Shl,
+                    Opcode::Shr => BinaryOp::Shr,
+                    _ => comparison_op(instruction.comparison),
+                };
+                let data_type = if logical || instruction.opcode == Opcode::Cmp {
+                    DataType::Boolean
+                } else if left.data_type == DataType::Variable || right.data_type == DataType::Variable {
+                    DataType::Variable
+                } else {
+                    left.data_type.max(right.data_type)
+                };
+                stack.push(Item::new(Expr::Binary(op, Box::new(left.expr), Box::new(right.expr)), data_type));
+            }
+            Opcode::Neg | Opcode::Not => {
+                let value = self.pop(index, stack)?;
+                let op = match instruction.opcode {
+                    Opcode::Neg => UnaryOp::Neg,
+                    _ if instruction.type1 == DataType::Boolean => UnaryOp::Not,
+                    _ => UnaryOp::BitNot,
+                };
+                let expr = match (op, value.expr) {
+                    (UnaryOp::Not, expr) => negate(expr),
+                    (op, expr) => Expr::Unary(op, Box::new(expr)),
+                };
+                stack.push(Item::new(expr, value.data_type));
+            }
+            Opcode::Dup if instruction.dup_mode == 0 => {
+                // The size is counted in bytes of the instruction type, which may span several values
+                let size = (instruction.value as usize + 1) * type_size(instruction.type1);
+                let mut total = 0;
+                let mut count = 0;
+                for item in stack.iter().rev() {
+                    if total >= size {
+                        break;
+                    }
+                    total += type_size(item.data_type);
+                    count += 1;
+                }
+                if total != size {
+                    count = instruction.value as usize + 1;
+                }
+                if count > stack.len() {
+                    return Err(self.error(index, "Stack underflow"));
+                }
+                let values = stack[stack.len() - count..].to_vec();
+                stack.extend(values);
+            }
+            Opcode::Dup => {}
+            Opcode::Pop if instruction.type1 == DataType::Int16 => {}
+            Opcode::Pop => {
+                let Operand::Variable(link) = &instruction.operand else {
+                    return Err(self.error(index, "Pop without a variable"));
+                };
+                // Integers are stored before the target is resolved
+                let value = if instruction.type1 == DataType::Int32 { Some(self.pop(index, stack)?) } else { None };
+                let target = self.variable(index, link, stack)?;
+                let value = match value {
+                    Some(value) => value,
+                    None => self.pop(index, stack)?,
+                };
+                output.push(Stmt::Assign(target, value.expr));
+            }
+            Opcode::Popz => {
+                let value = self.pop(index, stack)?;
+                // Function declarations keep a copy of the method around, which has no side effects to show
+                if !matches!(value.expr, Expr::Function(_) | Expr::FunctionRef(_)) {
+                    output.push(Stmt::Expr(value.expr));
+                }
+            }
+            Opcode::Call => {
+                let Operand::Function(link) = &instruction.operand else {
+                    return Err(self.error(index, "Call without a function"));
+                };
+                let name = self.decompiler.disassembler.function_name(link);
+                let arguments = self.arguments(index, instruction.value.max(0) as usize, stack)?;
+                stack.push(Item::new(self.call(index, name, arguments)?, DataType::Variable));
+            }
+            Opcode::CallV => {
+                let function = self.pop(index, stack)?;
+                let _instance = self.pop(index, stack)?;
+                let arguments = self.arguments(index, instruction.value.max(0) as usize, stack)?;
+                stack.push(Item::new(Expr::CallValue(Box::new(function.expr), arguments), DataType::Variable));
+            }
+            Opcode::Ret => {
+                let value = self.pop(index, stack)?;
+                output.push(Stmt::Return(value.expr));
+            }
+            Opcode::Exit => output.push(Stmt::Exit),
+            Opcode::PopEnv => {}
+            Opcode::Break => match instruction.value {
+                -2 | -4 => {
+                    let array_index = self.pop(index, stack)?;
+                    let array = self.pop(index, stack)?;
+                    let expr = match array.expr {
+                        Expr::Variable { instance, name, mut indices } => {
+                            indices.push(array_index.expr);
+                            Expr::Variable { instance, name, indices }
+                        }
+                        expr => Expr::Raw(format!("/* index {:?} of {:?} */", array_index.expr, expr)),
+                    };
+                    stack.push(Item::new(expr, DataType::Variable));
+                }
+                -3 => {
+                    let array_index = self.pop(index, stack)?;
+                    let array = self.pop(index, stack)?;
+                    let value = self.pop(index, stack)?;
+                    let target = match array.expr {
+                        Expr::Variable { instance, name, mut indices } => {
+                            indices.push(array_index.expr);
+                            Expr::Variable { instance, name, indices }
+                        }
+                        expr => return Err(self.error(index, &format!("Cannot assign into {:?}", expr))),
+                    };
+                    output.push(Stmt::Assign(target, value.expr));
+                }
+                -5 => {
+                    self.pop(index, stack)?;
+                }
+                -6 => stack.push(Item::new(Expr::Raw(String::from("/* static initialized */")), DataType::Boolean)),
+                -10 => {
+                    let top = stack.last().ok_or_else(|| self.error(index, "Stack underflow"))?;
+                    stack.push(Item {
+                        nullish: true,
+                        ..top.clone()
+                    });
+                }
+                -11 => stack.push(match &instruction.operand {
+                    Operand::Function(link) => Item::new(self.function_expr(link), DataType::Int32),
+                    Operand::Int32(value) => Item::new(Expr::Int(*value as i64), DataType::Int32),
+                    _ => Item::new(Expr::Raw(String::from("/* reference */")), DataType::Int32),
+                }),
+                -1 | -7 | -8 | -9 => {}
+                value => output.push(Stmt::Raw(format!("// break {}", value))),
+            },
+            _ => output.push(Stmt::Raw(format!("// unhandled instruction at {:#x}", instruction.offset))),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::verify::tests::{assembled, version};
+    use std::io::Cursor;
+
+    #[test]
+    fn decompiles_branches_and_loops() {
+        let text = [
+            "pushi.e 0", "pop.v.i self.x",
+            ":[1]", "push.v self.x", "pushi.e 10", "cmp.i.v LT", "bf [2]",
+            "push.v self.x", "pushi.e 1", "add.i.v", "pop.v.v self.x", "b [1]",
+            ":[2]", "push.v self.x", "pushi.e 10", "cmp.i.v EQ", "bf [3]",
+            "pushi.e 1", "pop.v.i self.y", "b [4]",
+            ":[3]", "pushi.e 2", "pop.v.i self.y",
+            ":[4]", "exit.i",
+        ].join("\n");
+        let data = assembled(version(2, 3, 0, 0, 17), &text);
+        let mut reader = Reader::new(Cursor::new(data.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        reader.deserialize().unwrap();
+
+        let source = Decompiler::new(&reader).unwrap().decompile(0).unwrap();
+        let expected = [
+            "x = 0;",
+            "while (x < 10) {", "    x++;", "}",
+            "if (x == 10) {", "    y = 1;", "} else {", "    y = 2;", "}",
+        ].join("\n");
+        assert_eq!(source.trim_end(), expected);
+    }
+}
@@ -0,0 +1,513 @@
+use crate::core::{reader::Reader, serializing::FormatCheck, chunks::{sprt::ChunkSPRT, acrv::ChunkACRV, seqn::ChunkSEQN, extn::ChunkEXTN, font::ChunkFONT, tgin::ChunkTGIN, txtr::ChunkTXTR, psem::ChunkPSEM, room::ChunkROOM, func::ChunkFUNC}};
+use bstr::BString;
+use byteorder::ReadBytesExt;
+use std::{fmt, io::{Read, Result, Seek, SeekFrom}};
+use tracing::info;
+
+pub type Version = (i32, i32, i32, i32); // Major, minor, release and build
+
+// A heuristic that raised the version, and what it saw
+#[derive(Debug, Clone)]
+pub struct Evidence {
+    pub chunk: BString, // Chunk the heuristic looked at, or "FORM" for the chunk list itself
+    pub version: Version,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VersionReport {
+    pub stored: Version, // As written in GEN8, which stays at 2.0.0.0 for every GMS2 release
+    pub detected: Version,
+    pub evidence: Vec<Evidence>, // In the order the version was raised
+}
+
+impl fmt::Display for VersionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = |(major, minor, release, build): Version| format!("{}.{}.{}.{}", major, minor, release, build);
+        write!(f, "GEN8 stores {}, detected {}", version(self.stored), version(self.detected))?;
+        for evidence in self.evidence.iter() {
+            write!(f, "\n  {} -> {}: {}", evidence.chunk, version(evidence.version), evidence.reason)?;
+        }
+        Ok(())
+    }
+}
+
+// Chunks that only exist from the version introducing them
+const CHUNK_VERSIONS: [(&str, Version); 7] = [
+    ("SEQN", (2, 3, 0, 0)),
+    ("TAGS", (2, 3, 0, 0)),
+    ("ACRV", (2, 3, 0, 0)),
+    ("FEDS", (2022, 1, 0, 0)),
+    ("FEAT", (2022, 8, 0, 0)),
+    ("PSEM", (2023, 2, 0, 0)),
+    ("PSYS", (2023, 2, 0, 0)),
+];
+
+impl<T> Reader<T>
+    where T: Read + Seek + ReadBytesExt,
+{
+    // Raises the version to the given one if it's newer, keeping track of why
+    pub fn raise_version(&mut self, major: i32, minor: i32, release: i32, build: i32, reason: impl Into<String>) {
+        if self.version_info.is_version_at_least(major, minor, release, build) {
+            return;
+        }
+        self.version_info.set_version(major, minor, release, build);
+        let evidence = Evidence {
+            chunk: self.current_chunk.name.clone(),
+            version: (major, minor, release, build),
+            reason: reason.into(),
+        };
+        info!("Version raised to {}.{}.{}.{} by {}: {}", major, minor, release, build, evidence.chunk, evidence.reason);
+        self.version_report.evidence.push(evidence);
+    }
+
+    fn enter_chunk(&mut self, name: &str) -> Result<bool> {
+        let Some(chunk) = self.chunk_data.get(name.as_bytes()).cloned() else {
+            return Ok(false);
+        };
+        self.container.seek(SeekFrom::Start(chunk.start_offset))?;
+        self.current_chunk = chunk;
+        Ok(true)
+    }
+
+    // Runs every heuristic over the chunks found by deserialize_chunks, before any of them is parsed
+    pub fn detect_version(&mut self) -> Result<()> {
+        let return_to = self.container.stream_position()?;
+        self.version_report = VersionReport::default();
+
+        if self.enter_chunk("GEN8")? {
+            self.seek_relative(1)?; // Disable debug
+            self.version_info.format_id = self.read_i8()?;
+            self.version_info.room_object_pre_create = self.version_info.format_id >= 16;
+            self.seek_relative(42)?; // Up to the version, past the names, IDs and legacy GUID
+            let (major, minor, release, build) = (self.read_i32()?, self.read_i32()?, self.read_i32()?, self.read_i32()?);
+            self.version_info.set_version(major, minor, release, build);
+            self.version_report.stored = (major, minor, release, build);
+        }
+
+        if self.version_info.major >= 2 {
+            self.current_chunk.name = BString::from("FORM");
+            for (name, (major, minor, release, build)) in CHUNK_VERSIONS {
+                if self.chunk_data.contains_key(name.as_bytes()) {
+                    self.raise_version(major, minor, release, build, format!("{} chunk is present", name));
+                }
+            }
+        }
+
+        // Most heuristics only tell apart versions past the ones found before them, so the order matters
+        macro_rules! format_check {
+            ($name: expr, $ctype: ty) => {
+                if self.enter_chunk($name)? {
+                    <$ctype>::format_check(self)?;
+                }
+            }
+        }
+        format_check!("SPRT", ChunkSPRT);
+        format_check!("ACRV", ChunkACRV);
+        format_check!("SEQN", ChunkSEQN);
+        format_check!("EXTN", ChunkEXTN);
+        format_check!("FONT", ChunkFONT);
+        format_check!("TGIN", ChunkTGIN);
+        format_check!("TXTR", ChunkTXTR);
+        format_check!("PSEM", ChunkPSEM);
+        format_check!("ROOM", ChunkROOM);
+        format_check!("FUNC", ChunkFUNC);
+
+        let version_info = &self.version_info;
+        self.version_report.detected = (version_info.major, version_info.minor, version_info.release, version_info.build);
+        self.container.seek(SeekFrom::Start(return_to))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        chunks::{
+            ChunkOutput, strg::ChunkSTRG, sprt::ChunkSPRT, tpag::ChunkTPAG, feat::ChunkFEAT, tags::ChunkTAGS, code::ChunkCODE,
+            acrv::ChunkACRV, seqn::ChunkSEQN, extn::ChunkEXTN, font::ChunkFONT, tgin::ChunkTGIN, txtr::ChunkTXTR, psem::ChunkPSEM, room::ChunkROOM, func::ChunkFUNC,
+        },
+        models::{
+            sprite::{Sprite, SequenceReference, NineSlice}, animation_curve::{AnimationCurve, Channel, Point}, extension::Extension, font::{Font, Glyph},
+            texture_page_item::TexturePageItem, texture_group_info::TextureGroupInfo, embedded_texture::EmbeddedTexture, particle_system::ParticleEmitter,
+            room::{Room, RoomEntryFlags, GameObject, Layer, LayerType, LayerData, LayerTiles}, code_entry::CodeEntry,
+        },
+        lists::GMPointerList, shared::Shared, verify::tests::{fixture, gen8, optn, version}, GMVersionInfo,
+    };
+    use std::io::Cursor;
+
+    // Writes `chunks` the way `version_info` lays them out, with GEN8 storing 2.0.0.0 like every GMS2 release, and detects the version again
+    fn detect(version_info: GMVersionInfo, chunks: Vec<(&str, ChunkOutput)>) -> VersionReport {
+        let mut all = vec![
+            ("GEN8", gen8(&version(2, 0, 0, 0, version_info.format_id)).into()),
+            ("OPTN", optn().into()),
+        ];
+        all.extend(chunks);
+        if !all.iter().any(|(name, _)| *name == "STRG") {
+            all.push(("STRG", ChunkSTRG::default().into()));
+        }
+        let data = fixture(version_info, all);
+        let mut reader = Reader::new(Cursor::new(data.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        reader.version_report
+    }
+
+    // Chunk, version and part of the reason of every piece of evidence, in order
+    fn assert_evidence(report: &VersionReport, expected: &[(&str, Version, &str)]) {
+        assert_eq!(report.stored, (2, 0, 0, 0));
+        assert_eq!(report.evidence.len(), expected.len(), "{}", report);
+        for (evidence, (chunk, version, reason)) in report.evidence.iter().zip(expected) {
+            assert_eq!((evidence.chunk.as_slice(), evidence.version), (chunk.as_bytes(), *version), "{}", report);
+            assert!(evidence.reason.contains(reason), "{}", report);
+        }
+        assert_eq!(report.detected, expected.last().map_or(report.stored, |(_, version, _)| *version));
+    }
+
+    // Only there to get to 2.3 ahead of the heuristics that need it
+    fn tags() -> (&'static str, ChunkOutput) {
+        ("TAGS", ChunkTAGS::default().into())
+    }
+
+    fn sprite(name: &str) -> Sprite {
+        Sprite {
+            name: name.into(),
+            width: 16,
+            height: 16,
+            margin_right: 15,
+            margin_bottom: 15,
+            special_or_gms2: true,
+            special_version: 1,
+            collision_masks: vec![vec![0xff; 32]],
+            ..Default::default()
+        }
+    }
+
+    fn room(flags: RoomEntryFlags) -> Room {
+        Room {
+            name: "rm_test".into(),
+            width: 320,
+            height: 240,
+            speed: 60,
+            creation_code_id: -1,
+            flags,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detects_chunks_by_presence() {
+        let report = detect(version(2022, 8, 0, 0, 17), vec![("FEAT", ChunkFEAT { features: vec!["Spine".into()] }.into())]);
+        assert_evidence(&report, &[("FORM", (2022, 8, 0, 0), "FEAT chunk is present")]);
+    }
+
+    #[test]
+    fn detects_sprite_sequences() {
+        let mut sprt = ChunkSPRT::default();
+        sprt.sprites.push(Sprite {
+            special_version: 2,
+            gms2_3_sequence: Some(SequenceReference::default()),
+            ..sprite("spr_sequence")
+        });
+        let report = detect(version(2, 3, 0, 0, 17), vec![("SPRT", sprt.into())]);
+        assert_evidence(&report, &[("SPRT", (2, 3, 0, 0), "points at a sequence")]);
+    }
+
+    #[test]
+    fn detects_sprite_nine_slices() {
+        let mut sprt = ChunkSPRT::default();
+        sprt.sprites.push(Sprite {
+            special_version: 3,
+            gms2_3_2_nine_slice: Some(NineSlice::default()),
+            ..sprite("spr_nine_slice")
+        });
+        let report = detect(version(2, 3, 2, 0, 17), vec![("SPRT", sprt.into())]);
+        assert_evidence(&report, &[("SPRT", (2, 3, 2, 0), "points at a nine slice")]);
+    }
+
+    #[test]
+    fn detects_bounding_box_masks() {
+        let sprt = |version_info: &GMVersionInfo| {
+            let mut sprt = ChunkSPRT::default();
+            for name in ["spr_coin", "spr_gem"] {
+                let mut sprite = Sprite {
+                    margin_left: 4,
+                    margin_right: 11,
+                    margin_bottom: 9,
+                    margin_top: 2,
+                    ..sprite(name)
+                };
+                sprite.collision_masks[0] = vec![0; sprite.mask_size(version_info)];
+                sprt.sprites.push(sprite);
+            }
+            sprt
+        };
+        let version_info = version(2024, 6, 0, 0, 17);
+        let report = detect(version_info.clone(), vec![("SPRT", sprt(&version_info).into())]);
+        assert_evidence(&report, &[("SPRT", (2024, 6, 0, 0), "only cover its bounding box")]);
+
+        // Masks of the whole sprite are left alone
+        let version_info = version(2, 0, 0, 0, 17);
+        let report = detect(version_info.clone(), vec![("SPRT", sprt(&version_info).into())]);
+        assert_evidence(&report, &[]);
+    }
+
+    #[test]
+    fn detects_animation_curve_points() {
+        let mut channel = Channel {
+            name: "x".into(),
+            iterations: 16,
+            ..Default::default()
+        };
+        channel.points.push(Point { x: 0.0, value: 0.0, bezier_points: [0.0; 4] });
+        channel.points.push(Point { x: 1.0, value: 1.0, bezier_points: [0.0; 4] });
+        let mut curve = AnimationCurve {
+            name: "ac_ease".into(),
+            ..Default::default()
+        };
+        curve.channels.push(channel);
+        let mut acrv = ChunkACRV {
+            version: 1,
+            ..Default::default()
+        };
+        acrv.animation_curves.push(curve);
+        let report = detect(version(2, 3, 1, 0, 17), vec![("ACRV", acrv.into())]);
+        assert_evidence(&report, &[
+            ("FORM", (2, 3, 0, 0), "ACRV chunk is present"),
+            ("ACRV", (2, 3, 1, 0), "24 byte points"),
+        ]);
+    }
+
+    #[test]
+    fn detects_sequence_track_models() {
+        let mut strg = ChunkSTRG::default();
+        strg.push("GMTextTrack".into());
+        let seqn = ChunkSEQN {
+            version: 1,
+            ..Default::default()
+        };
+        let report = detect(version(2022, 2, 0, 0, 17), vec![("SEQN", seqn.into()), ("STRG", strg.into())]);
+        assert_evidence(&report, &[
+            ("FORM", (2, 3, 0, 0), "SEQN chunk is present"),
+            ("SEQN", (2022, 2, 0, 0), "GMTextTrack"),
+        ]);
+    }
+
+    #[test]
+    fn detects_extension_layouts() {
+        let extn = || {
+            let mut extn = ChunkEXTN::default();
+            extn.extensions.push(Extension {
+                name: "Steamworks".into(),
+                version: "1.0.0".into(),
+                class_name: "YYSteamworks".into(),
+                guid: Some([7; 16]),
+                ..Default::default()
+            });
+            extn
+        };
+        let report = detect(version(2022, 6, 0, 0, 17), vec![tags(), ("EXTN", extn().into())]);
+        assert_evidence(&report, &[
+            ("FORM", (2, 3, 0, 0), "TAGS chunk is present"),
+            ("EXTN", (2022, 6, 0, 0), "files and options pointers follow the class name"),
+        ]);
+
+        let report = detect(version(2023, 4, 0, 0, 17), vec![tags(), ("EXTN", extn().into())]);
+        assert_evidence(&report, &[
+            ("FORM", (2, 3, 0, 0), "TAGS chunk is present"),
+            ("EXTN", (2023, 4, 0, 0), "version before the class name"),
+        ]);
+    }
+
+    #[test]
+    fn detects_font_layout() {
+        let item = Shared::new(TexturePageItem::default());
+        let mut tpag = ChunkTPAG::default();
+        tpag.texture_page_items.push(item.clone());
+        let mut glyphs = GMPointerList::default();
+        for character in ['A', 'B'] {
+            glyphs.push(Glyph {
+                character: character as u16,
+                width: 8,
+                height: 12,
+                ..Default::default()
+            });
+        }
+        let mut font = ChunkFONT::default();
+        font.fonts.push(Font {
+            name: "fnt_main".into(),
+            display_name: "Arial".into(),
+            size_float: 12.0,
+            texture_item: item,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            glyphs,
+            ..Default::default()
+        });
+        let report = detect(version(2022, 2, 0, 0, 17), vec![tags(), ("FONT", font.into()), ("TPAG", tpag.into())]);
+        assert_evidence(&report, &[
+            ("FORM", (2, 3, 0, 0), "TAGS chunk is present"),
+            ("FONT", (2022, 2, 0, 0), "pre-2022.2 font layout"),
+        ]);
+    }
+
+    #[test]
+    fn detects_texture_group_layout() {
+        let mut tgin = ChunkTGIN {
+            version: 1,
+            ..Default::default()
+        };
+        tgin.groups.push(TextureGroupInfo {
+            name: "Default".into(),
+            texture_pages: vec![0],
+            ..Default::default()
+        });
+        let report = detect(version(2022, 9, 0, 0, 17), vec![tags(), ("TGIN", tgin.into())]);
+        assert_evidence(&report, &[
+            ("FORM", (2, 3, 0, 0), "TAGS chunk is present"),
+            ("TGIN", (2022, 9, 0, 0), "directory, extension and load type"),
+        ]);
+    }
+
+    #[test]
+    fn detects_texture_entries_and_formats() {
+        let txtr = |data: &[u8]| {
+            let mut txtr = ChunkTXTR::default();
+            txtr.textures.push(EmbeddedTexture {
+                data: data.to_vec(),
+                ..Default::default()
+            });
+            ("TXTR", txtr.into())
+        };
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x08\0\0\0\x08";
+        let report = detect(version(2022, 9, 0, 0, 17), vec![tags(), txtr(png)]);
+        assert_evidence(&report, &[
+            ("FORM", (2, 3, 0, 0), "TAGS chunk is present"),
+            ("TXTR", (2022, 3, 0, 0), "28 bytes, with a block size"),
+            ("TXTR", (2022, 9, 0, 0), "28 bytes, with a size and index in group"),
+        ]);
+
+        let report = detect(version(2022, 1, 0, 0, 17), vec![tags(), txtr(b"fioq\x08\0\x08\0\0\0\0\0")]);
+        assert_evidence(&report, &[
+            ("FORM", (2, 3, 0, 0), "TAGS chunk is present"),
+            ("TXTR", (2022, 1, 0, 0), "is QOI"),
+        ]);
+
+        // The uncompressed size only shows as four bytes between the dimensions and the BZip2 stream
+        let report = detect(version(2022, 1, 0, 0, 17), vec![tags(), txtr(b"2zoq\x08\0\x08\0BZh9")]);
+        assert_evidence(&report, &[
+            ("FORM", (2, 3, 0, 0), "TAGS chunk is present"),
+            ("TXTR", (2022, 1, 0, 0), "BZip2 compressed QOI"),
+        ]);
+        let report = detect(version(2022, 5, 0, 0, 17), vec![tags(), txtr(b"2zoq\x08\0\x08\0\0\x01\0\0BZh9")]);
+        assert_evidence(&report, &[
+            ("FORM", (2, 3, 0, 0), "TAGS chunk is present"),
+            ("TXTR", (2022, 3, 0, 0), "16 bytes, with a block size"),
+            ("TXTR", (2022, 5, 0, 0), "stores its uncompressed size"),
+        ]);
+    }
+
+    #[test]
+    fn detects_particle_emitter_sizes() {
+        for minor in [4, 6, 8] {
+            let mut psem = ChunkPSEM::default();
+            psem.emitters.push(ParticleEmitter {
+                name: "pe_sparks".into(),
+                ..Default::default()
+            });
+            let report = detect(version(2023, minor, 0, 0, 17), vec![("PSEM", psem.into())]);
+            assert_evidence(&report, &[
+                ("FORM", (2023, 2, 0, 0), "PSEM chunk is present"),
+                ("PSEM", (2023, minor, 0, 0), "Particle emitters are"),
+            ]);
+        }
+    }
+
+    #[test]
+    fn detects_room_object_spacing() {
+        let mut rm = room(RoomEntryFlags::IsGMS2);
+        for instance_id in [100001, 100002] {
+            rm.game_objects.push(GameObject {
+                instance_id,
+                creation_code_id: -1,
+                pre_create_code_id: -1,
+                ..Default::default()
+            });
+        }
+        let mut rooms = ChunkROOM::default();
+        rooms.rooms.push(rm);
+        let report = detect(version(2, 2, 2, 302, 17), vec![("ROOM", rooms.into())]);
+        assert_evidence(&report, &[("ROOM", (2, 2, 2, 302), "spaced for image speed and index")]);
+    }
+
+    #[test]
+    fn detects_gms2_3_rooms() {
+        let mut rooms = ChunkROOM::default();
+        rooms.rooms.push(room(RoomEntryFlags::IsGMS2 | RoomEntryFlags::IsGMS2_3));
+        let report = detect(version(2, 3, 0, 0, 17), vec![("ROOM", rooms.into())]);
+        assert_evidence(&report, &[("ROOM", (2, 3, 0, 0), "flagged as GMS2.3")]);
+    }
+
+    #[test]
+    fn detects_compressed_tiles() {
+        let mut rm = room(RoomEntryFlags::IsGMS2 | RoomEntryFlags::IsGMS2_3);
+        rm.layers.push(Layer {
+            name: "Tiles".into(),
+            id: 1,
+            kind: LayerType::Tiles,
+            data: LayerData::Tiles(LayerTiles {
+                tiles_x: 8,
+                tiles_y: 8,
+                tiles: vec![vec![0; 8]; 8],
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        rm.layers.push(Layer {
+            name: "Path".into(),
+            id: 2,
+            ..Default::default()
+        });
+        let mut rooms = ChunkROOM::default();
+        rooms.rooms.push(rm);
+        // Layers only have effects once something else showed 2022.1
+        let feat = ChunkFEAT { features: Vec::new() };
+        let report = detect(version(2024, 2, 0, 0, 17), vec![("FEAT", feat.into()), ("ROOM", rooms.into())]);
+        assert_evidence(&report, &[
+            ("FORM", (2022, 8, 0, 0), "FEAT chunk is present"),
+            ("ROOM", (2024, 2, 0, 0), "too short for 64 uncompressed tiles"),
+        ]);
+    }
+
+    #[test]
+    fn detects_path2_layers() {
+        let mut rm = room(RoomEntryFlags::IsGMS2 | RoomEntryFlags::IsGMS2_3);
+        rm.layers.push(Layer {
+            name: "Path".into(),
+            id: 1,
+            kind: LayerType::Path2,
+            ..Default::default()
+        });
+        let mut rooms = ChunkROOM::default();
+        rooms.rooms.push(rm);
+        let report = detect(version(2024, 13, 0, 0, 17), vec![("ROOM", rooms.into())]);
+        assert_evidence(&report, &[
+            ("ROOM", (2, 3, 0, 0), "flagged as GMS2.3"),
+            ("ROOM", (2024, 13, 0, 0), "new style path layer"),
+        ]);
+    }
+
+    #[test]
+    fn detects_missing_code_locals() {
+        let mut code = ChunkCODE::default();
+        code.entries.push(CodeEntry {
+            name: "gml_Script_scr_test".into(),
+            length: 4,
+            bytecode: vec![0x00, 0x00, 0x02, 0x9d], // exit.i
+            ..Default::default()
+        });
+        let report = detect(version(2024, 8, 0, 0, 17), vec![("CODE", code.into()), ("FUNC", ChunkFUNC::default().into())]);
+        assert_evidence(&report, &[("FUNC", (2024, 8, 0, 0), "Code locals are missing")]);
+    }
+}
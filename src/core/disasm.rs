@@ -0,0 +1,349 @@
+use crate::core::{
+    reader::Reader,
+    bytecode::{self, Instruction, Opcode, DataType, ComparisonKind, VariableType, ChainLink, Operand},
+    chunks::{code::ChunkCODE, strg::ChunkSTRG, vari::ChunkVARI, func::ChunkFUNC, objt::ChunkOBJT},
+    models::function::LocalVariable,
+    references,
+};
+use bstr::{BStr, ByteSlice};
+use integer_hasher::IntMap;
+use std::{collections::BTreeMap, fmt::Write, io::{Error, ErrorKind, Read, Result, Seek}};
+
+pub fn opcode_name(opcode: Opcode) -> &'static str {
+    match opcode {
+        Opcode::Conv => "conv",
+        Opcode::Mul => "mul",
+        Opcode::Div => "div",
+        Opcode::Rem => "rem",
+        Opcode::Mod => "mod",
+        Opcode::Add => "add",
+        Opcode::Sub => "sub",
+        Opcode::And => "and",
+        Opcode::Or => "or",
+        Opcode::Xor => "xor",
+        Opcode::Neg => "neg",
+        Opcode::Not => "not",
+        Opcode::Shl => "shl",
+        Opcode::Shr => "shr",
+        Opcode::Cmp => "cmp",
+        Opcode::Pop => "pop",
+        Opcode::PushI => "pushi",
+        Opcode::Dup => "dup",
+        Opcode::CallV => "callv",
+        Opcode::Ret => "ret",
+        Opcode::Exit => "exit",
+        Opcode::Popz => "popz",
+        Opcode::B => "b",
+        Opcode::Bt => "bt",
+        Opcode::Bf => "bf",
+        Opcode::PushEnv => "pushenv",
+        Opcode::PopEnv => "popenv",
+        Opcode::Push => "push",
+        Opcode::PushLoc => "pushloc",
+        Opcode::PushGlb => "pushglb",
+        Opcode::PushBltn => "pushbltn",
+        Opcode::Call => "call",
+        Opcode::Break => "break",
+        _ => "???",
+    }
+}
+
+pub fn type_name(data_type: DataType) -> &'static str {
+    match data_type {
+        DataType::Double => "d",
+        DataType::Float => "f",
+        DataType::Int32 => "i",
+        DataType::Int64 => "l",
+        DataType::Boolean => "b",
+        DataType::Variable => "v",
+        DataType::String => "s",
+        DataType::Instance => "inst",
+        DataType::Delete => "delete",
+        DataType::Undefined => "undefined",
+        DataType::UnsignedInt => "u",
+        DataType::Int16 => "e",
+        _ => "?",
+    }
+}
+
+pub fn comparison_name(comparison: ComparisonKind) -> &'static str {
+    match comparison {
+        ComparisonKind::Less => "LT",
+        ComparisonKind::LessEqual => "LTE",
+        ComparisonKind::Equal => "EQ",
+        ComparisonKind::NotEqual => "NEQ",
+        ComparisonKind::GreaterEqual => "GTE",
+        ComparisonKind::Greater => "GT",
+        _ => "???",
+    }
+}
+
+// Extended opcodes are encoded as a break with a negative value
+pub const EXTENDED_OPCODES: [(i16, &str); 11] = [
+    (-1, "chkindex"),
+    (-2, "pushaf"),
+    (-3, "popaf"),
+    (-4, "pushac"),
+    (-5, "setowner"),
+    (-6, "isstaticok"),
+    (-7, "setstatic"),
+    (-8, "savearef"),
+    (-9, "restorearef"),
+    (-10, "chknullish"),
+    (-11, "pushref"),
+];
+
+pub fn instance_name(instance: i16) -> Option<&'static str> {
+    match instance {
+        -1 => Some("self"),
+        -2 => Some("other"),
+        -3 => Some("all"),
+        -4 => Some("noone"),
+        -5 => Some("global"),
+        -6 => Some("builtin"),
+        -7 => Some("local"),
+        -9 => Some("stacktop"),
+        -15 => Some("arg"),
+        -16 => Some("static"),
+        _ => None,
+    }
+}
+
+pub fn variable_type_prefix(kind: VariableType) -> &'static str {
+    match kind {
+        VariableType::Array => "[array]",
+        VariableType::StackTop => "[stacktop]",
+        VariableType::Instance => "[instance]",
+        VariableType::MultiPush => "[multipush]",
+        VariableType::MultiPushPop => "[multipushpop]",
+        _ => "",
+    }
+}
+
+// Bytes that aren't valid UTF-8 are escaped as \xNN, so they survive being assembled again
+pub fn escape_string(string: &BStr) -> String {
+    let mut escaped = String::with_capacity(string.len() + 2);
+    escaped.push('"');
+    for chunk in string.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                _ => escaped.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            let _ = write!(escaped, "\\x{:02x}", byte);
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+pub struct Disassembler<'a> {
+    pub code: &'a ChunkCODE,
+    pub strings: Option<&'a ChunkSTRG>,
+    pub variables: Option<&'a ChunkVARI>,
+    pub functions: Option<&'a ChunkFUNC>,
+    pub objects: Option<&'a ChunkOBJT>,
+    pub format_id: i8,
+    variable_map: IntMap<u64, usize>, // Address of each referencing instruction to its variable
+    function_map: IntMap<u64, usize>, // Address of each referencing instruction to its function
+}
+
+impl<'a> Disassembler<'a> {
+    pub fn new<R>(reader: &'a Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let code = reader.code().ok_or_else(|| Error::new(ErrorKind::NotFound, "The CODE chunk was not loaded"))?;
+        let variables = reader.variables();
+        let functions = reader.functions();
+        let variable_map = match variables {
+            Some(variables) => references::reference_map(&variables.references(code)?),
+            None => IntMap::default(),
+        };
+        let function_map = match functions {
+            Some(functions) => references::reference_map(&functions.references(code)?),
+            None => IntMap::default(),
+        };
+
+        Ok(Self {
+            code,
+            strings: reader.strings(),
+            variables,
+            functions,
+            objects: reader.objects(),
+            format_id: reader.version_info.format_id,
+            variable_map,
+            function_map,
+        })
+    }
+
+    // Decoded instructions of a code entry, offsets being relative to the bytecode it shares with its parent
+    pub fn instructions(&self, index: usize) -> Result<Vec<Instruction>> {
+        let entry = self.code.entries.values.get(index)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("Code entry {} does not exist", index)))?;
+        let root = self.code.root(index);
+        let mut instructions = bytecode::decode(&root.bytecode, entry.offset, self.format_id)?;
+        bytecode::resolve_references(&mut instructions, root.bytecode_address, &self.variable_map, &self.function_map);
+
+        Ok(instructions)
+    }
+
+    pub fn disassemble(&self, index: usize) -> Result<String> {
+        let instructions = self.instructions(index)?;
+        let start = self.code.entries[index].offset;
+        let end = self.code.root(index).bytecode.len() as u32;
+
+        // Labels are numbered in address order, with the start of the entry always being the first one
+        let mut labels = BTreeMap::new();
+        labels.insert(start, String::new());
+        let children: BTreeMap<u32, usize> = self.code.children(self.code.entries[index].parent.unwrap_or(index))
+            .filter(|(_, child)| child.offset > start)
+            .map(|(child, entry)| (entry.offset, child))
+            .collect();
+        for offset in children.keys() {
+            labels.insert(*offset, String::new());
+        }
+        for instruction in instructions.iter() {
+            if let Some(target) = instruction.target() {
+                labels.insert(target, String::new());
+            }
+        }
+        let mut count = 0;
+        for (target, label) in labels.iter_mut() {
+            if *target == end {
+                *label = String::from("[end]");
+            } else {
+                *label = format!("[{}]", count);
+                count += 1;
+            }
+        }
+
+        let mut output = String::new();
+        for local in self.locals(index) {
+            let _ = writeln!(output, ".localvar {} {}", local.index, local.name);
+        }
+        let mut first = true;
+        for instruction in instructions.iter() {
+            if let Some(child) = children.get(&instruction.offset) {
+                let entry = &self.code.entries[*child];
+                let _ = write!(output, "\n> {} (locals={}, argc={})\n", entry.name, entry.locals_count, entry.arguments_count);
+            }
+            if let Some(label) = labels.get(&instruction.offset) {
+                if !first && !children.contains_key(&instruction.offset) {
+                    output.push('\n');
+                }
+                let _ = writeln!(output, ":{}", label);
+            }
+            first = false;
+            let _ = writeln!(output, "{}", self.format_instruction(instruction, &labels));
+        }
+        if let Some(label) = labels.get(&end) {
+            let _ = write!(output, "\n:{}\n", label);
+        }
+
+        Ok(output)
+    }
+
+    // Locals declared by a code entry, as listed in the FUNC chunk
+    pub fn locals(&self, index: usize) -> &'a [LocalVariable] {
+        let name = &self.code.entries[index].name;
+        self.functions
+            .and_then(|functions| functions.code_locals.values.iter().find(|locals| &locals.name == name))
+            .map(|locals| locals.locals.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn format_instruction(&self, instruction: &Instruction, labels: &BTreeMap<u32, String>) -> String {
+        let name = opcode_name(instruction.opcode);
+        let t1 = type_name(instruction.type1);
+        let t2 = type_name(instruction.type2);
+        match instruction.opcode {
+            Opcode::Conv | Opcode::Mul | Opcode::Div | Opcode::Rem | Opcode::Mod | Opcode::Add | Opcode::Sub |
+            Opcode::And | Opcode::Or | Opcode::Xor | Opcode::Shl | Opcode::Shr => format!("{}.{}.{}", name, t1, t2),
+            Opcode::Cmp => format!("{}.{}.{} {}", name, t1, t2, comparison_name(instruction.comparison)),
+            Opcode::Neg | Opcode::Not | Opcode::Ret | Opcode::Exit | Opcode::Popz => format!("{}.{}", name, t1),
+            Opcode::Dup if instruction.dup_mode != 0 => format!("{}.{} {} {}", name, t1, instruction.value, instruction.dup_mode),
+            Opcode::Dup | Opcode::CallV => format!("{}.{} {}", name, t1, instruction.value),
+            Opcode::B | Opcode::Bt | Opcode::Bf | Opcode::PushEnv | Opcode::PopEnv => {
+                match instruction.target() {
+                    None => format!("{} [drop]", name),
+                    Some(target) => match labels.get(&target) {
+                        Some(label) => format!("{} {}", name, label),
+                        None => format!("{} [@{:#x}]", name, target),
+                    },
+                }
+            }
+            Opcode::Pop => match instruction.operand {
+                Operand::Variable(link) => format!("{}.{}.{} {}", name, t1, t2, self.format_variable(instruction.value, &link)),
+                _ => format!("{}.{}.{} {}", name, t1, t2, instruction.value),
+            },
+            Opcode::Push | Opcode::PushLoc | Opcode::PushGlb | Opcode::PushBltn | Opcode::PushI => {
+                format!("{}.{} {}", name, t1, self.format_operand(instruction))
+            }
+            Opcode::Call => match instruction.operand {
+                Operand::Function(link) => format!("{}.{} {}(argc={})", name, t1, self.function_name(&link), instruction.value as u16),
+                _ => format!("{}.{} ???(argc={})", name, t1, instruction.value as u16),
+            },
+            Opcode::Break => {
+                let extended = EXTENDED_OPCODES.iter().find(|(value, _)| *value == instruction.value).map(|(_, name)| *name);
+                let mut text = match extended {
+                    Some(extended) => format!("{}.{}", extended, t1),
+                    None => format!("{}.{} {}", name, t1, instruction.value),
+                };
+                if instruction.operand != Operand::None {
+                    text.push(' ');
+                    text.push_str(&self.format_operand(instruction));
+                }
+                text
+            }
+            _ => format!("{} ; unknown opcode {:#04x}", name, instruction.opcode.bits()),
+        }
+    }
+
+    fn format_operand(&self, instruction: &Instruction) -> String {
+        match instruction.operand {
+            Operand::None => instruction.value.to_string(),
+            Operand::Double(value) => value.to_string(),
+            Operand::Float(value) => value.to_string(),
+            Operand::Int32(value) => value.to_string(),
+            Operand::Int64(value) => value.to_string(),
+            Operand::Boolean(value) => value.to_string(),
+            Operand::String(id) => match self.strings.and_then(|strings| strings.get(id as usize)) {
+                Some(string) => format!("{}@{}", escape_string(string.as_bstr()), id),
+                None => format!("<string {}>@{}", id, id),
+            },
+            Operand::Variable(link) => self.format_variable(instruction.value, &link),
+            Operand::Function(link) => format!("[function]{}", self.function_name(&link)),
+        }
+    }
+
+    fn format_variable(&self, instance: i16, link: &ChainLink) -> String {
+        let instance = match instance_name(instance) {
+            Some(name) => name.to_string(),
+            None => match self.objects.and_then(|objects| objects.objects.values.get(instance as usize)) {
+                Some(object) if instance >= 0 => object.name.to_string(),
+                _ => instance.to_string(),
+            },
+        };
+        format!("{}{}.{}", variable_type_prefix(link.kind), instance, self.variable_name(link))
+    }
+
+    pub fn variable_name(&self, link: &ChainLink) -> String {
+        match link.index.and_then(|index| self.variables.and_then(|variables| variables.variables.get(index))) {
+            Some(variable) => variable.name.to_string(),
+            None => format!("<variable {:#x}>", link.raw),
+        }
+    }
+
+    pub fn function_name(&self, link: &ChainLink) -> String {
+        match link.index.and_then(|index| self.functions.and_then(|functions| functions.functions.values.get(index))) {
+            Some(function) => function.name.to_string(),
+            None => format!("<function {:#x}>", link.raw),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use crate::core::{reader::Reader, writer::Writer};
+use bstr::BString;
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::{error, fmt, io::{self, ErrorKind, Read, Seek, Write}};
+
+pub type ClovyResult<T> = Result<T, ClovyError>;
+
+// An error located in the data file, built up while it bubbles out of the models and lists
+#[derive(Debug)]
+pub struct ClovyError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub chunk: Option<BString>, // Chunk being read or written
+    pub offset: Option<u64>, // Absolute offset of the innermost model that failed, or where the chunk stopped
+    pub index: Option<usize>, // Index of the resource in its chunk
+    pub path: Vec<PathSegment>, // From the resource down to the innermost field, such as Sprite[3].collision_masks[0]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathSegment {
+    pub name: String, // Field name, or the type name until a field claims it
+    pub index: Option<usize>,
+    pub field: bool,
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(index) = self.index {
+            write!(f, "[{}]", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl ClovyError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            chunk: None,
+            offset: None,
+            index: None,
+            path: Vec::new(),
+        }
+    }
+
+    // Adds a model or list element on the outside of the path, `index` being its position in the list holding it
+    pub fn within(mut self, type_name: &str, index: Option<usize>, offset: Option<u64>) -> Self {
+        self.path.insert(0, PathSegment {
+            name: short_type_name(type_name),
+            index,
+            field: false,
+        });
+        // The outermost list is the one of the chunk, so its index is the one that sticks
+        if index.is_some() {
+            self.index = index;
+        }
+        // The innermost offset is the closest to what went wrong
+        if self.offset.is_none() {
+            self.offset = offset;
+        }
+        self
+    }
+
+    // Names the field that was being read, which takes the place of the list or model type it was read as
+    pub fn in_field(mut self, name: &str, index: Option<usize>) -> Self {
+        // Lists read through a pointer show up as their type, followed by the element
+        while self.path.first().is_some_and(|segment| !segment.field && segment.index.is_none()) && self.path.len() > 1 {
+            self.path.remove(0);
+        }
+        match self.path.first_mut() {
+            Some(segment) if !segment.field => {
+                segment.name = name.to_string();
+                segment.index = index.or(segment.index);
+                segment.field = true;
+            }
+            _ => self.path.insert(0, PathSegment {
+                name: name.to_string(),
+                index,
+                field: true,
+            }),
+        }
+        self
+    }
+
+    pub fn in_chunk(mut self, chunk: BString, offset: Option<u64>) -> Self {
+        self.chunk = Some(chunk);
+        if self.offset.is_none() {
+            self.offset = offset;
+        }
+        self
+    }
+}
+
+impl fmt::Display for ClovyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(chunk) = &self.chunk {
+            write!(f, " in {}", chunk)?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " at {:#x}", offset)?;
+        }
+        if let Some(index) = self.index {
+            write!(f, ", resource {}", index)?;
+        }
+        if !self.path.is_empty() {
+            let path = self.path.iter().map(|segment| segment.to_string()).collect::<Vec<_>>();
+            write!(f, " ({})", path.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ClovyError {}
+
+impl From<io::Error> for ClovyError {
+    fn from(error: io::Error) -> Self {
+        // Errors that already went through a list come back wrapped in an io::Error
+        if error.get_ref().is_some_and(|inner| inner.is::<ClovyError>()) {
+            if let Some(Ok(inner)) = error.into_inner().map(|inner| inner.downcast::<ClovyError>()) {
+                return *inner;
+            }
+            return Self::new(ErrorKind::Other, "Lost the context of an error");
+        }
+        Self::new(error.kind(), error.to_string())
+    }
+}
+
+impl From<ClovyError> for io::Error {
+    fn from(error: ClovyError) -> Self {
+        io::Error::new(error.kind, error)
+    }
+}
+
+// Strips the module paths out of a type name, "clovy::core::lists::GMPointerList<clovy::core::models::sprite::Sprite>" becomes "GMPointerList<Sprite>"
+pub fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut segment = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            segment.push(c);
+        } else {
+            short.push_str(segment.rsplit("::").next().unwrap_or_default());
+            segment.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(segment.rsplit("::").next().unwrap_or_default());
+    short
+}
+
+// Names the field a model is reading when an error comes out of it, as in `reader.read_u32().field("count")?`
+pub trait ErrorContext<T> {
+    fn field(self, name: &str) -> io::Result<T>;
+    // For fields holding a list read by hand, `index` being the element
+    fn element(self, name: &str, index: usize) -> io::Result<T>;
+}
+
+impl<T> ErrorContext<T> for io::Result<T> {
+    fn field(self, name: &str) -> io::Result<T> {
+        self.map_err(|e| ClovyError::from(e).in_field(name, None).into())
+    }
+
+    fn element(self, name: &str, index: usize) -> io::Result<T> {
+        self.map_err(|e| ClovyError::from(e).in_field(name, Some(index)).into())
+    }
+}
+
+impl<T> Reader<T>
+    where T: Read + Seek + ReadBytesExt,
+{
+    // Locates an error raised while reading a `type_name` starting at `offset`, element `index` of its list if it's in one
+    pub fn locate_error(&mut self, error: io::Error, type_name: &str, index: Option<usize>, offset: u64) -> io::Error {
+        ClovyError::from(error).within(type_name, index, Some(offset)).into()
+    }
+
+    pub fn locate_chunk_error(&mut self, error: io::Error, chunk: &BString) -> io::Error {
+        let offset = self.stream_position().ok();
+        ClovyError::from(error).in_chunk(chunk.clone(), offset).into()
+    }
+}
+
+impl<T> Writer<T>
+    where T: Write + WriteBytesExt + Seek,
+{
+    pub fn locate_error(&mut self, error: io::Error, type_name: &str, index: Option<usize>, offset: u64) -> io::Error {
+        ClovyError::from(error).within(type_name, index, Some(offset)).into()
+    }
+
+    pub fn locate_chunk_error(&mut self, error: io::Error, chunk: &BString) -> io::Error {
+        let offset = self.stream_position().ok();
+        ClovyError::from(error).in_chunk(chunk.clone(), offset).into()
+    }
+}
@@ -0,0 +1,275 @@
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
+use bzip2::read::BzDecoder;
+
+pub const PNG_MAGIC: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+pub const QOI_MAGIC: [u8; 4] = *b"fioq"; // GameMaker stores "qoif" reversed
+pub const BZ2_QOI_MAGIC: [u8; 4] = *b"2zoq";
+pub const BZ2_MAGIC: [u8; 3] = *b"BZh";
+pub const MAX_IMAGE_BYTES: usize = 16384 * 16384 * 4; // Largest texture page GameMaker builds, as RGBA8
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Qoi, // From 2022.1>=
+    Bz2Qoi, // From 2022.1>=, with the uncompressed size in the header from 2022.5>=
+    Unknown,
+}
+
+#[derive(Default, Clone)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>, // RGBA8, row by row
+}
+
+pub fn detect_format(blob: &[u8]) -> ImageFormat {
+    if blob.starts_with(&PNG_MAGIC) {
+        ImageFormat::Png
+    } else if blob.starts_with(&QOI_MAGIC) {
+        ImageFormat::Qoi
+    } else if blob.starts_with(&BZ2_QOI_MAGIC) {
+        ImageFormat::Bz2Qoi
+    } else {
+        ImageFormat::Unknown
+    }
+}
+
+pub fn image_size(blob: &[u8]) -> Result<(u32, u32)> {
+    match detect_format(blob) {
+        ImageFormat::Png => {
+            // The IHDR chunk always comes first, right after the signature
+            let header = blob.get(16..24).ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "Truncated PNG header"))?;
+            Ok((
+                u32::from_be_bytes([header[0], header[1], header[2], header[3]]),
+                u32::from_be_bytes([header[4], header[5], header[6], header[7]]),
+            ))
+        }
+        ImageFormat::Qoi | ImageFormat::Bz2Qoi => {
+            let header = blob.get(4..8).ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "Truncated QOI header"))?;
+            Ok((
+                u16::from_le_bytes([header[0], header[1]]) as u32,
+                u16::from_le_bytes([header[2], header[3]]) as u32,
+            ))
+        }
+        ImageFormat::Unknown => Err(Error::new(ErrorKind::InvalidData, "Unknown image format")),
+    }
+}
+
+pub fn decode(blob: &[u8]) -> Result<Image> {
+    match detect_format(blob) {
+        ImageFormat::Png => decode_png(blob),
+        ImageFormat::Qoi => decode_qoi(blob),
+        ImageFormat::Bz2Qoi => decode_bz2_qoi(blob),
+        ImageFormat::Unknown => Err(Error::new(ErrorKind::InvalidData, "Unknown image format")),
+    }
+}
+
+pub fn decode_png(blob: &[u8]) -> Result<Image> {
+    let mut decoder = png::Decoder::new(Cursor::new(blob));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let info = reader.info();
+    pixel_bytes(info.width, info.height)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    buffer.truncate(info.buffer_size());
+
+    let data = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 0xff]).collect(),
+        png::ColorType::GrayscaleAlpha => buffer.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|p| [*p, *p, *p, 0xff]).collect(),
+        png::ColorType::Indexed => {
+            return Err(Error::new(ErrorKind::InvalidData, "Indexed PNG was not expanded"));
+        }
+    };
+
+    Ok(Image {
+        width: info.width,
+        height: info.height,
+        data,
+    })
+}
+
+// Size of the RGBA8 pixels of a `width`x`height` image, rejecting sizes no texture page can have
+fn pixel_bytes(width: u32, height: u32) -> Result<usize> {
+    (width as usize).checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .filter(|size| *size <= MAX_IMAGE_BYTES)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Image size {}x{} is too large", width, height)))
+}
+
+// Sign-extends the lowest `bits` bits of `value`
+fn signed(value: u32, bits: u32) -> u8 {
+    (((value << (32 - bits)) as i32) >> (32 - bits)) as u8
+}
+
+pub fn decode_qoi(blob: &[u8]) -> Result<Image> {
+    if !blob.starts_with(&QOI_MAGIC) || blob.len() < 12 {
+        return Err(Error::new(ErrorKind::InvalidData, "Invalid QOI header"));
+    }
+    let width = u16::from_le_bytes([blob[4], blob[5]]) as u32;
+    let height = u16::from_le_bytes([blob[6], blob[7]]) as u32;
+    let length = u32::from_le_bytes([blob[8], blob[9], blob[10], blob[11]]) as usize;
+    let data = blob.get(12..12 + length).ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "Truncated QOI data"))?;
+
+    let mut pos = 0;
+    let next = |pos: &mut usize| -> Result<u8> {
+        let value = *data.get(*pos).ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "Truncated QOI data"))?;
+        *pos += 1;
+        Ok(value)
+    };
+    let size = pixel_bytes(width, height)?;
+    let mut pixels = Vec::with_capacity(size);
+    let mut index = [[0u8; 4]; 64];
+    let (mut r, mut g, mut b, mut a) = (0u8, 0u8, 0u8, 0xffu8);
+    let mut run = 0usize;
+    for _ in 0..size / 4 {
+        // Once the data runs out, the last pixel is repeated
+        if run > 0 {
+            run -= 1;
+        } else if pos < data.len() {
+            let b1 = next(&mut pos)?;
+            if b1 & 0xc0 == 0x00 { // Index
+                [r, g, b, a] = index[(b1 & 0x3f) as usize];
+            } else if b1 & 0xe0 == 0x40 { // Run (8 bits)
+                run = (b1 & 0x1f) as usize;
+            } else if b1 & 0xe0 == 0x60 { // Run (16 bits)
+                let b2 = next(&mut pos)?;
+                run = ((((b1 & 0x1f) as usize) << 8) | b2 as usize) + 32;
+            } else if b1 & 0xc0 == 0x80 { // Difference (8 bits)
+                r = r.wrapping_add(signed((b1 as u32 >> 4) & 0x3, 2));
+                g = g.wrapping_add(signed((b1 as u32 >> 2) & 0x3, 2));
+                b = b.wrapping_add(signed(b1 as u32 & 0x3, 2));
+            } else if b1 & 0xe0 == 0xc0 { // Difference (16 bits)
+                let merged = (b1 as u32) << 8 | next(&mut pos)? as u32;
+                r = r.wrapping_add(signed((merged >> 8) & 0x1f, 5));
+                g = g.wrapping_add(signed((merged >> 4) & 0xf, 4));
+                b = b.wrapping_add(signed(merged & 0xf, 4));
+            } else if b1 & 0xf0 == 0xe0 { // Difference (24 bits)
+                let merged = (b1 as u32) << 16 | (next(&mut pos)? as u32) << 8 | next(&mut pos)? as u32;
+                r = r.wrapping_add(signed((merged >> 15) & 0x1f, 5));
+                g = g.wrapping_add(signed((merged >> 10) & 0x1f, 5));
+                b = b.wrapping_add(signed((merged >> 5) & 0x1f, 5));
+                a = a.wrapping_add(signed(merged & 0x1f, 5));
+            } else { // Color
+                if b1 & 0x8 != 0 { r = next(&mut pos)?; }
+                if b1 & 0x4 != 0 { g = next(&mut pos)?; }
+                if b1 & 0x2 != 0 { b = next(&mut pos)?; }
+                if b1 & 0x1 != 0 { a = next(&mut pos)?; }
+            }
+            index[((r ^ g ^ b ^ a) & 0x3f) as usize] = [r, g, b, a];
+        }
+        pixels.extend_from_slice(&[r, g, b, a]);
+    }
+
+    Ok(Image {
+        width,
+        height,
+        data: pixels,
+    })
+}
+
+pub fn decode_bz2_qoi(blob: &[u8]) -> Result<Image> {
+    if !blob.starts_with(&BZ2_QOI_MAGIC) || blob.len() < 8 {
+        return Err(Error::new(ErrorKind::InvalidData, "Invalid BZip2 QOI header"));
+    }
+    // From 2022.5>= the header also stores the uncompressed size
+    let header_size = if blob.get(8..11) == Some(&BZ2_MAGIC) { 8 } else { 12 };
+    let mut qoi = Vec::new();
+    // The QOI header and data can't be larger than the biggest image, so anything past it is garbage
+    BzDecoder::new(blob.get(header_size..).unwrap_or_default()).take((MAX_IMAGE_BYTES * 2) as u64).read_to_end(&mut qoi)?;
+    decode_qoi(&qoi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2x1 RGBA: opaque red, then half transparent green
+    const PNG_RGBA: &[u8] = b"\x89\x50\x4e\x47\x0d\x0a\x1a\x0a\x00\x00\x00\x0d\x49\x48\x44\x52\x00\x00\x00\x02\x00\x00\x00\x01\x08\x06\x00\x00\x00\xf4\x22\x7f\x8a\x00\x00\x00\x0f\x49\x44\x41\x54\x78\xda\x63\xf8\xcf\xc0\xf0\x1f\x08\x1b\x00\x10\x79\x03\x7e\xc4\x18\x72\x90\x00\x00\x00\x00\x49\x45\x4e\x44\xae\x42\x60\x82";
+    // 1x2 RGB: (1, 2, 3) above (4, 5, 6)
+    const PNG_RGB: &[u8] = b"\x89\x50\x4e\x47\x0d\x0a\x1a\x0a\x00\x00\x00\x0d\x49\x48\x44\x52\x00\x00\x00\x01\x00\x00\x00\x02\x08\x02\x00\x00\x00\x16\xe3\x21\x70\x00\x00\x00\x10\x49\x44\x41\x54\x78\xda\x63\x60\x64\x62\x66\x60\x61\x65\x03\x00\x00\x46\x00\x16\x9f\xf4\x67\xf0\x00\x00\x00\x00\x49\x45\x4e\x44\xae\x42\x60\x82";
+    // 20000x20000 RGBA, which is past any texture page
+    const PNG_HUGE: &[u8] = b"\x89\x50\x4e\x47\x0d\x0a\x1a\x0a\x00\x00\x00\x0d\x49\x48\x44\x52\x00\x00\x4e\x20\x00\x00\x4e\x20\x08\x06\x00\x00\x00\xe3\x70\x46\x39\x00\x00\x00\x0b\x49\x44\x41\x54\x78\x9c\x63\x60\x40\x05\x00\x00\x10\x00\x01\x39\xbd\x8f\x65\x00\x00\x00\x00\x49\x45\x4e\x44\xae\x42\x60\x82";
+    // 4x2, one of each opcode: color, 8, 16 and 24 bit differences, index and run, after which the data runs out
+    const QOI: &[u8] = b"fioq\x04\x00\x02\x00\x0d\x00\x00\x00\xff\x10\x20\x30\xff\x93\xdd\x58\xe0\xfc\x50\x21\x41";
+    // QOI, compressed with BZip2 at block size 9
+    const BZ2: &[u8] = b"\x42\x5a\x68\x39\x31\x41\x59\x26\x53\x59\xcf\xa4\x44\x02\x00\x00\x07\xff\xa3\xd4\x02\x40\x00\x60\x00\x40\x00\x20\x00\x40\x40\x01\x20\xa0\x00\x08\x00\x00\x02\x40\x00\x00\x04\xa0\x00\x22\x9a\x7a\x80\x19\x0d\xa8\x53\x09\xa6\x80\xd3\x10\x20\x12\x83\x21\xae\xaf\x82\xeb\xce\xfb\x2e\xe5\x8f\xc5\xdc\x91\x4e\x14\x24\x33\xe9\x11\x00\x80";
+
+    fn qoi_pixels() -> Vec<u8> {
+        let mut pixels = vec![
+            0x10, 0x20, 0x30, 0xff, // Color
+            0x11, 0x20, 0x2f, 0xff, // +1, 0, -1
+            0x0e, 0x25, 0x27, 0xff, // -3, +5, -8
+            0x0f, 0x24, 0x29, 0xef, // +1, -1, +2, -16
+        ];
+        // Index of the second pixel, repeated by the run and then once the data runs out
+        for _ in 0..4 {
+            pixels.extend_from_slice(&[0x11, 0x20, 0x2f, 0xff]);
+        }
+        pixels
+    }
+
+    fn assert_image(image: &Image, width: u32, height: u32, data: &[u8]) {
+        assert_eq!((image.width, image.height), (width, height));
+        assert_eq!(image.data, data);
+    }
+
+    #[test]
+    fn decodes_png() {
+        assert_eq!(detect_format(PNG_RGBA), ImageFormat::Png);
+        assert_eq!(image_size(PNG_RGBA).unwrap(), (2, 1));
+        assert_image(&decode(PNG_RGBA).unwrap(), 2, 1, &[0xff, 0, 0, 0xff, 0, 0xff, 0, 0x80]);
+        // Pixels without alpha come out opaque
+        assert_image(&decode(PNG_RGB).unwrap(), 1, 2, &[1, 2, 3, 0xff, 4, 5, 6, 0xff]);
+    }
+
+    #[test]
+    fn decodes_qoi() {
+        assert_eq!(detect_format(QOI), ImageFormat::Qoi);
+        assert_eq!(image_size(QOI).unwrap(), (4, 2));
+        assert_image(&decode(QOI).unwrap(), 4, 2, &qoi_pixels());
+
+        // 16 bit runs count from 32
+        let long_run = b"fioq\x28\x00\x01\x00\x07\x00\x00\x00\xff\x01\x02\x03\x04\x60\x06";
+        assert_image(&decode(long_run).unwrap(), 40, 1, &[1, 2, 3, 4].repeat(40));
+
+        assert!(decode_qoi(&QOI[..8]).is_err());
+        assert!(decode_qoi(&QOI[..QOI.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn decodes_bz2_qoi() {
+        // Before 2022.5 the BZip2 stream follows the dimensions, afterwards the uncompressed size comes first
+        let mut short = b"2zoq\x04\x00\x02\x00".to_vec();
+        short.extend_from_slice(BZ2);
+        let mut long = b"2zoq\x04\x00\x02\x00".to_vec();
+        long.extend_from_slice(&(QOI.len() as u32).to_le_bytes());
+        long.extend_from_slice(BZ2);
+        for blob in [short, long] {
+            assert_eq!(detect_format(&blob), ImageFormat::Bz2Qoi);
+            assert_eq!(image_size(&blob).unwrap(), (4, 2));
+            assert_image(&decode(&blob).unwrap(), 4, 2, &qoi_pixels());
+        }
+
+        assert!(decode_bz2_qoi(b"2zoq\x04\x00\x02\x00BZh9garbage").is_err());
+    }
+
+    #[test]
+    fn rejects_images_past_the_largest_texture_page() {
+        assert_eq!(pixel_bytes(16384, 16384).unwrap(), MAX_IMAGE_BYTES);
+        assert!(pixel_bytes(16385, 16384).is_err());
+        assert!(pixel_bytes(u32::MAX, u32::MAX).is_err());
+
+        // Both are turned down before anything gets allocated for their pixels
+        for (blob, size) in [(PNG_HUGE, "20000x20000"), (b"fioq\xff\xff\xff\xff\x00\x00\x00\x00".as_slice(), "65535x65535")] {
+            let Err(error) = decode(blob) else {
+                panic!("The {} image went through", size);
+            };
+            assert_eq!(error.to_string(), format!("Image size {} is too large", size));
+        }
+        assert_eq!(detect_format(b"GIF89a"), ImageFormat::Unknown);
+        assert!(decode(b"GIF89a").is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use super::{reader::Reader, serializing::Serialize, writer::Writer};
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Seek, SeekFrom, Result}, ops::Index};
+use std::{any::type_name, io::{Read, Seek, SeekFrom, Result, Write}, ops::Index};
 
 #[derive(Clone)]
 pub struct GMPointerList<T> {
@@ -43,7 +43,8 @@ impl<T> GMPointerList<T>
                 script(reader, *ptr as _, index, size)?;
             }
             reader.seek(SeekFrom::Start(*ptr as _))?;
-            self.values.push(T::deserialize(reader)?);
+            let value = T::deserialize(reader).map_err(|e| reader.locate_error(e, type_name::<T>(), Some(index), *ptr as _))?;
+            self.values.push(value);
             if let Some(script) = script_after.as_mut() {
                 script(reader, *ptr as _, index, size)?;
             }
@@ -52,10 +53,12 @@ impl<T> GMPointerList<T>
         Ok(())
     }
 
+    #[track_caller]
     pub fn serialize<W>(&self, writer: &mut Writer<W>, mut script_before: Option<WriterScriptBefore<W>>, mut script_after: Option<WriterScriptAfter<W>>) -> Result<()>
     where
         W: Write + WriteBytesExt + Seek,
     {
+        writer.register_pointer(self)?;
         writer.write_u32(self.values.len() as _)?;
         let offset = writer.stream_position()?;
         for _ in 0..self.values.len() {
@@ -70,7 +73,9 @@ impl<T> GMPointerList<T>
             writer.seek(SeekFrom::Start(offset + (index * 4) as u64))?;
             writer.write_u32(current_offset as _)?;
             writer.seek(SeekFrom::Start(current_offset))?;
-            T::serialize(value, writer)?;
+            writer.register_pointer_object(value)?;
+            T::serialize(value, writer).map_err(|e| writer.locate_error(e, type_name::<T>(), Some(index), current_offset))?;
+            writer.trace_element(type_name::<T>(), index, current_offset)?;
             if let Some(script) = script_after.as_mut() {
                 script(writer, index, size)?;
             }
@@ -153,7 +158,8 @@ impl<T> GMSimpleList<T>
             if let Some(script) = script_before.as_mut() {
                 script(reader, pos, index as _, size as _)?;
             }
-            self.values.push(T::deserialize(reader)?);
+            let value = T::deserialize(reader).map_err(|e| reader.locate_error(e, type_name::<T>(), Some(index as _), pos))?;
+            self.values.push(value);
             if let Some(script) = script_after.as_mut() {
                 script(reader, pos, index as _, size as _)?;
             }
@@ -162,16 +168,21 @@ impl<T> GMSimpleList<T>
         Ok(())
     }
 
+    #[track_caller]
     pub fn serialize<W>(&self, writer: &mut Writer<W>, mut script_before: Option<WriterScriptBefore<W>>, mut script_after: Option<WriterScriptAfter<W>>) -> Result<()>
         where W: Write + WriteBytesExt + Seek,
     {
         let size = self.values.len();
+        writer.register_pointer(self)?;
         writer.write_u32(self.values.len() as _)?;
         for (index, value) in self.values.iter().enumerate() {
             if let Some(script) = script_before.as_mut() {
                 script(writer, index as _, size)?;
             }
-            T::serialize(value, writer)?;
+            let offset = writer.stream_position()?;
+            writer.register_pointer_object(value)?;
+            T::serialize(value, writer).map_err(|e| writer.locate_error(e, type_name::<T>(), Some(index), offset))?;
+            writer.trace_element(type_name::<T>(), index, offset)?;
             if let Some(script) = script_after.as_mut() {
                 script(writer, index as _, size)?;
             }
@@ -7,6 +7,17 @@ pub mod chunks;
 pub mod models;
 pub mod serializing;
 pub mod string;
+pub mod image;
+pub mod audio;
+pub mod references;
+pub mod bytecode;
+pub mod disasm;
+pub mod asm;
+pub mod decompiler;
+pub mod shared;
+pub mod verify;
+pub mod detection;
+pub mod error;
 
 #[derive(Clone, Debug)]
 pub struct GMVersionInfo {
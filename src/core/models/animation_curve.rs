@@ -1,8 +1,8 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMSimpleList};
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, lists::GMSimpleList};
 use bitflags::bitflags;
 use bstr::BString;
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -62,7 +62,7 @@ impl Serialize for AnimationCurve {
 
         chunk.name = reader.read_pointer_string()?;
         chunk.graph_type = GraphType::from_bits_retain(reader.read_i32()?);
-        chunk.channels.deserialize(reader, None, None)?;
+        chunk.channels.deserialize(reader, None, None).field("channels")?;
 
         Ok(chunk)
     }
@@ -89,7 +89,7 @@ impl Serialize for Channel {
         chunk.name = reader.read_pointer_string()?;
         chunk.function_type = FunctionType::from_bits_retain(reader.read_i32()?);
         chunk.iterations = reader.read_u32()?;
-        chunk.points.deserialize(reader, None, None)?;
+        chunk.points.deserialize(reader, None, None).field("points")?;
 
         Ok(chunk)
     }
@@ -0,0 +1,39 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use bstr::BString;
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct AssetTags {
+    pub asset_id: i32,
+    pub tags: Vec<BString>,
+}
+
+impl Serialize for AssetTags {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.asset_id = reader.read_i32()?;
+        for _ in 0..reader.read_u32()? {
+            chunk.tags.push(reader.read_pointer_string()?);
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_i32(chunk.asset_id)?;
+        writer.write_u32(chunk.tags.len() as u32)?;
+        for tag in chunk.tags.iter() {
+            writer.write_pointer_string(tag)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,8 +1,9 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, shared::Shared};
 use bstr::BString;
 use byteorder::WriteBytesExt;
 use tracing::warn;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
+use super::texture_page_item::TexturePageItem;
 
 #[derive(Default, Clone)]
 pub struct Background {
@@ -10,7 +11,7 @@ pub struct Background {
     pub transparent: bool,
     pub smooth: bool,
     pub preload: bool,
-    pub texture_item: u32, // TODO: Set this to Texture Item once it's finished
+    pub texture_item: Option<Shared<TexturePageItem>>,
     pub tile_unknown1: u32, // Seems to always be 2, currently unknown (maybe it's tile version?)
     pub tile_width: u32, // GMS2 only
     pub tile_height: u32, // GMS2 only
@@ -34,7 +35,7 @@ impl Serialize for Background {
         chunk.transparent = reader.read_wide_bool()?;
         chunk.smooth = reader.read_wide_bool()?;
         chunk.preload = reader.read_wide_bool()?;
-        chunk.texture_item = reader.read_u32()?;
+        chunk.texture_item = reader.read_pointer_object_safe::<Shared<TexturePageItem>>().field("texture_item")?;
 
         if reader.version_info.major >= 2 {
             chunk.tile_unknown1 = reader.read_u32()?;
@@ -72,7 +73,7 @@ impl Serialize for Background {
         writer.write_wide_bool(chunk.transparent)?;
         writer.write_wide_bool(chunk.smooth)?;
         writer.write_wide_bool(chunk.preload)?;
-        writer.write_u32(chunk.texture_item)?;
+        writer.write_pointer_object_safe(&chunk.texture_item)?;
 
         if writer.version_info.major >= 2 {
             writer.write_u32(chunk.tile_unknown1)?;
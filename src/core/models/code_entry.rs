@@ -0,0 +1,71 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use bstr::BString;
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct CodeEntry {
+    pub name: BString,
+    pub length: u32, // Length of the bytecode in bytes
+    pub locals_count: u16, // From bytecode 15>=
+    pub arguments_count: u16, // From bytecode 15>=
+    pub weird_local_flag: bool, // From bytecode 15>=, stored in the high bit of the argument count
+    pub bytecode_address: u64, // Absolute offset of the bytecode, shared between a parent and its child functions from bytecode 15>=
+    pub offset: u32, // From bytecode 15>=, where this entry starts inside the shared bytecode
+    pub bytecode: Vec<u8>, // Only filled on entries that own their bytecode, child functions use their parent's
+    pub parent: Option<usize>, // From GMS 2.3>=, index of the entry owning the bytecode of this child function
+}
+
+impl CodeEntry {
+    pub fn is_child(&self) -> bool {
+        self.parent.is_some()
+    }
+}
+
+impl Serialize for CodeEntry {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        chunk.length = reader.read_u32()?;
+        if reader.version_info.format_id <= 14 {
+            chunk.bytecode_address = reader.stream_position()?;
+            chunk.bytecode = reader.read_vec(chunk.length as usize)?;
+        } else {
+            chunk.locals_count = reader.read_u16()?;
+            chunk.arguments_count = reader.read_u16()?;
+            if chunk.arguments_count & 0x8000 != 0 {
+                chunk.arguments_count &= 0x7fff;
+                chunk.weird_local_flag = true;
+            }
+            // The address is relative to the field itself
+            let pos = reader.stream_position()?;
+            chunk.bytecode_address = (pos as i64 + reader.read_i32()? as i64) as u64;
+            chunk.offset = reader.read_u32()?;
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_u32(chunk.length)?;
+        if writer.version_info.format_id <= 14 {
+            writer.write_bytes(&chunk.bytecode)?;
+        } else {
+            writer.write_u16(chunk.locals_count)?;
+            writer.write_u16(chunk.arguments_count | if chunk.weird_local_flag { 0x8000 } else { 0 })?;
+            let pos = writer.stream_position()?;
+            writer.write_i32((chunk.bytecode_address as i64 - pos as i64) as i32)?;
+            writer.write_u32(chunk.offset)?;
+        }
+
+        Ok(())
+    }
+}
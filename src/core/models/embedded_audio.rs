@@ -0,0 +1,38 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, audio::{self, AudioFormat}};
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct EmbeddedAudio {
+    pub data: Vec<u8>, // WAV or OGG file, as it would be on disk
+}
+
+impl EmbeddedAudio {
+    pub fn format(&self) -> AudioFormat {
+        audio::detect_format(&self.data)
+    }
+}
+
+impl Serialize for EmbeddedAudio {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        let length = reader.read_u32()?;
+        chunk.data = reader.read_vec(length as usize)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_u32(chunk.data.len() as _)?;
+        writer.write_bytes(&chunk.data)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, shared::Shared};
+use bstr::BString;
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+use super::texture_page_item::TexturePageItem;
+
+#[derive(Default, Clone)]
+pub struct EmbeddedImage {
+    pub name: BString,
+    pub texture_item: Option<Shared<TexturePageItem>>,
+}
+
+impl Serialize for EmbeddedImage {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        chunk.texture_item = reader.read_pointer_object_safe::<Shared<TexturePageItem>>().field("texture_item")?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_pointer_object_safe(&chunk.texture_item)?;
+
+        Ok(())
+    }
+}
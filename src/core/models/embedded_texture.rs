@@ -0,0 +1,148 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, image::{self, Image, ImageFormat, BZ2_MAGIC}};
+use byteorder::WriteBytesExt;
+use bzip2::{Decompress, Status};
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+
+#[derive(Default, Clone)]
+pub struct EmbeddedTexture {
+    pub scaled: u32,
+    pub generated_mips: u32, // From 2.0.6>=
+    pub texture_block_size: u32, // Length of the texture data, from 2022.3>=
+    pub width: i32, // From 2022.9>=
+    pub height: i32, // From 2022.9>=
+    pub index_in_group: i32, // From 2022.9>=
+    pub external: bool, // From 2022.9>=, the page is stored in a file of its texture group instead of the data file
+    pub data: Vec<u8>, // PNG, QOI or BZip2 compressed QOI blob
+}
+
+impl EmbeddedTexture {
+    pub fn format(&self) -> ImageFormat {
+        image::detect_format(&self.data)
+    }
+
+    pub fn size(&self) -> Result<(u32, u32)> {
+        if self.width > 0 && self.height > 0 {
+            return Ok((self.width as _, self.height as _));
+        }
+        image::image_size(&self.data)
+    }
+
+    pub fn decode(&self) -> Result<Image> {
+        image::decode(&self.data)
+    }
+
+    fn read_data<R>(reader: &mut Reader<R>, block_size: Option<u32>) -> Result<Vec<u8>>
+        where R: Read + Seek,
+    {
+        let start = reader.stream_position()?;
+        let length = if let Some(block_size) = block_size {
+            block_size as u64
+        } else {
+            let magic = reader.read_bytes::<8>()?;
+            reader.seek(SeekFrom::Start(start))?;
+            match image::detect_format(&magic) {
+                ImageFormat::Png => {
+                    reader.seek_relative(8)?; // Skip the signature
+                    loop {
+                        let length = reader.read_u32()?.swap_bytes();
+                        let kind = reader.read_bytes::<4>()?;
+                        reader.seek_relative(length as i64 + 4)?; // Data and CRC
+                        if &kind == b"IEND" {
+                            break;
+                        }
+                    }
+                    reader.stream_position()? - start
+                }
+                ImageFormat::Qoi => {
+                    reader.seek_relative(8)?;
+                    12 + reader.read_u32()? as u64
+                }
+                ImageFormat::Bz2Qoi => {
+                    // Without a block size, the only way to find the end is decompressing the stream
+                    let mut rest = vec![0; (reader.current_chunk.end_offset.saturating_sub(start)) as usize];
+                    reader.read_exact(&mut rest)?;
+                    let header_size = if rest.get(8..11) == Some(&BZ2_MAGIC) { 8 } else { 12 };
+                    let mut decompress = Decompress::new(false);
+                    let mut output = vec![0; 0x10000];
+                    loop {
+                        let consumed = decompress.total_in() as usize;
+                        let input = rest.get(header_size + consumed..).unwrap_or_default();
+                        match decompress.decompress(input, &mut output).map_err(|e| Error::new(ErrorKind::InvalidData, e))? {
+                            Status::StreamEnd => break,
+                            _ if input.is_empty() => {
+                                return Err(Error::new(ErrorKind::UnexpectedEof, "Truncated BZip2 texture data"));
+                            }
+                            _ => {}
+                        }
+                    }
+                    header_size as u64 + decompress.total_in()
+                }
+                ImageFormat::Unknown => {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("Unknown texture format at offset {start}")));
+                }
+            }
+        };
+        reader.seek(SeekFrom::Start(start))?;
+        reader.read_vec(length as usize)
+    }
+}
+
+impl Serialize for EmbeddedTexture {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.scaled = reader.read_u32()?;
+        if reader.version_info.is_version_at_least(2, 0, 6, 0) {
+            chunk.generated_mips = reader.read_u32()?;
+        }
+        let mut block_size = None;
+        if reader.version_info.is_version_at_least(2022, 3, 0, 0) {
+            chunk.texture_block_size = reader.read_u32()?;
+            block_size = Some(chunk.texture_block_size);
+        }
+        if reader.version_info.is_version_at_least(2022, 9, 0, 0) {
+            chunk.width = reader.read_i32()?;
+            chunk.height = reader.read_i32()?;
+            chunk.index_in_group = reader.read_i32()?;
+        }
+        let ptr = reader.read_u32()?;
+        chunk.external = ptr == 0 && reader.version_info.is_version_at_least(2022, 9, 0, 0);
+        if ptr != 0 {
+            let return_to = reader.stream_position()?;
+            reader.seek(SeekFrom::Start(ptr as _))?;
+            chunk.data = Self::read_data(reader, block_size)?;
+            reader.seek(SeekFrom::Start(return_to))?;
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_u32(chunk.scaled)?;
+        if writer.version_info.is_version_at_least(2, 0, 6, 0) {
+            writer.write_u32(chunk.generated_mips)?;
+        }
+        if writer.version_info.is_version_at_least(2022, 3, 0, 0) {
+            writer.write_u32(if chunk.external { 0 } else { chunk.data.len() as u32 })?;
+        }
+        if writer.version_info.is_version_at_least(2022, 9, 0, 0) {
+            writer.write_i32(chunk.width)?;
+            writer.write_i32(chunk.height)?;
+            writer.write_i32(chunk.index_in_group)?;
+        }
+        // TXTR only writes out blobs with something in them, the others stay null
+        if chunk.external || chunk.data.is_empty() {
+            writer.write_u32(0)?;
+        } else {
+            writer.write_pointer(&chunk.data)?;
+        }
+
+        Ok(())
+    }
+}
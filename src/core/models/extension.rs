@@ -1,8 +1,8 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList};
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList};
 use bstr::BString;
 use byteorder::WriteBytesExt;
 use bitflags::bitflags;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -121,10 +121,12 @@ impl Serialize for Extension {
         chunk.class_name = reader.read_pointer_string()?;
 
         if reader.version_info.is_version_at_least(2022, 6, 0, 0) {
-            chunk.files = reader.read_pointer_object::<GMPointerList<ExtensionFile>>()?;
-            chunk.options = reader.read_pointer_object::<GMPointerList<ExtensionOption>>()?;
+            // Both lists follow right after, and the chunk's product IDs come after the options of the last extension
+            let (files_ptr, options_ptr) = (reader.read_u32()?, reader.read_u32()?);
+            chunk.files = reader.read_pointer_object_ext::<GMPointerList<ExtensionFile>>(files_ptr as _, false).field("files")?;
+            chunk.options = reader.read_pointer_object_ext::<GMPointerList<ExtensionOption>>(options_ptr as _, false).field("options")?;
         } else {
-            chunk.files.deserialize(reader, None, None)?;
+            chunk.files.deserialize(reader, None, None).field("files")?;
         }
 
         Ok(chunk)
@@ -144,6 +146,8 @@ impl Serialize for Extension {
         if writer.version_info.is_version_at_least(2022, 6, 0, 0) {
             writer.write_pointer_object(&chunk.files)?;
             writer.write_pointer_object(&chunk.options)?;
+            chunk.files.serialize(writer, None, None)?;
+            chunk.options.serialize(writer, None, None)?;
         } else {
             chunk.files.serialize(writer, None, None)?;
         }
@@ -164,7 +168,7 @@ impl Serialize for ExtensionFile {
         chunk.final_function = reader.read_pointer_string()?;
         chunk.initial_function = reader.read_pointer_string()?;
         chunk.kind = ExtensionKind::from_bits_retain(reader.read_i32()?);
-        chunk.functions.deserialize(reader, None, None)?;
+        chunk.functions.deserialize(reader, None, None).field("functions")?;
 
         Ok(chunk)
     }
@@ -1,7 +1,7 @@
 use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
 use bstr::BString;
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 
 #[derive(Default, Clone)]
 pub struct FilterEffect {
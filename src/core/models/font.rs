@@ -1,7 +1,8 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList};
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, shared::Shared, lists::GMPointerList};
 use bstr::BString;
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
+use super::texture_page_item::TexturePageItem;
 
 #[derive(Default, Clone)]
 pub struct Font {
@@ -15,7 +16,7 @@ pub struct Font {
     pub charset: i8,
     pub antialiasing: i8,
     pub range_end: i32,
-    pub texture_item: i32, // TODO: Change it to a Texture Item once it's done
+    pub texture_item: Shared<TexturePageItem>,
     pub scale_x: f32,
     pub scale_y: f32,
     pub ascender_offset: i32,
@@ -44,7 +45,7 @@ impl Serialize for Font {
         chunk.charset = reader.read_i8()?;
         chunk.antialiasing = reader.read_i8()?;
         chunk.range_end = reader.read_i32()?;
-        chunk.texture_item = reader.read_i32()?;
+        chunk.texture_item = reader.read_pointer_object::<Shared<TexturePageItem>>().field("texture_item")?;
         chunk.scale_x = reader.read_f32()?;
         chunk.scale_y = reader.read_f32()?;
         if reader.version_info.format_id >= 17 {
@@ -53,7 +54,7 @@ impl Serialize for Font {
         if reader.version_info.is_version_at_least(2022, 2, 0, 0) {
             chunk.ascender = reader.read_i32()?;
         }
-        chunk.glyphs.deserialize(reader, None, None)?;
+        chunk.glyphs.deserialize(reader, None, None).field("glyphs")?;
 
         Ok(chunk)
     }
@@ -74,7 +75,7 @@ impl Serialize for Font {
         writer.write_i8(chunk.charset)?;
         writer.write_i8(chunk.antialiasing)?;
         writer.write_i32(chunk.range_end)?;
-        writer.write_i32(chunk.texture_item)?;
+        writer.write_pointer_object(&chunk.texture_item)?;
         writer.write_f32(chunk.scale_x)?;
         writer.write_f32(chunk.scale_y)?;
         if writer.version_info.format_id >= 17 {
@@ -116,8 +117,8 @@ impl Serialize for Glyph {
         chunk.height = reader.read_u16()?;
         chunk.shift = reader.read_i16()?;
         chunk.offset = reader.read_i16()?;
-        for _ in 0..reader.read_u16()? {
-            chunk.kerning.push(Kerning::deserialize(reader)?);
+        for index in 0..reader.read_u16()? {
+            chunk.kerning.push(Kerning::deserialize(reader).element("kerning", index as _)?);
         }
 
         Ok(chunk)
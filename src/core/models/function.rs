@@ -0,0 +1,103 @@
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, references::{self, CodeLocation}};
+use bstr::BString;
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct Function {
+    pub name: BString,
+    pub occurrences: u32,
+    pub first_occurrence: Option<CodeLocation>, // First instruction calling this function, the rest are chained from it
+}
+
+impl Serialize for Function {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        chunk.occurrences = reader.read_u32()?;
+        chunk.first_occurrence = references::read_first_occurrence(reader, chunk.occurrences)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_u32(chunk.occurrences)?;
+        references::write_first_occurrence(writer, chunk.first_occurrence)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct LocalVariable {
+    pub index: u32,
+    pub name: BString,
+}
+
+impl Serialize for LocalVariable {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.index = reader.read_u32()?;
+        chunk.name = reader.read_pointer_string()?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_u32(chunk.index)?;
+        writer.write_pointer_string(&chunk.name)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct CodeLocals {
+    pub name: BString, // Name of the code entry these locals belong to
+    pub locals: Vec<LocalVariable>,
+}
+
+impl Serialize for CodeLocals {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        let count = reader.read_u32()?;
+        chunk.name = reader.read_pointer_string()?;
+        for index in 0..count {
+            chunk.locals.push(LocalVariable::deserialize(reader).element("locals", index as _)?);
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_u32(chunk.locals.len() as _)?;
+        writer.write_pointer_string(&chunk.name)?;
+        for local in chunk.locals.iter() {
+            LocalVariable::serialize(local, writer)?;
+        }
+
+        Ok(())
+    }
+}
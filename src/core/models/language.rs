@@ -1,13 +1,13 @@
 use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMSimpleList};
 use bstr::BString;
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 
 #[derive(Default, Clone)]
 pub struct Language {
-    name: BString,
-    region: BString,
-    entries: GMSimpleList<BString>,
+    pub name: BString,
+    pub region: BString,
+    pub entries: GMSimpleList<BString>,
 }
 
 impl Serialize for Language {
@@ -18,10 +18,10 @@ impl Serialize for Language {
             ..Default::default()
         };
 
-        chunk.name = reader.read_pointer_string().expect("Failed to read name");
-        chunk.region = reader.read_pointer_string().expect("Failed to read region");
+        chunk.name = reader.read_pointer_string()?;
+        chunk.region = reader.read_pointer_string()?;
         for _ in 0..reader.global_data.lang_entry_count {
-            chunk.entries.push(reader.read_pointer_string().expect("Failed to read string"));
+            chunk.entries.push(reader.read_pointer_string()?);
         }
 
         Ok(chunk)
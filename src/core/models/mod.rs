@@ -1,5 +1,3 @@
-pub mod dummy;
-
 pub mod option;
 pub mod language;
 pub mod extension;
@@ -15,4 +13,15 @@ pub mod timeline;
 pub mod object;
 pub mod filter_effect;
 pub mod animation_curve;
-pub mod sequence;
\ No newline at end of file
+pub mod sequence;
+pub mod room;
+pub mod texture_page_item;
+pub mod embedded_texture;
+pub mod code_entry;
+pub mod variable;
+pub mod function;
+pub mod embedded_audio;
+pub mod texture_group_info;
+pub mod embedded_image;
+pub mod asset_tags;
+pub mod particle_system;
\ No newline at end of file
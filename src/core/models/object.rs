@@ -1,7 +1,7 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList};
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList};
 use bstr::BString;
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 use bitflags::bitflags;
 
 bitflags! {
@@ -99,8 +99,8 @@ impl Serialize for Object {
         chunk.persistent = reader.read_wide_bool()?;
         chunk.parent_object_id = reader.read_i32()?;
         chunk.mask_sprite_id = reader.read_i32()?;
-        chunk.physics = PhysicsProperties::deserialize(reader)?;
-        chunk.events.deserialize(reader, None, None)?;
+        chunk.physics = PhysicsProperties::deserialize(reader).field("physics")?;
+        chunk.events.deserialize(reader, None, None).field("events")?;
 
         Ok(chunk)
     }
@@ -146,8 +146,8 @@ impl Serialize for PhysicsProperties {
         chunk.friction = reader.read_f32()?;
         chunk.is_awake = reader.read_wide_bool()?;
         chunk.is_kinematic = reader.read_wide_bool()?;
-        for _ in 0..vertex_count {
-            chunk.vertices.push(PhysicsVertex::deserialize(reader)?);
+        for index in 0..vertex_count {
+            chunk.vertices.push(PhysicsVertex::deserialize(reader).element("vertices", index as _)?);
         }
 
         Ok(chunk)
@@ -209,7 +209,7 @@ impl Serialize for Event {
         };
 
         chunk.subtype = reader.read_i32()?;
-        chunk.actions.deserialize(reader, None, None)?;
+        chunk.actions.deserialize(reader, None, None).field("actions")?;
 
         Ok(chunk)
     }
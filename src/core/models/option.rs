@@ -1,4 +1,4 @@
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 use bstr::BString;
 use byteorder::WriteBytesExt;
 use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
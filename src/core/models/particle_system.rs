@@ -0,0 +1,374 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use bitflags::bitflags;
+use bstr::BString;
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct EmitMode: u32 {
+        const Stream = 0;
+        const Burst = 1;
+    }
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct EmitterDistribution: u32 {
+        const Linear = 0;
+        const Gaussian = 1;
+        const InverseGaussian = 2;
+    }
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct EmitterShape: u32 {
+        const Rectangle = 0;
+        const Ellipse = 1;
+        const Diamond = 2;
+        const Line = 3;
+    }
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct ParticleTexture: i32 {
+        const None = -1;
+        const Pixel = 0;
+        const Disk = 1;
+        const Square = 2;
+        const Line = 3;
+        const Star = 4;
+        const Circle = 5;
+        const Ring = 6;
+        const Sphere = 7;
+        const Flare = 8;
+        const Spark = 9;
+        const Explosion = 10;
+        const Cloud = 11;
+        const Smoke = 12;
+        const Snow = 13;
+    }
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct TimeUnit: u32 {
+        const Seconds = 0;
+        const Frames = 1;
+    }
+}
+
+impl Default for EmitMode {
+    fn default() -> Self {
+        Self::Stream
+    }
+}
+
+impl Default for EmitterDistribution {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+impl Default for EmitterShape {
+    fn default() -> Self {
+        Self::Rectangle
+    }
+}
+
+impl Default for ParticleTexture {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl Default for TimeUnit {
+    fn default() -> Self {
+        Self::Seconds
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct ParticleEmitter {
+    pub name: BString,
+    pub enabled: bool, // From 2023.6>=
+    pub mode: EmitMode,
+    pub emit_count: f32, // Stored as an integer until 2023.8
+    pub emit_relative: bool, // From 2023.8>=
+    pub delay_min: f32, // From 2023.8>=
+    pub delay_max: f32, // From 2023.8>=
+    pub delay_unit: TimeUnit, // From 2023.8>=
+    pub interval_min: f32, // From 2023.8>=
+    pub interval_max: f32, // From 2023.8>=
+    pub interval_unit: TimeUnit, // From 2023.8>=
+    pub distribution: EmitterDistribution,
+    pub shape: EmitterShape,
+    pub region_x: f32,
+    pub region_y: f32,
+    pub region_width: f32,
+    pub region_height: f32,
+    pub rotation: f32,
+    pub sprite_id: i32, // -1 when a builtin texture is used instead
+    pub texture: ParticleTexture,
+    pub frame_index: f32,
+    pub animate: bool, // From 2023.4>=
+    pub stretch: bool, // From 2023.4>=
+    pub random: bool, // From 2023.4>=
+    pub start_color: u32,
+    pub mid_color: u32,
+    pub end_color: u32,
+    pub additive_blend: bool,
+    pub lifetime_min: f32,
+    pub lifetime_max: f32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub size_min_x: f32, // Sizes are shared by both axes until 2023.8, the Y values being copies of the X ones
+    pub size_max_x: f32,
+    pub size_min_y: f32,
+    pub size_max_y: f32,
+    pub size_increase_x: f32,
+    pub size_increase_y: f32,
+    pub size_wiggle_x: f32,
+    pub size_wiggle_y: f32,
+    pub speed_min: f32,
+    pub speed_max: f32,
+    pub speed_increase: f32,
+    pub speed_wiggle: f32,
+    pub gravity_force: f32,
+    pub gravity_direction: f32,
+    pub direction_min: f32,
+    pub direction_max: f32,
+    pub direction_increase: f32,
+    pub direction_wiggle: f32,
+    pub orientation_min: f32,
+    pub orientation_max: f32,
+    pub orientation_increase: f32,
+    pub orientation_wiggle: f32,
+    pub orientation_relative: bool,
+    pub spawn_on_death_id: i32, // Emitter IDs, -1 for none
+    pub spawn_on_death_count: u32,
+    pub spawn_on_update_id: i32,
+    pub spawn_on_update_count: u32,
+}
+
+impl ParticleEmitter {
+    // Size of an emitter record for each version that changed it, used to detect the version
+    pub const SIZE_2023_2: u64 = 0xb0;
+    pub const SIZE_2023_4: u64 = 0xbc;
+    pub const SIZE_2023_6: u64 = 0xc0;
+    pub const SIZE_2023_8: u64 = 0xec;
+}
+
+#[derive(Default, Clone)]
+pub struct ParticleSystem {
+    pub name: BString,
+    pub origin_x: i32,
+    pub origin_y: i32,
+    pub draw_order: i32, // Non-zero draws the emitters from the last one to the first one
+    pub global_space_particles: bool, // From 2023.8>=
+    pub emitters: Vec<i32>, // Emitter IDs in PSEM
+}
+
+impl Serialize for ParticleEmitter {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        chunk.enabled = true;
+        if reader.version_info.is_version_at_least(2023, 6, 0, 0) {
+            chunk.enabled = reader.read_wide_bool()?;
+        }
+        chunk.mode = EmitMode::from_bits_retain(reader.read_u32()?);
+        if reader.version_info.is_version_at_least(2023, 8, 0, 0) {
+            chunk.emit_count = reader.read_f32()?;
+            chunk.emit_relative = reader.read_wide_bool()?;
+            chunk.delay_min = reader.read_f32()?;
+            chunk.delay_max = reader.read_f32()?;
+            chunk.delay_unit = TimeUnit::from_bits_retain(reader.read_u32()?);
+            chunk.interval_min = reader.read_f32()?;
+            chunk.interval_max = reader.read_f32()?;
+            chunk.interval_unit = TimeUnit::from_bits_retain(reader.read_u32()?);
+        } else {
+            chunk.emit_count = reader.read_i32()? as f32;
+        }
+        chunk.distribution = EmitterDistribution::from_bits_retain(reader.read_u32()?);
+        chunk.shape = EmitterShape::from_bits_retain(reader.read_u32()?);
+        chunk.region_x = reader.read_f32()?;
+        chunk.region_y = reader.read_f32()?;
+        chunk.region_width = reader.read_f32()?;
+        chunk.region_height = reader.read_f32()?;
+        chunk.rotation = reader.read_f32()?;
+        chunk.sprite_id = reader.read_i32()?;
+        chunk.texture = ParticleTexture::from_bits_retain(reader.read_i32()?);
+        chunk.frame_index = reader.read_f32()?;
+        if reader.version_info.is_version_at_least(2023, 4, 0, 0) {
+            chunk.animate = reader.read_wide_bool()?;
+            chunk.stretch = reader.read_wide_bool()?;
+            chunk.random = reader.read_wide_bool()?;
+        }
+        chunk.start_color = reader.read_u32()?;
+        chunk.mid_color = reader.read_u32()?;
+        chunk.end_color = reader.read_u32()?;
+        chunk.additive_blend = reader.read_wide_bool()?;
+        chunk.lifetime_min = reader.read_f32()?;
+        chunk.lifetime_max = reader.read_f32()?;
+        chunk.scale_x = reader.read_f32()?;
+        chunk.scale_y = reader.read_f32()?;
+        if reader.version_info.is_version_at_least(2023, 8, 0, 0) {
+            chunk.size_min_x = reader.read_f32()?;
+            chunk.size_max_x = reader.read_f32()?;
+            chunk.size_min_y = reader.read_f32()?;
+            chunk.size_max_y = reader.read_f32()?;
+            chunk.size_increase_x = reader.read_f32()?;
+            chunk.size_increase_y = reader.read_f32()?;
+            chunk.size_wiggle_x = reader.read_f32()?;
+            chunk.size_wiggle_y = reader.read_f32()?;
+        } else {
+            chunk.size_min_x = reader.read_f32()?;
+            chunk.size_max_x = reader.read_f32()?;
+            chunk.size_increase_x = reader.read_f32()?;
+            chunk.size_wiggle_x = reader.read_f32()?;
+            chunk.size_min_y = chunk.size_min_x;
+            chunk.size_max_y = chunk.size_max_x;
+            chunk.size_increase_y = chunk.size_increase_x;
+            chunk.size_wiggle_y = chunk.size_wiggle_x;
+        }
+        chunk.speed_min = reader.read_f32()?;
+        chunk.speed_max = reader.read_f32()?;
+        chunk.speed_increase = reader.read_f32()?;
+        chunk.speed_wiggle = reader.read_f32()?;
+        chunk.gravity_force = reader.read_f32()?;
+        chunk.gravity_direction = reader.read_f32()?;
+        chunk.direction_min = reader.read_f32()?;
+        chunk.direction_max = reader.read_f32()?;
+        chunk.direction_increase = reader.read_f32()?;
+        chunk.direction_wiggle = reader.read_f32()?;
+        chunk.orientation_min = reader.read_f32()?;
+        chunk.orientation_max = reader.read_f32()?;
+        chunk.orientation_increase = reader.read_f32()?;
+        chunk.orientation_wiggle = reader.read_f32()?;
+        chunk.orientation_relative = reader.read_wide_bool()?;
+        chunk.spawn_on_death_id = reader.read_i32()?;
+        chunk.spawn_on_death_count = reader.read_u32()?;
+        chunk.spawn_on_update_id = reader.read_i32()?;
+        chunk.spawn_on_update_count = reader.read_u32()?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        if writer.version_info.is_version_at_least(2023, 6, 0, 0) {
+            writer.write_wide_bool(chunk.enabled)?;
+        }
+        writer.write_u32(chunk.mode.bits())?;
+        if writer.version_info.is_version_at_least(2023, 8, 0, 0) {
+            writer.write_f32(chunk.emit_count)?;
+            writer.write_wide_bool(chunk.emit_relative)?;
+            writer.write_f32(chunk.delay_min)?;
+            writer.write_f32(chunk.delay_max)?;
+            writer.write_u32(chunk.delay_unit.bits())?;
+            writer.write_f32(chunk.interval_min)?;
+            writer.write_f32(chunk.interval_max)?;
+            writer.write_u32(chunk.interval_unit.bits())?;
+        } else {
+            writer.write_i32(chunk.emit_count as i32)?;
+        }
+        writer.write_u32(chunk.distribution.bits())?;
+        writer.write_u32(chunk.shape.bits())?;
+        writer.write_f32(chunk.region_x)?;
+        writer.write_f32(chunk.region_y)?;
+        writer.write_f32(chunk.region_width)?;
+        writer.write_f32(chunk.region_height)?;
+        writer.write_f32(chunk.rotation)?;
+        writer.write_i32(chunk.sprite_id)?;
+        writer.write_i32(chunk.texture.bits())?;
+        writer.write_f32(chunk.frame_index)?;
+        if writer.version_info.is_version_at_least(2023, 4, 0, 0) {
+            writer.write_wide_bool(chunk.animate)?;
+            writer.write_wide_bool(chunk.stretch)?;
+            writer.write_wide_bool(chunk.random)?;
+        }
+        writer.write_u32(chunk.start_color)?;
+        writer.write_u32(chunk.mid_color)?;
+        writer.write_u32(chunk.end_color)?;
+        writer.write_wide_bool(chunk.additive_blend)?;
+        writer.write_f32(chunk.lifetime_min)?;
+        writer.write_f32(chunk.lifetime_max)?;
+        writer.write_f32(chunk.scale_x)?;
+        writer.write_f32(chunk.scale_y)?;
+        if writer.version_info.is_version_at_least(2023, 8, 0, 0) {
+            writer.write_f32(chunk.size_min_x)?;
+            writer.write_f32(chunk.size_max_x)?;
+            writer.write_f32(chunk.size_min_y)?;
+            writer.write_f32(chunk.size_max_y)?;
+            writer.write_f32(chunk.size_increase_x)?;
+            writer.write_f32(chunk.size_increase_y)?;
+            writer.write_f32(chunk.size_wiggle_x)?;
+            writer.write_f32(chunk.size_wiggle_y)?;
+        } else {
+            writer.write_f32(chunk.size_min_x)?;
+            writer.write_f32(chunk.size_max_x)?;
+            writer.write_f32(chunk.size_increase_x)?;
+            writer.write_f32(chunk.size_wiggle_x)?;
+        }
+        writer.write_f32(chunk.speed_min)?;
+        writer.write_f32(chunk.speed_max)?;
+        writer.write_f32(chunk.speed_increase)?;
+        writer.write_f32(chunk.speed_wiggle)?;
+        writer.write_f32(chunk.gravity_force)?;
+        writer.write_f32(chunk.gravity_direction)?;
+        writer.write_f32(chunk.direction_min)?;
+        writer.write_f32(chunk.direction_max)?;
+        writer.write_f32(chunk.direction_increase)?;
+        writer.write_f32(chunk.direction_wiggle)?;
+        writer.write_f32(chunk.orientation_min)?;
+        writer.write_f32(chunk.orientation_max)?;
+        writer.write_f32(chunk.orientation_increase)?;
+        writer.write_f32(chunk.orientation_wiggle)?;
+        writer.write_wide_bool(chunk.orientation_relative)?;
+        writer.write_i32(chunk.spawn_on_death_id)?;
+        writer.write_u32(chunk.spawn_on_death_count)?;
+        writer.write_i32(chunk.spawn_on_update_id)?;
+        writer.write_u32(chunk.spawn_on_update_count)?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for ParticleSystem {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        chunk.origin_x = reader.read_i32()?;
+        chunk.origin_y = reader.read_i32()?;
+        chunk.draw_order = reader.read_i32()?;
+        if reader.version_info.is_version_at_least(2023, 8, 0, 0) {
+            chunk.global_space_particles = reader.read_wide_bool()?;
+        }
+        for _ in 0..reader.read_u32()? {
+            chunk.emitters.push(reader.read_i32()?);
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_i32(chunk.origin_x)?;
+        writer.write_i32(chunk.origin_y)?;
+        writer.write_i32(chunk.draw_order)?;
+        if writer.version_info.is_version_at_least(2023, 8, 0, 0) {
+            writer.write_wide_bool(chunk.global_space_particles)?;
+        }
+        writer.write_u32(chunk.emitters.len() as u32)?;
+        for emitter in chunk.emitters.iter() {
+            writer.write_i32(*emitter)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMSimpleList};
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, lists::GMSimpleList};
 use bstr::BString;
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 
 #[derive(Default, Clone)]
 pub struct Path {
@@ -24,7 +24,7 @@ impl Serialize for Path {
         chunk.smooth = reader.read_wide_bool()?;
         chunk.closed = reader.read_wide_bool()?;
         chunk.precision = reader.read_u32()?;
-        chunk.points.deserialize(reader, None, None)?;
+        chunk.points.deserialize(reader, None, None).field("points")?;
 
         Ok(chunk)
     }
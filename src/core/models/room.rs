@@ -0,0 +1,1005 @@
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, lists::{GMPointerList, GMSimpleList}};
+use bitflags::bitflags;
+use bstr::BString;
+use byteorder::WriteBytesExt;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+use super::sprite::AnimSpeedType;
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct RoomEntryFlags: u32 {
+        const None = 0x0;
+        const EnableViews = 0x1;
+        const ShowColor = 0x2;
+        const DoNotClearDisplayBuffer = 0x4;
+        const IsGMS2_3 = 0x10000;
+        const IsGMS2 = 0x20000;
+    }
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct LayerType: i32 {
+        const Path = 0;
+        const Background = 1;
+        const Instances = 2;
+        const Assets = 3;
+        const Tiles = 4;
+        const Effect = 6;
+        const Path2 = 7;
+    }
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct EffectPropertyKind: i32 {
+        const Real = 0;
+        const Color = 1;
+        const Sampler = 2;
+    }
+}
+
+impl Default for RoomEntryFlags {
+    fn default() -> Self {
+        Self::EnableViews
+    }
+}
+
+impl Default for LayerType {
+    fn default() -> Self {
+        Self::Path
+    }
+}
+
+impl Default for EffectPropertyKind {
+    fn default() -> Self {
+        Self::Real
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct Room {
+    pub name: BString,
+    pub caption: Option<BString>, // Null when the room has none
+    pub width: u32,
+    pub height: u32,
+    pub speed: u32,
+    pub persistent: bool,
+    pub background_color: u32,
+    pub draw_background_color: bool,
+    pub creation_code_id: i32, // -1 if the room has no creation code
+    pub flags: RoomEntryFlags,
+    pub backgrounds: GMPointerList<RoomBackground>,
+    pub views: GMPointerList<View>,
+    pub game_objects: GMPointerList<GameObject>,
+    pub tiles: GMPointerList<Tile>,
+    pub world: bool,
+    pub top: u32,
+    pub left: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub gravity_x: f32,
+    pub gravity_y: f32,
+    pub meters_per_pixel: f32,
+    pub layers: GMPointerList<Layer>, // GMS2 only
+    pub sequences: Vec<i32>, // Sequence IDs, GMS2.3 only
+}
+
+#[derive(Default, Clone)]
+pub struct RoomBackground {
+    pub enabled: bool,
+    pub foreground: bool,
+    pub background_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub tile_x: bool,
+    pub tile_y: bool,
+    pub speed_x: i32,
+    pub speed_y: i32,
+    pub stretch: bool,
+}
+
+#[derive(Default, Clone)]
+pub struct View {
+    pub enabled: bool,
+    pub view_x: i32,
+    pub view_y: i32,
+    pub view_width: i32,
+    pub view_height: i32,
+    pub port_x: i32,
+    pub port_y: i32,
+    pub port_width: i32,
+    pub port_height: i32,
+    pub border_x: u32,
+    pub border_y: u32,
+    pub speed_x: i32,
+    pub speed_y: i32,
+    pub object_id: i32,
+}
+
+#[derive(Default, Clone)]
+pub struct GameObject {
+    pub x: i32,
+    pub y: i32,
+    pub object_id: i32,
+    pub instance_id: u32,
+    pub creation_code_id: i32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub image_speed: f32, // From 2.2.2.302>=
+    pub image_index: i32, // From 2.2.2.302>=
+    pub color: u32,
+    pub rotation: f32,
+    pub pre_create_code_id: i32, // Only if the data file uses Pre-Create events
+}
+
+#[derive(Default, Clone)]
+pub struct Tile {
+    pub x: i32,
+    pub y: i32,
+    pub background_id: i32, // This is a Sprite ID in GMS2
+    pub source_x: i32,
+    pub source_y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub depth: i32,
+    pub instance_id: u32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub color: u32,
+}
+
+#[derive(Default, Clone)]
+pub struct Layer {
+    pub name: BString,
+    pub id: u32,
+    pub kind: LayerType,
+    pub depth: i32,
+    pub x_offset: f32,
+    pub y_offset: f32,
+    pub horizontal_speed: f32,
+    pub vertical_speed: f32,
+    pub visible: bool,
+    pub effect_enabled: bool, // From 2022.1>=
+    pub effect_type: Option<BString>, // From 2022.1>=, null without an effect
+    pub effect_properties: GMSimpleList<EffectProperty>, // From 2022.1>=
+    pub data: LayerData,
+}
+
+#[derive(Default, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum LayerData {
+    #[default]
+    None,
+    Background(LayerBackground),
+    Instances(LayerInstances),
+    Assets(LayerAssets),
+    Tiles(LayerTiles),
+    Effect(LayerEffect),
+}
+
+#[derive(Default, Clone)]
+pub struct EffectProperty {
+    pub kind: EffectPropertyKind,
+    pub name: BString,
+    pub value: BString,
+}
+
+#[derive(Default, Clone)]
+pub struct LayerBackground {
+    pub visible: bool,
+    pub foreground: bool,
+    pub sprite_id: i32,
+    pub tiled_horizontally: bool,
+    pub tiled_vertically: bool,
+    pub stretch: bool,
+    pub color: u32,
+    pub first_frame: f32,
+    pub animation_speed: f32,
+    pub animation_speed_type: AnimSpeedType,
+}
+
+#[derive(Default, Clone)]
+pub struct LayerInstances {
+    pub instance_ids: Vec<u32>,
+}
+
+#[derive(Default, Clone)]
+pub struct LayerAssets {
+    pub legacy_tiles: GMPointerList<Tile>,
+    pub sprites: GMPointerList<SpriteInstance>,
+    pub sequences: GMPointerList<SequenceInstance>, // From 2.3>=
+    pub nine_slices: GMPointerList<SpriteInstance>, // From 2.3>= until 2.3.2
+    pub particle_systems: GMPointerList<ParticleSystemInstance>, // From 2023.2>=
+}
+
+#[derive(Default, Clone)]
+pub struct LayerTiles {
+    pub background_id: i32,
+    pub tiles_x: u32,
+    pub tiles_y: u32,
+    pub tiles: Vec<Vec<u32>>, // Rows of tile IDs, run-length compressed from 2024.2>=
+}
+
+#[derive(Default, Clone)]
+pub struct LayerEffect {
+    pub effect_type: BString, // Before 2022.1, moved to the layer itself afterwards
+    pub properties: GMSimpleList<EffectProperty>,
+}
+
+#[derive(Default, Clone)]
+pub struct SpriteInstance {
+    pub name: BString,
+    pub sprite_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub color: u32,
+    pub animation_speed: f32,
+    pub animation_speed_type: AnimSpeedType,
+    pub frame_index: f32,
+    pub rotation: f32,
+}
+
+#[derive(Default, Clone)]
+pub struct SequenceInstance {
+    pub name: BString,
+    pub sequence_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub color: u32,
+    pub animation_speed: f32,
+    pub animation_speed_type: AnimSpeedType,
+    pub frame_index: f32,
+    pub rotation: f32,
+}
+
+#[derive(Default, Clone)]
+pub struct ParticleSystemInstance {
+    pub name: BString,
+    pub particle_system_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub color: u32,
+    pub rotation: f32,
+}
+
+impl Serialize for Room {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        chunk.caption = reader.read_pointer_string_option()?;
+        chunk.width = reader.read_u32()?;
+        chunk.height = reader.read_u32()?;
+        chunk.speed = reader.read_u32()?;
+        chunk.persistent = reader.read_wide_bool()?;
+        chunk.background_color = reader.read_u32()?;
+        chunk.draw_background_color = reader.read_wide_bool()?;
+        chunk.creation_code_id = reader.read_i32()?;
+        chunk.flags = RoomEntryFlags::from_bits_retain(reader.read_u32()?);
+        chunk.backgrounds = reader.read_pointer_object::<GMPointerList<RoomBackground>>().field("backgrounds")?;
+        chunk.views = reader.read_pointer_object::<GMPointerList<View>>().field("views")?;
+        chunk.game_objects = reader.read_pointer_object::<GMPointerList<GameObject>>().field("game_objects")?;
+        chunk.tiles = reader.read_pointer_object::<GMPointerList<Tile>>().field("tiles")?;
+        chunk.world = reader.read_wide_bool()?;
+        chunk.top = reader.read_u32()?;
+        chunk.left = reader.read_u32()?;
+        chunk.right = reader.read_u32()?;
+        chunk.bottom = reader.read_u32()?;
+        chunk.gravity_x = reader.read_f32()?;
+        chunk.gravity_y = reader.read_f32()?;
+        chunk.meters_per_pixel = reader.read_f32()?;
+        if reader.version_info.major >= 2 {
+            chunk.layers = reader.read_pointer_object::<GMPointerList<Layer>>().field("layers")?;
+            if reader.version_info.is_version_at_least(2, 3, 0, 0) {
+                let ptr = reader.read_u32()?;
+                let return_to = reader.stream_position()?;
+                reader.seek(SeekFrom::Start(ptr as _))?;
+                for _ in 0..reader.read_u32()? {
+                    chunk.sequences.push(reader.read_i32()?);
+                }
+                reader.seek(SeekFrom::Start(return_to))?;
+            }
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_pointer_string_option(chunk.caption.as_ref())?;
+        writer.write_u32(chunk.width)?;
+        writer.write_u32(chunk.height)?;
+        writer.write_u32(chunk.speed)?;
+        writer.write_wide_bool(chunk.persistent)?;
+        writer.write_u32(chunk.background_color)?;
+        writer.write_wide_bool(chunk.draw_background_color)?;
+        writer.write_i32(chunk.creation_code_id)?;
+        writer.write_u32(chunk.flags.bits())?;
+        writer.write_pointer_object(&chunk.backgrounds)?;
+        writer.write_pointer_object(&chunk.views)?;
+        writer.write_pointer_object(&chunk.game_objects)?;
+        writer.write_pointer_object(&chunk.tiles)?;
+        writer.write_wide_bool(chunk.world)?;
+        writer.write_u32(chunk.top)?;
+        writer.write_u32(chunk.left)?;
+        writer.write_u32(chunk.right)?;
+        writer.write_u32(chunk.bottom)?;
+        writer.write_f32(chunk.gravity_x)?;
+        writer.write_f32(chunk.gravity_y)?;
+        writer.write_f32(chunk.meters_per_pixel)?;
+        if writer.version_info.major >= 2 {
+            writer.write_pointer_object(&chunk.layers)?;
+            if writer.version_info.is_version_at_least(2, 3, 0, 0) {
+                writer.write_pointer(&chunk.sequences)?;
+            }
+        }
+
+        chunk.backgrounds.serialize(writer, None, None)?;
+        chunk.views.serialize(writer, None, None)?;
+        chunk.game_objects.serialize(writer, None, None)?;
+        chunk.tiles.serialize(writer, None, None)?;
+        if writer.version_info.major >= 2 {
+            chunk.layers.serialize(writer, None, None)?;
+            if writer.version_info.is_version_at_least(2, 3, 0, 0) {
+                writer.register_pointer(&chunk.sequences)?;
+                writer.write_u32(chunk.sequences.len() as u32)?;
+                for sequence in chunk.sequences.iter() {
+                    writer.write_i32(*sequence)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for RoomBackground {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.enabled = reader.read_wide_bool()?;
+        chunk.foreground = reader.read_wide_bool()?;
+        chunk.background_id = reader.read_i32()?;
+        chunk.x = reader.read_i32()?;
+        chunk.y = reader.read_i32()?;
+        chunk.tile_x = reader.read_wide_bool()?;
+        chunk.tile_y = reader.read_wide_bool()?;
+        chunk.speed_x = reader.read_i32()?;
+        chunk.speed_y = reader.read_i32()?;
+        chunk.stretch = reader.read_wide_bool()?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_wide_bool(chunk.enabled)?;
+        writer.write_wide_bool(chunk.foreground)?;
+        writer.write_i32(chunk.background_id)?;
+        writer.write_i32(chunk.x)?;
+        writer.write_i32(chunk.y)?;
+        writer.write_wide_bool(chunk.tile_x)?;
+        writer.write_wide_bool(chunk.tile_y)?;
+        writer.write_i32(chunk.speed_x)?;
+        writer.write_i32(chunk.speed_y)?;
+        writer.write_wide_bool(chunk.stretch)?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for View {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.enabled = reader.read_wide_bool()?;
+        chunk.view_x = reader.read_i32()?;
+        chunk.view_y = reader.read_i32()?;
+        chunk.view_width = reader.read_i32()?;
+        chunk.view_height = reader.read_i32()?;
+        chunk.port_x = reader.read_i32()?;
+        chunk.port_y = reader.read_i32()?;
+        chunk.port_width = reader.read_i32()?;
+        chunk.port_height = reader.read_i32()?;
+        chunk.border_x = reader.read_u32()?;
+        chunk.border_y = reader.read_u32()?;
+        chunk.speed_x = reader.read_i32()?;
+        chunk.speed_y = reader.read_i32()?;
+        chunk.object_id = reader.read_i32()?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_wide_bool(chunk.enabled)?;
+        writer.write_i32(chunk.view_x)?;
+        writer.write_i32(chunk.view_y)?;
+        writer.write_i32(chunk.view_width)?;
+        writer.write_i32(chunk.view_height)?;
+        writer.write_i32(chunk.port_x)?;
+        writer.write_i32(chunk.port_y)?;
+        writer.write_i32(chunk.port_width)?;
+        writer.write_i32(chunk.port_height)?;
+        writer.write_u32(chunk.border_x)?;
+        writer.write_u32(chunk.border_y)?;
+        writer.write_i32(chunk.speed_x)?;
+        writer.write_i32(chunk.speed_y)?;
+        writer.write_i32(chunk.object_id)?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for GameObject {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.x = reader.read_i32()?;
+        chunk.y = reader.read_i32()?;
+        chunk.object_id = reader.read_i32()?;
+        chunk.instance_id = reader.read_u32()?;
+        chunk.creation_code_id = reader.read_i32()?;
+        chunk.scale_x = reader.read_f32()?;
+        chunk.scale_y = reader.read_f32()?;
+        if reader.version_info.is_version_at_least(2, 2, 2, 302) {
+            chunk.image_speed = reader.read_f32()?;
+            chunk.image_index = reader.read_i32()?;
+        }
+        chunk.color = reader.read_u32()?;
+        chunk.rotation = reader.read_f32()?;
+        if reader.version_info.room_object_pre_create {
+            chunk.pre_create_code_id = reader.read_i32()?;
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_i32(chunk.x)?;
+        writer.write_i32(chunk.y)?;
+        writer.write_i32(chunk.object_id)?;
+        writer.write_u32(chunk.instance_id)?;
+        writer.write_i32(chunk.creation_code_id)?;
+        writer.write_f32(chunk.scale_x)?;
+        writer.write_f32(chunk.scale_y)?;
+        if writer.version_info.is_version_at_least(2, 2, 2, 302) {
+            writer.write_f32(chunk.image_speed)?;
+            writer.write_i32(chunk.image_index)?;
+        }
+        writer.write_u32(chunk.color)?;
+        writer.write_f32(chunk.rotation)?;
+        if writer.version_info.room_object_pre_create {
+            writer.write_i32(chunk.pre_create_code_id)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for Tile {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.x = reader.read_i32()?;
+        chunk.y = reader.read_i32()?;
+        chunk.background_id = reader.read_i32()?;
+        chunk.source_x = reader.read_i32()?;
+        chunk.source_y = reader.read_i32()?;
+        chunk.width = reader.read_u32()?;
+        chunk.height = reader.read_u32()?;
+        chunk.depth = reader.read_i32()?;
+        chunk.instance_id = reader.read_u32()?;
+        chunk.scale_x = reader.read_f32()?;
+        chunk.scale_y = reader.read_f32()?;
+        chunk.color = reader.read_u32()?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_i32(chunk.x)?;
+        writer.write_i32(chunk.y)?;
+        writer.write_i32(chunk.background_id)?;
+        writer.write_i32(chunk.source_x)?;
+        writer.write_i32(chunk.source_y)?;
+        writer.write_u32(chunk.width)?;
+        writer.write_u32(chunk.height)?;
+        writer.write_i32(chunk.depth)?;
+        writer.write_u32(chunk.instance_id)?;
+        writer.write_f32(chunk.scale_x)?;
+        writer.write_f32(chunk.scale_y)?;
+        writer.write_u32(chunk.color)?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for Layer {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        chunk.id = reader.read_u32()?;
+        chunk.kind = LayerType::from_bits_retain(reader.read_i32()?);
+        chunk.depth = reader.read_i32()?;
+        chunk.x_offset = reader.read_f32()?;
+        chunk.y_offset = reader.read_f32()?;
+        chunk.horizontal_speed = reader.read_f32()?;
+        chunk.vertical_speed = reader.read_f32()?;
+        chunk.visible = reader.read_wide_bool()?;
+        if reader.version_info.is_version_at_least(2022, 1, 0, 0) {
+            chunk.effect_enabled = reader.read_wide_bool()?;
+            chunk.effect_type = reader.read_pointer_string_option()?;
+            chunk.effect_properties.deserialize(reader, None, None).field("effect_properties")?;
+        }
+
+        match chunk.kind {
+            LayerType::Path | LayerType::Path2 => {}
+            LayerType::Background => {
+                chunk.data = LayerData::Background(LayerBackground::deserialize(reader).field("data")?);
+            }
+            LayerType::Instances => {
+                chunk.data = LayerData::Instances(LayerInstances::deserialize(reader).field("data")?);
+            }
+            LayerType::Assets => {
+                chunk.data = LayerData::Assets(LayerAssets::deserialize(reader).field("data")?);
+            }
+            LayerType::Tiles => {
+                chunk.data = LayerData::Tiles(LayerTiles::deserialize(reader).field("data")?);
+            }
+            LayerType::Effect => {
+                if !reader.version_info.is_version_at_least(2022, 1, 0, 0) {
+                    chunk.data = LayerData::Effect(LayerEffect::deserialize(reader).field("data")?);
+                }
+            }
+            _ => {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Unknown layer type: {}", chunk.kind.bits())));
+            }
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_u32(chunk.id)?;
+        writer.write_i32(chunk.kind.bits())?;
+        writer.write_i32(chunk.depth)?;
+        writer.write_f32(chunk.x_offset)?;
+        writer.write_f32(chunk.y_offset)?;
+        writer.write_f32(chunk.horizontal_speed)?;
+        writer.write_f32(chunk.vertical_speed)?;
+        writer.write_wide_bool(chunk.visible)?;
+        if writer.version_info.is_version_at_least(2022, 1, 0, 0) {
+            writer.write_wide_bool(chunk.effect_enabled)?;
+            writer.write_pointer_string_option(chunk.effect_type.as_ref())?;
+            chunk.effect_properties.serialize(writer, None, None)?;
+        }
+
+        match &chunk.data {
+            LayerData::None => {}
+            LayerData::Background(background) => { LayerBackground::serialize(background, writer)?; }
+            LayerData::Instances(instances) => { LayerInstances::serialize(instances, writer)?; }
+            LayerData::Assets(assets) => { LayerAssets::serialize(assets, writer)?; }
+            LayerData::Tiles(tiles) => { LayerTiles::serialize(tiles, writer)?; }
+            LayerData::Effect(effect) => { LayerEffect::serialize(effect, writer)?; }
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for EffectProperty {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.kind = EffectPropertyKind::from_bits_retain(reader.read_i32()?);
+        chunk.name = reader.read_pointer_string()?;
+        chunk.value = reader.read_pointer_string()?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_i32(chunk.kind.bits())?;
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_pointer_string(&chunk.value)?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for LayerBackground {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.visible = reader.read_wide_bool()?;
+        chunk.foreground = reader.read_wide_bool()?;
+        chunk.sprite_id = reader.read_i32()?;
+        chunk.tiled_horizontally = reader.read_wide_bool()?;
+        chunk.tiled_vertically = reader.read_wide_bool()?;
+        chunk.stretch = reader.read_wide_bool()?;
+        chunk.color = reader.read_u32()?;
+        chunk.first_frame = reader.read_f32()?;
+        chunk.animation_speed = reader.read_f32()?;
+        chunk.animation_speed_type = AnimSpeedType::from_bits_retain(reader.read_i32()?);
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_wide_bool(chunk.visible)?;
+        writer.write_wide_bool(chunk.foreground)?;
+        writer.write_i32(chunk.sprite_id)?;
+        writer.write_wide_bool(chunk.tiled_horizontally)?;
+        writer.write_wide_bool(chunk.tiled_vertically)?;
+        writer.write_wide_bool(chunk.stretch)?;
+        writer.write_u32(chunk.color)?;
+        writer.write_f32(chunk.first_frame)?;
+        writer.write_f32(chunk.animation_speed)?;
+        writer.write_i32(chunk.animation_speed_type.bits())?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for LayerInstances {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        for _ in 0..reader.read_u32()? {
+            chunk.instance_ids.push(reader.read_u32()?);
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_u32(chunk.instance_ids.len() as u32)?;
+        for instance_id in chunk.instance_ids.iter() {
+            writer.write_u32(*instance_id)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for LayerAssets {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.legacy_tiles = reader.read_pointer_object::<GMPointerList<Tile>>().field("legacy_tiles")?;
+        chunk.sprites = reader.read_pointer_object::<GMPointerList<SpriteInstance>>().field("sprites")?;
+        if reader.version_info.is_version_at_least(2, 3, 0, 0) {
+            chunk.sequences = reader.read_pointer_object::<GMPointerList<SequenceInstance>>().field("sequences")?;
+            if !reader.version_info.is_version_at_least(2, 3, 2, 0) {
+                chunk.nine_slices = reader.read_pointer_object::<GMPointerList<SpriteInstance>>().field("nine_slices")?;
+            }
+            if reader.version_info.is_version_at_least(2023, 2, 0, 0) {
+                chunk.particle_systems = reader.read_pointer_object::<GMPointerList<ParticleSystemInstance>>().field("particle_systems")?;
+            }
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_object(&chunk.legacy_tiles)?;
+        writer.write_pointer_object(&chunk.sprites)?;
+        if writer.version_info.is_version_at_least(2, 3, 0, 0) {
+            writer.write_pointer_object(&chunk.sequences)?;
+            if !writer.version_info.is_version_at_least(2, 3, 2, 0) {
+                writer.write_pointer_object(&chunk.nine_slices)?;
+            }
+            if writer.version_info.is_version_at_least(2023, 2, 0, 0) {
+                writer.write_pointer_object(&chunk.particle_systems)?;
+            }
+        }
+
+        chunk.legacy_tiles.serialize(writer, None, None)?;
+        chunk.sprites.serialize(writer, None, None)?;
+        if writer.version_info.is_version_at_least(2, 3, 0, 0) {
+            chunk.sequences.serialize(writer, None, None)?;
+            if !writer.version_info.is_version_at_least(2, 3, 2, 0) {
+                chunk.nine_slices.serialize(writer, None, None)?;
+            }
+            if writer.version_info.is_version_at_least(2023, 2, 0, 0) {
+                chunk.particle_systems.serialize(writer, None, None)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for LayerTiles {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.background_id = reader.read_i32()?;
+        chunk.tiles_x = reader.read_u32()?;
+        chunk.tiles_y = reader.read_u32()?;
+        if reader.version_info.is_version_at_least(2024, 2, 0, 0) {
+            let tile_count = chunk.tiles_x as usize * chunk.tiles_y as usize;
+            let mut tiles = Vec::new(); // Not reserved up front, the counts may be garbage
+            while tiles.len() < tile_count {
+                let length = reader.read_u8()?;
+                if length & 0x80 != 0 { // Repeated run
+                    let tile = reader.read_u32()?;
+                    for _ in 0..(length & 0x7f) as usize + 1 {
+                        tiles.push(tile);
+                    }
+                } else { // Verbatim run
+                    for _ in 0..length as usize + 1 {
+                        tiles.push(reader.read_u32()?);
+                    }
+                }
+            }
+            if tiles.len() != tile_count {
+                return Err(Error::new(ErrorKind::InvalidData, "Compressed tile data overflows the layer"));
+            }
+            reader.pad(4)?;
+            for row in tiles.chunks(chunk.tiles_x.max(1) as usize) {
+                chunk.tiles.push(row.to_vec());
+            }
+        } else {
+            for _ in 0..chunk.tiles_y {
+                let mut row = Vec::new();
+                for _ in 0..chunk.tiles_x {
+                    row.push(reader.read_u32()?);
+                }
+                chunk.tiles.push(row);
+            }
+        }
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_i32(chunk.background_id)?;
+        writer.write_u32(chunk.tiles_x)?;
+        writer.write_u32(chunk.tiles_y)?;
+        let tiles = chunk.tiles.concat();
+        if writer.version_info.is_version_at_least(2024, 2, 0, 0) {
+            let mut index = 0;
+            while index < tiles.len() {
+                let mut repeat = 1;
+                while index + repeat < tiles.len() && repeat < 0x80 && tiles[index + repeat] == tiles[index] {
+                    repeat += 1;
+                }
+                if repeat > 1 {
+                    writer.write_u8(0x80 | (repeat - 1) as u8)?;
+                    writer.write_u32(tiles[index])?;
+                    index += repeat;
+                } else {
+                    let mut verbatim = 1;
+                    while index + verbatim < tiles.len() && verbatim < 0x80 && (index + verbatim + 1 >= tiles.len() || tiles[index + verbatim] != tiles[index + verbatim + 1]) {
+                        verbatim += 1;
+                    }
+                    writer.write_u8((verbatim - 1) as u8)?;
+                    for tile in tiles[index..index + verbatim].iter() {
+                        writer.write_u32(*tile)?;
+                    }
+                    index += verbatim;
+                }
+            }
+            writer.pad_check_byte(4, 0)?;
+        } else {
+            for tile in tiles.iter() {
+                writer.write_u32(*tile)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for LayerEffect {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.effect_type = reader.read_pointer_string()?;
+        chunk.properties.deserialize(reader, None, None).field("properties")?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.effect_type)?;
+        chunk.properties.serialize(writer, None, None)?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for SpriteInstance {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        chunk.sprite_id = reader.read_i32()?;
+        chunk.x = reader.read_i32()?;
+        chunk.y = reader.read_i32()?;
+        chunk.scale_x = reader.read_f32()?;
+        chunk.scale_y = reader.read_f32()?;
+        chunk.color = reader.read_u32()?;
+        chunk.animation_speed = reader.read_f32()?;
+        chunk.animation_speed_type = AnimSpeedType::from_bits_retain(reader.read_i32()?);
+        chunk.frame_index = reader.read_f32()?;
+        chunk.rotation = reader.read_f32()?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_i32(chunk.sprite_id)?;
+        writer.write_i32(chunk.x)?;
+        writer.write_i32(chunk.y)?;
+        writer.write_f32(chunk.scale_x)?;
+        writer.write_f32(chunk.scale_y)?;
+        writer.write_u32(chunk.color)?;
+        writer.write_f32(chunk.animation_speed)?;
+        writer.write_i32(chunk.animation_speed_type.bits())?;
+        writer.write_f32(chunk.frame_index)?;
+        writer.write_f32(chunk.rotation)?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for SequenceInstance {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        chunk.sequence_id = reader.read_i32()?;
+        chunk.x = reader.read_i32()?;
+        chunk.y = reader.read_i32()?;
+        chunk.scale_x = reader.read_f32()?;
+        chunk.scale_y = reader.read_f32()?;
+        chunk.color = reader.read_u32()?;
+        chunk.animation_speed = reader.read_f32()?;
+        chunk.animation_speed_type = AnimSpeedType::from_bits_retain(reader.read_i32()?);
+        chunk.frame_index = reader.read_f32()?;
+        chunk.rotation = reader.read_f32()?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_i32(chunk.sequence_id)?;
+        writer.write_i32(chunk.x)?;
+        writer.write_i32(chunk.y)?;
+        writer.write_f32(chunk.scale_x)?;
+        writer.write_f32(chunk.scale_y)?;
+        writer.write_u32(chunk.color)?;
+        writer.write_f32(chunk.animation_speed)?;
+        writer.write_i32(chunk.animation_speed_type.bits())?;
+        writer.write_f32(chunk.frame_index)?;
+        writer.write_f32(chunk.rotation)?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for ParticleSystemInstance {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        chunk.particle_system_id = reader.read_i32()?;
+        chunk.x = reader.read_i32()?;
+        chunk.y = reader.read_i32()?;
+        chunk.scale_x = reader.read_f32()?;
+        chunk.scale_y = reader.read_f32()?;
+        chunk.color = reader.read_u32()?;
+        chunk.rotation = reader.read_f32()?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_i32(chunk.particle_system_id)?;
+        writer.write_i32(chunk.x)?;
+        writer.write_i32(chunk.y)?;
+        writer.write_f32(chunk.scale_x)?;
+        writer.write_f32(chunk.scale_y)?;
+        writer.write_u32(chunk.color)?;
+        writer.write_f32(chunk.rotation)?;
+
+        Ok(())
+    }
+}
@@ -1,9 +1,9 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMSimpleList};
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, lists::GMSimpleList};
 use bitflags::bitflags;
 use bstr::{BString, ByteSlice};
 use byteorder::WriteBytesExt;
-use tracing::{error, info, warn};
-use std::{fmt::Write, io::{Error, ErrorKind, Read, Result, Seek}};
+use tracing::warn;
+use std::io::{Error, ErrorKind, Read, Result, Seek, Write};
 use integer_hasher::IntMap;
 use super::{sprite::AnimSpeedType, animation_curve::AnimationCurve};
 
@@ -68,7 +68,7 @@ pub struct Keyframe<T>
     pub length: f32,
     pub stretch: bool,
     pub disabled: bool,
-    pub channels: IntMap<i32, T>,
+    pub channels: Vec<(i32, T)>, // Channel number and its data, in file order
 }
 
 #[derive(Default, Clone)]
@@ -87,21 +87,15 @@ pub struct Track {
     pub tracks: Vec<Track>,
     pub keyframes: TrackKeyframes,
     pub owned_resources: Vec<OwnedResources>,
-    pub owned_resource_types: Vec<BString>,
 }
 
-#[derive(Clone)]
+#[derive(Default, Clone)]
 pub enum OwnedResources {
+    #[default]
     None,
     AnimCurve(AnimationCurve),
 }
 
-impl Default for OwnedResources {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 #[derive(Default, Clone)]
 pub struct Moment {
     pub internal_count: i32,
@@ -125,15 +119,15 @@ impl Serialize for Sequence {
         chunk.origin_y = reader.read_i32()?;
         chunk.volume = reader.read_f32()?;
 
-        chunk.broadcast_messages.deserialize(reader, None, None)?;
-        chunk.tracks.deserialize(reader, None, None)?;
+        chunk.broadcast_messages.deserialize(reader, None, None).field("broadcast_messages")?;
+        chunk.tracks.deserialize(reader, None, None).field("tracks")?;
 
         for _ in 0..reader.read_u32()? {
             let key = reader.read_i32()?;
             chunk.function_ids.insert(key, reader.read_pointer_string()?);
         }
 
-        chunk.moments.deserialize(reader, None, None)?;
+        chunk.moments.deserialize(reader, None, None).field("moments")?;
 
         Ok(chunk)
     }
@@ -177,11 +171,11 @@ impl<T> Serialize for Keyframe<T>
 
         chunk.key = reader.read_f32()?;
         chunk.length = reader.read_f32()?;
-        chunk.stretch = reader.read_bool()?;
-        chunk.disabled = reader.read_bool()?;
-        for _ in 0..reader.read_u32()? {
+        chunk.stretch = reader.read_wide_bool()?;
+        chunk.disabled = reader.read_wide_bool()?;
+        for index in 0..reader.read_u32()? {
             let channel = reader.read_i32()?;
-            chunk.channels.insert(channel, T::deserialize(reader)?);
+            chunk.channels.push((channel, T::deserialize(reader).element("channels", index as _)?));
         }
 
         Ok(chunk)
@@ -192,8 +186,8 @@ impl<T> Serialize for Keyframe<T>
     {
         writer.write_f32(chunk.key)?;
         writer.write_f32(chunk.length)?;
-        writer.write_bool(chunk.stretch)?;
-        writer.write_bool(chunk.disabled)?;
+        writer.write_wide_bool(chunk.stretch)?;
+        writer.write_wide_bool(chunk.disabled)?;
         writer.write_u32(chunk.channels.len() as u32)?;
         for (channel, data) in chunk.channels.iter() {
             writer.write_i32(*channel)?;
@@ -236,7 +230,6 @@ impl Serialize for Track {
             ..Default::default()
         };
 
-        info!("{:?}", reader.stream_position());
         chunk.model_name = reader.read_pointer_string()?;
         chunk.name = reader.read_pointer_string_safe()?;
         chunk.built_in_name = reader.read_i32()?;
@@ -250,49 +243,35 @@ impl Serialize for Track {
         for _ in 0..tag_count {
             chunk.tags.push(reader.read_i32()?);
         }
-        for _ in 0..owned_resource_count {
+        for index in 0..owned_resource_count {
             let str = reader.read_pointer_string_safe()?;
-            chunk.owned_resource_types.push(str.clone());
-            info!("{str:?}");
-            info!("{:?}", reader.stream_position());
             if str.to_str() == Ok("GMAnimCurve") {
-                chunk.owned_resources.push(OwnedResources::AnimCurve(AnimationCurve::deserialize(reader)?));
+                chunk.owned_resources.push(OwnedResources::AnimCurve(AnimationCurve::deserialize(reader).element("owned_resources", index as _)?));
             } else {
-                warn!("Unknown resource type: {str:?}");
+                return Err(Error::new(ErrorKind::InvalidData, format!("Unknown owned resource type {str:?}")));
             }
         }
-        for _ in 0..track_count {
-            chunk.tracks.push(Track::deserialize(reader)?);
+        for index in 0..track_count {
+            chunk.tracks.push(Track::deserialize(reader).element("tracks", index as _)?);
         }
-        info!("{:?}", chunk.model_name);
-        match chunk.model_name.to_str() {
-            Ok("GMAudioTrack") => {
-                chunk.keyframes = TrackKeyframes::Audio(AudioKeyframes::deserialize(reader)?);
-            }
-            Ok("GMStringTrack") => {
-                chunk.keyframes = TrackKeyframes::String(StringKeyframes::deserialize(reader)?);
-            }
-            Ok("GMInstanceTrack") | Ok("GMGraphicTrack") | Ok("GMSequenceTrack") | Ok("GMSpriteFramesTrack") | Ok("GMBoolTrack") => {
-                chunk.keyframes = TrackKeyframes::Default(DefaultKeyframes::deserialize(reader)?);
-            }
-            Ok("GMParticleTrack") => {
-                reader.version_info.set_version(2023, 2, 0, 0);
-                chunk.keyframes = TrackKeyframes::Default(DefaultKeyframes::deserialize(reader)?);
-            }
-            Ok("GMAssetTrack") => {
-                error!("GMAssetTrack is not implemented. Please report this error!");
-            }
-            Ok("GMRealTrack") | Ok("GMColourTrack") => {
-                chunk.keyframes = TrackKeyframes::Real(RealKeyframes::deserialize(reader)?);
-            }
-            Ok("GMTextTrack") => {
-                reader.version_info.set_version(2022, 2, 0, 0);
-                chunk.keyframes = TrackKeyframes::Text(TextKeyframes::deserialize(reader)?);
-            }
+        chunk.keyframes = match chunk.model_name.to_str() {
+            Ok("GMAudioTrack") => TrackKeyframes::Audio(KeyframeList::deserialize(reader).field("keyframes")?),
+            Ok("GMInstanceTrack") => TrackKeyframes::Instance(KeyframeList::deserialize(reader).field("keyframes")?),
+            Ok("GMGraphicTrack") => TrackKeyframes::Graphic(KeyframeList::deserialize(reader).field("keyframes")?),
+            Ok("GMSequenceTrack") => TrackKeyframes::Sequence(KeyframeList::deserialize(reader).field("keyframes")?),
+            Ok("GMSpriteFramesTrack") => TrackKeyframes::SpriteFrames(KeyframeList::deserialize(reader).field("keyframes")?),
+            Ok("GMAssetTrack") => TrackKeyframes::Asset(KeyframeList::deserialize(reader).field("keyframes")?),
+            Ok("GMBoolTrack") => TrackKeyframes::Bool(KeyframeList::deserialize(reader).field("keyframes")?),
+            Ok("GMStringTrack") => TrackKeyframes::String(KeyframeList::deserialize(reader).field("keyframes")?),
+            Ok("GMRealTrack") | Ok("GMColourTrack") => TrackKeyframes::Real(RealKeyframes::deserialize(reader).field("keyframes")?),
+            Ok("GMTextTrack") => TrackKeyframes::Text(KeyframeList::deserialize(reader).field("keyframes")?),
+            Ok("GMParticleTrack") => TrackKeyframes::Particle(KeyframeList::deserialize(reader).field("keyframes")?),
+            // Group tracks only hold other tracks
+            Ok("GMGroupTrack") => TrackKeyframes::None,
             _ => {
-                error!("Unknown sequence {:?} model name", chunk.model_name);
+                return Err(Error::new(ErrorKind::InvalidData, format!("Unknown sequence track model name {:?}", chunk.model_name)));
             }
-        }
+        };
 
         Ok(chunk)
     }
@@ -316,10 +295,11 @@ impl Serialize for Track {
         for owned_resource in chunk.owned_resources.iter() {
             match owned_resource {
                 OwnedResources::None => {
-                    panic!("No resource type???");
+                    return Err(Error::new(ErrorKind::InvalidData, "Owned resource without a type"));
                 }
                 OwnedResources::AnimCurve(curve) => {
-                    writer.write_pointer::<AnimationCurve>(curve.clone())?;
+                    writer.write_pointer_string(&BString::from("GMAnimCurve"))?;
+                    AnimationCurve::serialize(curve, writer)?;
                 }
             }
         }
@@ -328,18 +308,7 @@ impl Serialize for Track {
             Track::serialize(track, writer)?;
         }
 
-        match &chunk.keyframes {
-            TrackKeyframes::Audio(audio) => { AudioKeyframes::serialize(audio, writer)?; }
-            TrackKeyframes::String(string) => { StringKeyframes::serialize(string, writer)?; }
-            TrackKeyframes::Default(default) => { DefaultKeyframes::serialize(default, writer)?; }
-            TrackKeyframes::Real(real) => { RealKeyframes::serialize(real, writer)?; }
-            TrackKeyframes::Text(text) => { TextKeyframes::serialize(text, writer)?; }
-            _ => {
-                error!("Unknown keyframe type");
-            }
-        }
-
-        Ok(())
+        chunk.keyframes.serialize(writer)
     }
 }
 
@@ -373,57 +342,130 @@ impl Serialize for Moment {
 pub enum TrackKeyframes {
     #[default]
     None,
-    Default(DefaultKeyframes),
-    Audio(AudioKeyframes),
-    String(StringKeyframes),
-    Real(RealKeyframes),
-    Text(TextKeyframes),
+    Audio(KeyframeList<AudioData>),
+    Instance(KeyframeList<ResourceData>), // Object IDs
+    Graphic(KeyframeList<ResourceData>), // Sprite IDs
+    Sequence(KeyframeList<ResourceData>), // Sequence IDs
+    SpriteFrames(KeyframeList<IntData>),
+    Asset(KeyframeList<ResourceData>),
+    Bool(KeyframeList<IntData>),
+    String(KeyframeList<StringData>),
+    Real(RealKeyframes), // Colour tracks too
+    Text(KeyframeList<TextData>),
+    Particle(KeyframeList<ResourceData>), // Particle system IDs
+}
+
+impl TrackKeyframes {
+    fn serialize<W>(&self, writer: &mut Writer<W>) -> Result<()>
+            where W: Write + WriteBytesExt + Seek {
+        // One arm per keyframe data type, as the lists can't share a pattern binding
+        match self {
+            Self::None => Ok(()),
+            Self::Instance(keyframes) | Self::Graphic(keyframes) | Self::Sequence(keyframes) | Self::Asset(keyframes) | Self::Particle(keyframes) => KeyframeList::serialize(keyframes, writer),
+            Self::SpriteFrames(keyframes) | Self::Bool(keyframes) => KeyframeList::serialize(keyframes, writer),
+            Self::Audio(keyframes) => KeyframeList::serialize(keyframes, writer),
+            Self::String(keyframes) => KeyframeList::serialize(keyframes, writer),
+            Self::Text(keyframes) => KeyframeList::serialize(keyframes, writer),
+            Self::Real(keyframes) => RealKeyframes::serialize(keyframes, writer),
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct KeyframeList<T>
+    where T: Serialize + Default,
+{
+    pub keyframes: GMSimpleList<Keyframe<T>>,
+}
+
+impl<T> Serialize for KeyframeList<T>
+    where T: Serialize + Default,
+{
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+            where R: Read + Seek {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        reader.pad_check_byte(4, 0)?;
+        chunk.keyframes.deserialize(reader, None, None).field("keyframes")?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+            where W: Write + WriteBytesExt + Seek {
+        writer.pad_check_byte(4, 0)?;
+        chunk.keyframes.serialize(writer, None, None)?;
+
+        Ok(())
+    }
 }
 
 #[derive(Default, Clone)]
-pub struct DefaultKeyframes {
-    pub data: u32,
+pub struct ResourceData {
+    pub resource_id: i32,
 }
 
-impl Serialize for DefaultKeyframes {
+impl Serialize for ResourceData {
     fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
             where R: Read + Seek {
         let mut chunk = Self {
             ..Default::default()
         };
 
-        //reader.pad_check_byte(4, 0)?;
-        chunk.data = reader.read_u32()?;
+        chunk.resource_id = reader.read_i32()?;
 
         Ok(chunk)
     }
 
     fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
             where W: Write + WriteBytesExt + Seek {
-        //writer.pad_check_byte(4, 0)?;
-        writer.write_u32(chunk.data)?;
+        writer.write_i32(chunk.resource_id)?;
 
         Ok(())
     }
 }
 
 #[derive(Default, Clone)]
-pub struct AudioKeyframes {
-    // TODO: Replace this with the actual data
-    pub data: u32, // Pointer -> SOND Chunk Resource ID
+pub struct IntData {
+    pub value: i32, // Frame index or boolean depending on the track
+}
+
+impl Serialize for IntData {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+            where R: Read + Seek {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.value = reader.read_i32()?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+            where W: Write + WriteBytesExt + Seek {
+        writer.write_i32(chunk.value)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct AudioData {
+    pub sound_id: i32,
     pub mode: i32,
 }
 
-impl Serialize for AudioKeyframes {
+impl Serialize for AudioData {
     fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
             where R: Read + Seek {
         let mut chunk = Self {
             ..Default::default()
         };
 
-        //reader.pad_check_byte(4, 0)?;
-        //chunk.data.deserialize(reader, None, None)?;
-        chunk.data = reader.read_u32()?;
+        chunk.sound_id = reader.read_i32()?;
         if reader.read_u32()? != 0 {
             warn!("Expected 0 in Audio Keyframe (Offset: {})", reader.stream_position()?);
         }
@@ -434,9 +476,7 @@ impl Serialize for AudioKeyframes {
 
     fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
             where W: Write + WriteBytesExt + Seek {
-        //writer.pad_check_byte(4, 0)?;
-        //chunk.data.serialize(writer, None, None)?;
-        writer.write_u32(chunk.data)?;
+        writer.write_i32(chunk.sound_id)?;
         writer.write_u32(0)?;
         writer.write_i32(chunk.mode)?;
 
@@ -445,29 +485,25 @@ impl Serialize for AudioKeyframes {
 }
 
 #[derive(Default, Clone)]
-pub struct StringKeyframes {
-    pub data: BString,
+pub struct StringData {
+    pub value: BString,
 }
 
-impl Serialize for StringKeyframes {
+impl Serialize for StringData {
     fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
             where R: Read + Seek {
         let mut chunk = Self {
             ..Default::default()
         };
 
-        //reader.pad_check_byte(4, 0).expect("Failed to pad reader");
-        //chunk.data.deserialize(reader, None, None);
-        chunk.data = reader.read_pointer_string().expect("Failed to read data");
+        chunk.value = reader.read_pointer_string()?;
 
         Ok(chunk)
     }
 
     fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
             where W: Write + WriteBytesExt + Seek {
-        //writer.pad_check_byte(4, 0)?;
-        //chunk.data.serialize(writer, None, None)?;
-        writer.write_pointer_string(&chunk.data)?;
+        writer.write_pointer_string(&chunk.value)?;
 
         Ok(())
     }
@@ -486,19 +522,19 @@ impl Serialize for RealKeyframes {
             ..Default::default()
         };
 
-        reader.pad_check_byte(4, 0).expect("Failed to pad reader");
+        reader.pad_check_byte(4, 0)?;
         //chunk.data.deserialize(reader, None, None);
-        chunk.interpolation = reader.read_i32().expect("Failed to read interpolation");
-        chunk.list.deserialize(reader, None, None)?;
+        chunk.interpolation = reader.read_i32()?;
+        chunk.list.deserialize(reader, None, None).field("list")?;
 
         Ok(chunk)
     }
 
     fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
             where W: Write + WriteBytesExt + Seek {
-        writer.pad_check_byte(4, 0).expect("Failed to pad writer");
+        writer.pad_check_byte(4, 0)?;
         //chunk.data.serialize(writer, None, None);
-        writer.write_i32(chunk.interpolation).expect("Failed to write interpolation");
+        writer.write_i32(chunk.interpolation)?;
         chunk.list.serialize(writer, None, None)?;
 
         Ok(())
@@ -519,14 +555,14 @@ impl Serialize for RealData {
         };
 
         chunk.value = reader.read_f32()?;
-        chunk.curve = CurveData::deserialize(reader)?;
+        chunk.curve = CurveData::deserialize(reader).field("curve")?;
 
         Ok(chunk)
     }
     
     fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
             where W: Write + WriteBytesExt + Seek {
-        writer.write_f32(chunk.value).expect("Failed to write value");
+        writer.write_f32(chunk.value)?;
         CurveData::serialize(&chunk.curve, writer)?;
 
         Ok(())
@@ -552,7 +588,7 @@ impl Serialize for CurveData {
             if reader.read_i32()? != -1 {
                 warn!("Expected -1 on CurveData");
             }
-            chunk.embedded_animation_curve = Some(AnimationCurve::deserialize(reader)?);
+            chunk.embedded_animation_curve = Some(AnimationCurve::deserialize(reader).field("embedded_animation_curve")?);
         } else {
             chunk.animation_curve_id = Some(reader.read_u32()?);
         }
@@ -583,14 +619,14 @@ impl Serialize for CurveData {
 }
 
 #[derive(Default, Clone)]
-pub struct TextKeyframes {
+pub struct TextData {
     pub text: BString,
     pub wrap: bool,
     pub alignment: AlignmentMagic,
     pub font_index: i32,
 }
 
-impl Serialize for TextKeyframes {
+impl Serialize for TextData {
     fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
             where R: Read + Seek {
         let mut chunk = Self {
@@ -2,8 +2,8 @@ use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
 use bitflags::bitflags;
 use bstr::BString;
 use byteorder::WriteBytesExt;
-use tracing::info;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use tracing::warn;
+use std::io::{Read, Result, Seek, SeekFrom, Write};
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -65,6 +65,62 @@ impl Default for Shader {
     }
 }
 
+impl Shader {
+    // HLSL11 blobs don't store their length, so it comes from the DXBC header or the next blob
+    fn read_blob<R>(reader: &mut Reader<R>, ptr: u32, length: Option<u32>, next: &[u32]) -> Result<Vec<u8>>
+        where R: Read + Seek,
+    {
+        if ptr == 0 {
+            return Ok(Vec::new());
+        }
+        let return_to = reader.stream_position()?;
+        reader.seek(SeekFrom::Start(ptr as _))?;
+        let length = match length {
+            Some(length) => length,
+            None => {
+                let magic = reader.read_bytes::<4>()?;
+                reader.seek_relative(20)?;
+                let size = reader.read_u32()?;
+                reader.seek(SeekFrom::Start(ptr as _))?;
+                if &magic == b"DXBC" {
+                    size
+                } else {
+                    let end = next.iter().copied().filter(|next| *next > ptr).min()
+                        .unwrap_or(reader.current_chunk.end_offset as u32);
+                    warn!("Shader blob at offset {} has no DXBC header, reading until offset {}", ptr, end);
+                    end.saturating_sub(ptr)
+                }
+            }
+        };
+        let data = reader.read_vec(length as usize)?;
+        reader.seek(SeekFrom::Start(return_to))?;
+
+        Ok(data)
+    }
+
+    fn write_blob_pointer<W>(writer: &mut Writer<W>, data: &[u8]) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        if data.is_empty() {
+            writer.write_u32(0)
+        } else {
+            writer.write_pointer(data)
+        }
+    }
+
+    fn write_blob<W>(writer: &mut Writer<W>, data: &[u8]) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        if !data.is_empty() {
+            writer.pad_check_byte(8, 0)?;
+            writer.register_pointer(data)?;
+            writer.write_bytes(data)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl Serialize for Shader {
     fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
         where R: Read + Seek,
@@ -74,28 +130,81 @@ impl Serialize for Shader {
         };
 
         chunk.name = reader.read_pointer_string()?;
-        chunk.kind = ShaderType::from_bits_retain(reader.read_i32()?);
+        chunk.kind = ShaderType::from_bits_retain(reader.read_i32()? & 0x7fffffff); // The highest bit is always set
         chunk.glsl_es_vertex = reader.read_pointer_string()?;
         chunk.glsl_es_fragment = reader.read_pointer_string()?;
         chunk.glsl_vertex = reader.read_pointer_string()?;
         chunk.glsl_fragment = reader.read_pointer_string()?;
         chunk.hlsl9_vertex = reader.read_pointer_string()?;
         chunk.hlsl9_fragment = reader.read_pointer_string()?;
-        let ptr1 = reader.read_i32()?;
-        info!("{:?}", ptr1);
+        let hlsl11_vertex_ptr = reader.read_u32()?;
+        let hlsl11_pixel_ptr = reader.read_u32()?;
+        for _ in 0..reader.read_u32()? {
+            chunk.vertex_attributes.push(reader.read_pointer_string()?);
+        }
+        if reader.version_info.format_id > 13 {
+            chunk.version = reader.read_i32()?;
+        }
+        // (Pointer, Length) pairs
+        let pssl_vertex = (reader.read_u32()?, reader.read_u32()?);
+        let pssl_pixel = (reader.read_u32()?, reader.read_u32()?);
+        let cg_psv_vertex = (reader.read_u32()?, reader.read_u32()?);
+        let cg_psv_pixel = (reader.read_u32()?, reader.read_u32()?);
+        let mut cg_ps3_vertex = (0, 0);
+        let mut cg_ps3_pixel = (0, 0);
+        if chunk.version >= 2 {
+            cg_ps3_vertex = (reader.read_u32()?, reader.read_u32()?);
+            cg_ps3_pixel = (reader.read_u32()?, reader.read_u32()?);
+        }
 
-        todo!("Not implemented");
-        // TODO: UTY's team didn't code shaders so I'll look into it later with another game
-        //chunk.hlsl11_vertex_buffer = reader.read_pointer_object(ptr1 as usize)?;
-        //let ptr2 = reader.read_i32()?;
-        //chunk.hlsl11_pixel_buffer = reader.read_pointer_object(ptr2 as usize)?;
+        let next = [hlsl11_vertex_ptr, hlsl11_pixel_ptr, pssl_vertex.0, pssl_pixel.0, cg_psv_vertex.0, cg_psv_pixel.0, cg_ps3_vertex.0, cg_ps3_pixel.0];
+        chunk.hlsl11_vertex_buffer = Self::read_blob(reader, hlsl11_vertex_ptr, None, &next)?;
+        chunk.hlsl11_pixel_buffer = Self::read_blob(reader, hlsl11_pixel_ptr, None, &next)?;
+        chunk.pssl_vertex_buffer = Self::read_blob(reader, pssl_vertex.0, Some(pssl_vertex.1), &next)?;
+        chunk.pssl_pixel_buffer = Self::read_blob(reader, pssl_pixel.0, Some(pssl_pixel.1), &next)?;
+        chunk.cg_psv_vertex_buffer = Self::read_blob(reader, cg_psv_vertex.0, Some(cg_psv_vertex.1), &next)?;
+        chunk.cg_psv_pixel_buffer = Self::read_blob(reader, cg_psv_pixel.0, Some(cg_psv_pixel.1), &next)?;
+        chunk.cg_ps3_vertex_buffer = Self::read_blob(reader, cg_ps3_vertex.0, Some(cg_ps3_vertex.1), &next)?;
+        chunk.cg_ps3_pixel_buffer = Self::read_blob(reader, cg_ps3_pixel.0, Some(cg_ps3_pixel.1), &next)?;
 
-        //chunk
+        Ok(chunk)
     }
 
-    fn serialize<W>(_chunk: &Self, _writer: &mut Writer<W>) -> Result<()>
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
         where W: Write + WriteBytesExt + Seek,
     {
-        todo!("Not implemented");
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_i32(chunk.kind.bits() | i32::MIN)?;
+        writer.write_pointer_string(&chunk.glsl_es_vertex)?;
+        writer.write_pointer_string(&chunk.glsl_es_fragment)?;
+        writer.write_pointer_string(&chunk.glsl_vertex)?;
+        writer.write_pointer_string(&chunk.glsl_fragment)?;
+        writer.write_pointer_string(&chunk.hlsl9_vertex)?;
+        writer.write_pointer_string(&chunk.hlsl9_fragment)?;
+        Self::write_blob_pointer(writer, &chunk.hlsl11_vertex_buffer)?;
+        Self::write_blob_pointer(writer, &chunk.hlsl11_pixel_buffer)?;
+        writer.write_u32(chunk.vertex_attributes.len() as _)?;
+        for attribute in chunk.vertex_attributes.iter() {
+            writer.write_pointer_string(attribute)?;
+        }
+        if writer.version_info.format_id > 13 {
+            writer.write_i32(chunk.version)?;
+        }
+        let mut buffers = vec![&chunk.pssl_vertex_buffer, &chunk.pssl_pixel_buffer, &chunk.cg_psv_vertex_buffer, &chunk.cg_psv_pixel_buffer];
+        if chunk.version >= 2 {
+            buffers.extend([&chunk.cg_ps3_vertex_buffer, &chunk.cg_ps3_pixel_buffer]);
+        }
+        for buffer in buffers.iter() {
+            Self::write_blob_pointer(writer, buffer)?;
+            writer.write_u32(buffer.len() as _)?;
+        }
+
+        Self::write_blob(writer, &chunk.hlsl11_vertex_buffer)?;
+        Self::write_blob(writer, &chunk.hlsl11_pixel_buffer)?;
+        for buffer in buffers {
+            Self::write_blob(writer, buffer)?;
+        }
+
+        Ok(())
     }
 }
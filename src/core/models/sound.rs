@@ -2,7 +2,7 @@ use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
 use bstr::BString;
 use byteorder::WriteBytesExt;
 use bitflags::bitflags;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -22,8 +22,8 @@ pub struct Sound {
     pub effects: u32,
     pub volume: f32,
     pub pitch: f32,
-    pub audio_id: i32,
-    pub group_id: i32,
+    pub audio_id: i32, // Index into the AUDO chunk holding the sound, see audio::find
+    pub group_id: i32, // Audio group, every group but the builtin one is loaded from its own audiogroupN.dat
     pub preload: bool,
 }
 
@@ -42,7 +42,7 @@ impl Serialize for Sound {
         };
 
         chunk.name = reader.read_pointer_string()?;
-        chunk.flags = AudioEntryFlags::from_bits_truncate(reader.read_u32()?);
+        chunk.flags = AudioEntryFlags::from_bits_retain(reader.read_u32()?);
         chunk.kind = reader.read_pointer_string_safe()?;
         chunk.file = reader.read_pointer_string()?;
         chunk.effects = reader.read_u32()?;
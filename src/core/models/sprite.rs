@@ -1,9 +1,10 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList};
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, shared::Shared, GMVersionInfo};
+use super::{texture_page_item::TexturePageItem, sequence::Sequence};
 use bitflags::bitflags;
 use bstr::BString;
 use byteorder::WriteBytesExt;
 use tracing::warn;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Error, ErrorKind, Read, Result, Seek, Write};
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -74,18 +75,82 @@ pub struct Sprite {
     pub origin_x: i32,
     pub origin_y: i32,
     pub special_or_gms2: bool,
+    pub special_version: i32, // Only for special sprites
     pub sprite_type: SpriteType,
-    pub buffer: Vec<u8>,
+    pub swf_version: i32, // Only for SWF sprites, 7 or 8
+    pub buffer: Vec<u8>, // YYSWF timeline or Spine data, not parsed, only kept as is so it can be written back
     pub gms2_playback_speed: f32,
     pub gms2_playback_speed_type: AnimSpeedType,
-    pub gms2_3_sequence: u32, //SequenceReference,
-    pub gms2_3_2_nine_slice: NineSlice,
-    pub texture_items: GMPointerList<BString>, // TODO: Change this to Texture Item
-    pub collision_masks: Vec<Vec<u8>>,
+    pub gms2_3_sequence: Option<SequenceReference>, // From 2.3>=, mirrors the playback speed of the sprite
+    pub gms2_3_2_nine_slice: Option<NineSlice>,
+    pub texture_items: Vec<Option<Shared<TexturePageItem>>>, // One per frame, these live in the TPAG chunk
+    pub collision_masks: Vec<Vec<u8>>, // One bit per pixel, rows padded to a full byte
 }
 
-impl Serialize for Sprite {
-    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+impl Sprite {
+    pub fn set_playback_speed(&mut self, speed: f32, speed_type: AnimSpeedType) {
+        self.gms2_playback_speed = speed;
+        self.gms2_playback_speed_type = speed_type;
+        if let Some(sequence) = self.gms2_3_sequence.as_mut() {
+            sequence.sequence.playback_speed = speed;
+            sequence.sequence.playback_speed_type = speed_type;
+        }
+    }
+
+    // Left, top, width and height of the area the collision masks cover.
+    // That's the whole sprite, or only its bounding box from 2024.6>=
+    fn mask_area(&self, version_info: &GMVersionInfo) -> (i64, i64, i64, i64) {
+        if version_info.is_version_at_least(2024, 6, 0, 0) {
+            let (left, top) = (self.margin_left as i64, self.margin_top as i64);
+            (left, top, self.margin_right as i64 - left + 1, self.margin_bottom as i64 - top + 1)
+        } else {
+            (0, 0, self.width as i64, self.height as i64)
+        }
+    }
+
+    // Size in bytes of every collision mask
+    pub fn mask_size(&self, version_info: &GMVersionInfo) -> usize {
+        let (_, _, width, height) = self.mask_area(version_info);
+        (width.max(0) as usize).div_ceil(8) * height.max(0) as usize
+    }
+
+    // Byte index and bit of a pixel in sprite coordinates, if the masks cover it
+    fn mask_bit(&self, version_info: &GMVersionInfo, x: i32, y: i32) -> Option<(usize, u8)> {
+        let (left, top, width, height) = self.mask_area(version_info);
+        let (x, y) = (x as i64 - left, y as i64 - top);
+        if x < 0 || y < 0 || x >= width || y >= height {
+            return None;
+        }
+        let stride = (width as usize).div_ceil(8);
+        Some((y as usize * stride + x as usize / 8, 0x80 >> (x % 8)))
+    }
+
+    pub fn mask_pixel(&self, version_info: &GMVersionInfo, mask: usize, x: i32, y: i32) -> bool {
+        let Some((index, bit)) = self.mask_bit(version_info, x, y) else {
+            return false;
+        };
+        self.collision_masks.get(mask)
+            .and_then(|mask| mask.get(index))
+            .is_some_and(|byte| byte & bit != 0)
+    }
+
+    pub fn set_mask_pixel(&mut self, version_info: &GMVersionInfo, mask: usize, x: i32, y: i32, value: bool) {
+        let Some((index, bit)) = self.mask_bit(version_info, x, y) else {
+            return;
+        };
+        let size = self.mask_size(version_info);
+        if let Some(mask) = self.collision_masks.get_mut(mask) {
+            mask.resize(size, 0);
+            if value {
+                mask[index] |= bit;
+            } else {
+                mask[index] &= !bit;
+            }
+        }
+    }
+
+    // SWF and Spine bodies are opaque and carry no length, so they run until `end`, or the sequence or nine slice if there's one
+    pub fn deserialize_until<R>(reader: &mut Reader<R>, end: u64) -> Result<Self>
         where R: Read + Seek,
     {
         let mut chunk = Self {
@@ -99,84 +164,266 @@ impl Serialize for Sprite {
         chunk.margin_right = reader.read_i32()?;
         chunk.margin_bottom = reader.read_i32()?;
         chunk.margin_top = reader.read_i32()?;
-        chunk.transparent = reader.read_bool()?;
-        chunk.smooth = reader.read_bool()?;
-        chunk.preload = reader.read_bool()?;
+        chunk.transparent = reader.read_wide_bool()?;
+        chunk.smooth = reader.read_wide_bool()?;
+        chunk.preload = reader.read_wide_bool()?;
         chunk.bbox_mode = reader.read_u32()?;
-        chunk.sep_masks = SepMaskType::from_bits_truncate(reader.read_i32()?);
+        chunk.sep_masks = SepMaskType::from_bits_retain(reader.read_i32()?);
         chunk.origin_x = reader.read_i32()?;
         chunk.origin_y = reader.read_i32()?;
         if reader.read_i32()? == -1 {
             chunk.special_or_gms2 = true;
-            let version = reader.read_i32()?;
+            chunk.special_version = reader.read_i32()?;
             chunk.sprite_type = SpriteType::from_bits_retain(reader.read_i32()?);
+            let mut sequence_ptr = 0;
+            let mut nine_slice_ptr = 0;
             if reader.version_info.is_version_at_least(2, 0, 0, 0) {
                 chunk.gms2_playback_speed = reader.read_f32()?;
                 chunk.gms2_playback_speed_type = AnimSpeedType::from_bits_retain(reader.read_i32()?);
-                if version >= 2 {
-                    //chunk.gms2_3_sequence = SequenceReference::deserialize(reader);
-                    chunk.gms2_3_sequence = reader.read_u32()?;
-                    if version >= 3 {
-                        reader.version_info.set_version(2, 3, 2, 0);
-                        chunk.gms2_3_2_nine_slice = reader.read_pointer_object::<NineSlice>()?;
+                if chunk.special_version >= 2 {
+                    sequence_ptr = reader.read_u32()?;
+                    if chunk.special_version >= 3 {
+                        nine_slice_ptr = reader.read_u32()?;
                     }
                 }
             }
 
-            // TODO: Finish this up
+            // The sequence and nine slice come right after the body
+            let body_end = [sequence_ptr, nine_slice_ptr].into_iter()
+                .filter(|ptr| *ptr != 0)
+                .map(|ptr| ptr as u64)
+                .min()
+                .unwrap_or(end);
             match chunk.sprite_type {
                 SpriteType::Normal => {
-
+                    chunk.read_textures(reader)?;
+                    chunk.read_masks(reader)?;
                 }
                 SpriteType::Swf => {
-
+                    chunk.swf_version = reader.read_i32()?;
+                    if chunk.swf_version != 7 && chunk.swf_version != 8 {
+                        warn!("Unexpected SWF version {} in sprite {}", chunk.swf_version, chunk.name);
+                    }
+                    if chunk.swf_version == 8 {
+                        chunk.read_textures(reader)?;
+                    }
+                    chunk.read_buffer(reader, body_end)?;
                 }
                 SpriteType::Spine => {
-
+                    chunk.read_buffer(reader, body_end)?;
                 }
                 _ => {
-                    panic!("Unexpected sprite type");
+                    return Err(Error::new(ErrorKind::InvalidData, format!("Unexpected sprite type {} in sprite {}", chunk.sprite_type.bits(), chunk.name)));
                 }
             }
+
+            if sequence_ptr != 0 {
+                chunk.gms2_3_sequence = Some(reader.read_pointer_object_ext::<SequenceReference>(sequence_ptr as _, false).field("gms2_3_sequence")?);
+            }
+            if nine_slice_ptr != 0 {
+                chunk.gms2_3_2_nine_slice = Some(reader.read_pointer_object_ext::<NineSlice>(nine_slice_ptr as _, false).field("gms2_3_2_nine_slice")?);
+            }
         } else {
             reader.seek_relative(-4)?;
+            chunk.read_textures(reader)?;
+            chunk.read_masks(reader)?;
         }
 
         Ok(chunk)
     }
 
-    fn serialize<W>(_chunk: &Self, _writer: &mut Writer<W>) -> Result<()>
+    fn read_textures<R>(&mut self, reader: &mut Reader<R>) -> Result<()>
+        where R: Read + Seek,
+    {
+        for index in 0..reader.read_u32()? {
+            self.texture_items.push(reader.read_pointer_object_safe::<Shared<TexturePageItem>>().element("texture_items", index as _)?);
+        }
+
+        Ok(())
+    }
+
+    fn read_masks<R>(&mut self, reader: &mut Reader<R>) -> Result<()>
+        where R: Read + Seek,
+    {
+        let size = self.mask_size(&reader.version_info);
+        let count = reader.read_u32()?;
+        // Width and height come from the file, so make sure the masks fit in the chunk before reading any
+        let available = reader.current_chunk.end_offset.saturating_sub(reader.stream_position()?);
+        if (count as u64).saturating_mul(size.max(1) as u64) > available {
+            return Err(Error::new(ErrorKind::InvalidData, format!("{} collision masks of {} bytes don't fit in the {} bytes left", count, size, available)));
+        }
+        for index in 0..count {
+            self.collision_masks.push(reader.read_vec(size).element("collision_masks", index as _)?);
+        }
+        reader.pad_check_byte(4, 0)?;
+
+        Ok(())
+    }
+
+    fn read_buffer<R>(&mut self, reader: &mut Reader<R>, end: u64) -> Result<()>
+        where R: Read + Seek,
+    {
+        let start = reader.stream_position()?;
+        if end < start {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Sprite {} data ends before it starts", self.name)));
+        }
+        self.buffer = reader.read_vec((end - start) as usize)?;
+
+        Ok(())
+    }
+
+    fn write_textures<W>(&self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_u32(self.texture_items.len() as _)?;
+        for texture_item in self.texture_items.iter() {
+            writer.write_pointer_object_safe(texture_item)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_masks<W>(&self, writer: &mut Writer<W>) -> Result<()>
         where W: Write + WriteBytesExt + Seek,
     {
-        todo!("Not implemented");
+        let size = self.mask_size(&writer.version_info);
+        writer.write_u32(self.collision_masks.len() as _)?;
+        for mask in self.collision_masks.iter() {
+            if mask.len() != size {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Collision mask of sprite {} is {} bytes long, expected {}", self.name, mask.len(), size)));
+            }
+            writer.write_bytes(mask)?;
+        }
+        writer.pad_check_byte(4, 0)?;
+
+        Ok(())
+    }
+}
+
+impl Serialize for Sprite {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let end = reader.current_chunk.end_offset;
+        Self::deserialize_until(reader, end)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        writer.write_i32(chunk.width)?;
+        writer.write_i32(chunk.height)?;
+        writer.write_i32(chunk.margin_left)?;
+        writer.write_i32(chunk.margin_right)?;
+        writer.write_i32(chunk.margin_bottom)?;
+        writer.write_i32(chunk.margin_top)?;
+        writer.write_wide_bool(chunk.transparent)?;
+        writer.write_wide_bool(chunk.smooth)?;
+        writer.write_wide_bool(chunk.preload)?;
+        writer.write_u32(chunk.bbox_mode)?;
+        writer.write_i32(chunk.sep_masks.bits())?;
+        writer.write_i32(chunk.origin_x)?;
+        writer.write_i32(chunk.origin_y)?;
+        if chunk.special_or_gms2 {
+            writer.write_i32(-1)?;
+            writer.write_i32(chunk.special_version)?;
+            writer.write_i32(chunk.sprite_type.bits())?;
+            if writer.version_info.is_version_at_least(2, 0, 0, 0) {
+                writer.write_f32(chunk.gms2_playback_speed)?;
+                writer.write_i32(chunk.gms2_playback_speed_type.bits())?;
+                if chunk.special_version >= 2 {
+                    match &chunk.gms2_3_sequence {
+                        Some(sequence) => writer.write_pointer_object(sequence)?,
+                        None => writer.write_u32(0)?,
+                    }
+                    if chunk.special_version >= 3 {
+                        match &chunk.gms2_3_2_nine_slice {
+                            Some(nine_slice) => writer.write_pointer_object(nine_slice)?,
+                            None => writer.write_u32(0)?,
+                        }
+                    }
+                }
+            }
+
+            match chunk.sprite_type {
+                SpriteType::Normal => {
+                    chunk.write_textures(writer)?;
+                    chunk.write_masks(writer)?;
+                }
+                SpriteType::Swf => {
+                    writer.write_i32(chunk.swf_version)?;
+                    if chunk.swf_version == 8 {
+                        chunk.write_textures(writer)?;
+                    }
+                    writer.write_bytes(&chunk.buffer)?;
+                }
+                SpriteType::Spine => {
+                    writer.write_bytes(&chunk.buffer)?;
+                }
+                _ => {
+                    return Err(Error::new(ErrorKind::InvalidData, format!("Unexpected sprite type {} in sprite {}", chunk.sprite_type.bits(), chunk.name)));
+                }
+            }
+
+            if chunk.special_version >= 2 {
+                if let Some(sequence) = &chunk.gms2_3_sequence {
+                    writer.register_pointer(sequence)?;
+                    if sequence.sequence.playback_speed == chunk.gms2_playback_speed && sequence.sequence.playback_speed_type == chunk.gms2_playback_speed_type {
+                        SequenceReference::serialize(sequence, writer)?;
+                    } else {
+                        // The playback of the sprite was edited without going through set_playback_speed
+                        let mut sequence = sequence.clone();
+                        sequence.sequence.playback_speed = chunk.gms2_playback_speed;
+                        sequence.sequence.playback_speed_type = chunk.gms2_playback_speed_type;
+                        SequenceReference::serialize(&sequence, writer)?;
+                    }
+                }
+            }
+            if chunk.special_version >= 3 {
+                if let Some(nine_slice) = &chunk.gms2_3_2_nine_slice {
+                    writer.register_pointer(nine_slice)?;
+                    NineSlice::serialize(nine_slice, writer)?;
+                }
+            }
+        } else {
+            chunk.write_textures(writer)?;
+            chunk.write_masks(writer)?;
+        }
+
+        Ok(())
     }
 }
 
 #[derive(Default, Clone)]
 pub struct SequenceReference {
+    pub sequence: Sequence,
 }
 
 impl Serialize for SequenceReference {
     fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
         where R: Read + Seek,
     {
-        let _chunk = Self {
-            //..Default::default()
+        let mut chunk = Self {
+            ..Default::default()
         };
 
-        if reader.read_i32()? != 1 {
-            warn!("Unexpected version for sequence reference in Sprite.");
+        let version = reader.read_i32()?;
+        if version != 1 {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Unexpected version {} for sequence reference in Sprite", version)));
         }
+        chunk.sequence = Sequence::deserialize(reader).field("sequence")?;
 
-        todo!("Not implemented."); // This is quite literally a Sequence, not a pointer reference
-
-        //chunk
+        Ok(chunk)
     }
 
-    fn serialize<W>(_chunk: &Self, _writer: &mut Writer<W>) -> Result<()>
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
         where W: Write + WriteBytesExt + Seek,
     {
-        todo!("Not implemented.");
+        writer.write_i32(1)?;
+        Sequence::serialize(&chunk.sequence, writer)?;
+
+        Ok(())
     }
 }
 
@@ -187,7 +434,7 @@ pub struct NineSlice {
     pub right: i32,
     pub bottom: i32,
     pub enabled: bool,
-    pub tile_modes: Vec<TileMode>,
+    pub tile_modes: [TileMode; 5],
 }
 
 impl Serialize for NineSlice {
@@ -203,8 +450,8 @@ impl Serialize for NineSlice {
         chunk.right = reader.read_i32()?;
         chunk.bottom = reader.read_i32()?;
         chunk.enabled = reader.read_wide_bool()?;
-        for _ in 0..5 {
-            chunk.tile_modes.push(TileMode::from_bits_retain(reader.read_i32()?));
+        for tile_mode in chunk.tile_modes.iter_mut() {
+            *tile_mode = TileMode::from_bits_retain(reader.read_i32()?);
         }
 
         Ok(chunk)
@@ -0,0 +1,112 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use bitflags::bitflags;
+use bstr::BString;
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct TextureGroupLoadType: i32 {
+        const InFile = 0;
+        const SeparateGroup = 1;
+        const SeparateTextures = 2;
+    }
+}
+
+impl Default for TextureGroupLoadType {
+    fn default() -> Self {
+        Self::InFile
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct TextureGroupInfo {
+    pub name: BString,
+    pub directory: BString, // From 2022.9>=, relative to the data file
+    pub extension: BString, // From 2022.9>=
+    pub load_type: TextureGroupLoadType, // From 2022.9>=
+    pub texture_pages: Vec<i32>, // IDs of the embedded textures in TXTR
+    pub sprites: Vec<i32>,
+    pub spine_sprites: Vec<i32>,
+    pub fonts: Vec<i32>,
+    pub tilesets: Vec<i32>, // IDs of the backgrounds in BGND
+}
+
+impl TextureGroupInfo {
+    // Where a page of this group is loaded from when it is not embedded in the data file
+    pub fn external_file_name(&self, index_in_group: i32) -> String {
+        format!("{}_{}{}", self.name, index_in_group, self.extension)
+    }
+
+    fn read_ids<R>(reader: &mut Reader<R>) -> Result<Vec<i32>>
+        where R: Read + Seek,
+    {
+        let ptr = reader.read_u32()?;
+        let return_to = reader.stream_position()?;
+        reader.seek(SeekFrom::Start(ptr as _))?;
+        let mut ids = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            ids.push(reader.read_i32()?);
+        }
+        reader.seek(SeekFrom::Start(return_to))?;
+
+        Ok(ids)
+    }
+
+    fn write_ids<W>(ids: &[i32], writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_u32(ids.len() as u32)?;
+        for id in ids.iter() {
+            writer.write_i32(*id)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for TextureGroupInfo {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        if reader.version_info.is_version_at_least(2022, 9, 0, 0) {
+            chunk.directory = reader.read_pointer_string()?;
+            chunk.extension = reader.read_pointer_string()?;
+            chunk.load_type = TextureGroupLoadType::from_bits_retain(reader.read_i32()?);
+        }
+        chunk.texture_pages = Self::read_ids(reader)?;
+        chunk.sprites = Self::read_ids(reader)?;
+        chunk.spine_sprites = Self::read_ids(reader)?;
+        chunk.fonts = Self::read_ids(reader)?;
+        chunk.tilesets = Self::read_ids(reader)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        if writer.version_info.is_version_at_least(2022, 9, 0, 0) {
+            writer.write_pointer_string(&chunk.directory)?;
+            writer.write_pointer_string(&chunk.extension)?;
+            writer.write_i32(chunk.load_type.bits())?;
+        }
+        writer.write_pointer(&chunk.texture_pages)?;
+        writer.write_pointer(&chunk.sprites)?;
+        writer.write_pointer(&chunk.spine_sprites)?;
+        writer.write_pointer(&chunk.fonts)?;
+        writer.write_pointer(&chunk.tilesets)?;
+        for ids in [&chunk.texture_pages, &chunk.sprites, &chunk.spine_sprites, &chunk.fonts, &chunk.tilesets] {
+            writer.register_pointer(ids)?;
+            Self::write_ids(ids, writer)?;
+        }
+
+        Ok(())
+    }
+}
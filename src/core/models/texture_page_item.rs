@@ -0,0 +1,60 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+#[derive(Default, Clone)]
+pub struct TexturePageItem {
+    pub source_x: u16, // Rectangle inside of the texture page
+    pub source_y: u16,
+    pub source_width: u16,
+    pub source_height: u16,
+    pub target_x: u16, // Rectangle inside of the bounding box
+    pub target_y: u16,
+    pub target_width: u16,
+    pub target_height: u16,
+    pub bounding_width: u16,
+    pub bounding_height: u16,
+    pub texture_page_id: i16, // Index of the texture page in the TXTR chunk
+}
+
+impl Serialize for TexturePageItem {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.source_x = reader.read_u16()?;
+        chunk.source_y = reader.read_u16()?;
+        chunk.source_width = reader.read_u16()?;
+        chunk.source_height = reader.read_u16()?;
+        chunk.target_x = reader.read_u16()?;
+        chunk.target_y = reader.read_u16()?;
+        chunk.target_width = reader.read_u16()?;
+        chunk.target_height = reader.read_u16()?;
+        chunk.bounding_width = reader.read_u16()?;
+        chunk.bounding_height = reader.read_u16()?;
+        chunk.texture_page_id = reader.read_i16()?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_u16(chunk.source_x)?;
+        writer.write_u16(chunk.source_y)?;
+        writer.write_u16(chunk.source_width)?;
+        writer.write_u16(chunk.source_height)?;
+        writer.write_u16(chunk.target_x)?;
+        writer.write_u16(chunk.target_y)?;
+        writer.write_u16(chunk.target_width)?;
+        writer.write_u16(chunk.target_height)?;
+        writer.write_u16(chunk.bounding_width)?;
+        writer.write_u16(chunk.bounding_height)?;
+        writer.write_i16(chunk.texture_page_id)?;
+
+        Ok(())
+    }
+}
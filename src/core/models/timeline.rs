@@ -1,12 +1,29 @@
-use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use crate::core::{error::ErrorContext, reader::Reader, serializing::Serialize, writer::Writer, lists::GMPointerList};
 use bstr::BString;
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
+use super::object::Action;
 
 #[derive(Default, Clone)]
 pub struct Timeline {
-    name: BString,
-    moments: Vec<(i32, u32)>, // TODO: Change u32 for Object::Event::Action
+    pub name: BString,
+    pub moments: Vec<TimelineMoment>,
+}
+
+#[derive(Default, Clone)]
+pub struct TimelineMoment {
+    pub time: i32, // Step the actions run at
+    pub actions: GMPointerList<Action>, // Same as the actions of an object event
+}
+
+impl Timeline {
+    pub fn moment_at(&self, time: i32) -> Option<&TimelineMoment> {
+        self.moments.iter().find(|moment| moment.time == time)
+    }
+
+    pub fn moment_at_mut(&mut self, time: i32) -> Option<&mut TimelineMoment> {
+        self.moments.iter_mut().find(|moment| moment.time == time)
+    }
 }
 
 impl Serialize for Timeline {
@@ -20,7 +37,10 @@ impl Serialize for Timeline {
         chunk.name = reader.read_pointer_string()?;
         for _ in 0..reader.read_u32()? {
             let time = reader.read_i32()?;
-            chunk.moments.push((time, reader.read_u32()?));
+            chunk.moments.push(TimelineMoment {
+                time,
+                actions: reader.read_pointer_object::<GMPointerList<Action>>().field("actions")?,
+            });
         }
 
         Ok(chunk)
@@ -32,8 +52,12 @@ impl Serialize for Timeline {
         writer.write_pointer_string(&chunk.name)?;
         writer.write_u32(chunk.moments.len() as u32)?;
         for moment in chunk.moments.iter() {
-            writer.write_i32(moment.0)?;
-            writer.write_pointer::<u32>(0)?; // TODO: Write pointer instead
+            writer.write_i32(moment.time)?;
+            writer.write_pointer_object(&moment.actions)?;
+        }
+        // The action lists follow the moment table
+        for moment in chunk.moments.iter() {
+            moment.actions.serialize(writer, None, None)?;
         }
 
         Ok(())
@@ -0,0 +1,72 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer, references::{self, CodeLocation}};
+use bitflags::bitflags;
+use bstr::BString;
+use byteorder::WriteBytesExt;
+use std::io::{Read, Result, Seek, Write};
+
+bitflags! {
+    // Values of 0 and above are object indices
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct InstanceType: i32 {
+        const Undefined = 0;
+        const Self_ = -1;
+        const Other = -2;
+        const All = -3;
+        const Noone = -4;
+        const Global = -5;
+        const Builtin = -6;
+        const Local = -7;
+        const StackTop = -9;
+        const Argument = -15;
+        const Static = -16;
+    }
+}
+
+impl Default for InstanceType {
+    fn default() -> Self {
+        Self::Undefined
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct Variable {
+    pub name: BString,
+    pub instance_type: InstanceType, // From bytecode 15>=
+    pub var_id: i32, // From bytecode 15>=
+    pub occurrences: u32,
+    pub first_occurrence: Option<CodeLocation>, // First instruction referencing this variable, the rest are chained from it
+}
+
+impl Serialize for Variable {
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let mut chunk = Self {
+            ..Default::default()
+        };
+
+        chunk.name = reader.read_pointer_string()?;
+        if reader.version_info.format_id >= 15 {
+            chunk.instance_type = InstanceType::from_bits_retain(reader.read_i32()?);
+            chunk.var_id = reader.read_i32()?;
+        }
+        chunk.occurrences = reader.read_u32()?;
+        chunk.first_occurrence = references::read_first_occurrence(reader, chunk.occurrences)?;
+
+        Ok(chunk)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        writer.write_pointer_string(&chunk.name)?;
+        if writer.version_info.format_id >= 15 {
+            writer.write_i32(chunk.instance_type.bits())?;
+            writer.write_i32(chunk.var_id)?;
+        }
+        writer.write_u32(chunk.occurrences)?;
+        references::write_first_occurrence(writer, chunk.first_occurrence)?;
+
+        Ok(())
+    }
+}
@@ -1,9 +1,9 @@
-use crate::core::chunks::{dummy::DummyChunk, gen8::ChunkGEN8, ChunkOutput, optn::ChunkOPTN, lang::ChunkLANG, extn::ChunkEXTN, sond::ChunkSOND, agrp::ChunkAGRP, sprt::ChunkSPRT, bgnd::ChunkBGND, path::ChunkPATH, scpt::ChunkSCPT, glob::ChunkGLOB, shdr::ChunkSHDR, font::ChunkFONT, tmln::ChunkTMLN, objt::ChunkOBJT, feds::ChunkFEDS, acrv::ChunkACRV, seqn::ChunkSEQN};
+use crate::core::chunks::{raw::RawChunk, gen8::ChunkGEN8, ChunkOutput, optn::ChunkOPTN, lang::ChunkLANG, extn::ChunkEXTN, sond::ChunkSOND, agrp::ChunkAGRP, sprt::ChunkSPRT, bgnd::ChunkBGND, path::ChunkPATH, scpt::ChunkSCPT, glob::ChunkGLOB, shdr::ChunkSHDR, font::ChunkFONT, tmln::ChunkTMLN, objt::ChunkOBJT, feds::ChunkFEDS, acrv::ChunkACRV, seqn::ChunkSEQN, room::ChunkROOM, tpag::ChunkTPAG, txtr::ChunkTXTR, strg::ChunkSTRG, code::ChunkCODE, vari::ChunkVARI, func::ChunkFUNC, audo::ChunkAUDO, tgin::ChunkTGIN, embi::ChunkEMBI, dafl::ChunkDAFL, feat::ChunkFEAT, tags::ChunkTAGS, psem::ChunkPSEM, psys::ChunkPSYS};
 use bstr::{BString, ByteSlice};
 use byteorder::{LittleEndian, ReadBytesExt};
-use std::{collections::HashMap, io::{Error, ErrorKind, Read, Result, Seek, SeekFrom}, path::PathBuf};
-use tracing::{info, error};
-use super::{GMVersionInfo, Chunk, GlobalData, serializing::{Serialize, FormatCheck}};
+use std::{any::{type_name, Any, TypeId}, collections::HashMap, io::{Error, ErrorKind, Read, Result, Seek, SeekFrom}, path::PathBuf, rc::Rc};
+use tracing::{info, warn};
+use super::{GMVersionInfo, Chunk, GlobalData, serializing::Serialize, shared::Shared, detection::VersionReport, error::{ClovyError, ClovyResult}};
 
 #[derive(Clone)]
 pub struct Reader<T>
@@ -17,6 +17,9 @@ pub struct Reader<T>
     pub current_chunk: Chunk,
     pub global_data: GlobalData,
     pub path: Option<PathBuf>,
+    pub shared_objects: HashMap<(u64, TypeId), (Rc<dyn Any>, u64)>, // Handle and end offset of every shared object read so far
+    pub version_report: VersionReport, // Why the version ended up where it is, filled in by detect_version
+    pub raw_fallback: bool, // Keep chunks that fail to parse as raw bytes instead of failing the whole file
 }
 
 impl<T> Reader<T>
@@ -32,10 +35,13 @@ impl<T> Reader<T>
             current_chunk: Chunk::default(),
             global_data: GlobalData::default(),
             path,
+            shared_objects: HashMap::new(),
+            version_report: VersionReport::default(),
+            raw_fallback: false,
         }
     }
 
-    pub fn deserialize_chunks(&mut self) -> Result<()> {
+    pub fn deserialize_chunks(&mut self) -> ClovyResult<()> {
         self.chunk_order.clear();
         self.chunk_data.clear();
         let start_pos = self.container.stream_position()?;
@@ -49,42 +55,67 @@ impl<T> Reader<T>
                     .into(),
             );
             let chunk_size = self.container.read_i32::<LittleEndian>()?;
-            match chunk_name.to_str() {
-                Ok("EXTN") => { ChunkEXTN::format_check(self)?; }
-                Ok("FONT") => { ChunkFONT::format_check(self)?; }
-                Err(e) => {
-                    return Err(Error::new(ErrorKind::InvalidData, e));
-                }
-                _ => {}
-            }
-            self.chunk_order.push(chunk_name.clone());
-            self.chunk_data.insert(chunk_name.clone(), Chunk {
+            let chunk = Chunk {
                 name: chunk_name.clone(),
                 length: chunk_size as _,
                 start_offset: self.container.stream_position()?,
                 end_offset: self.container.stream_position()? + chunk_size as u64,
-            });
-            self.chunks.entry(chunk_name).or_insert(ChunkOutput::DummyChunk(DummyChunk {
-                ..Default::default()
-            }));
+            };
+            if let Err(e) = chunk_name.to_str() {
+                return Err(ClovyError::new(ErrorKind::InvalidData, e.to_string()).in_chunk(chunk_name, Some(chunk.start_offset - 8)));
+            }
+            self.chunk_order.push(chunk_name.clone());
+            self.chunk_data.insert(chunk_name, chunk);
             self.container.seek(SeekFrom::Current(chunk_size as _))?;
         }
+        // Chunks are padded to 16 bytes in newer versions, except for the last one
+        if let Some((_, chunks)) = self.chunk_order.split_last() {
+            self.version_info.align_chunks_to_16 = chunks.iter().all(|chunk| self.chunk_data[chunk].end_offset.is_multiple_of(16));
+        }
+        self.detect_version().map_err(|e| {
+            let chunk = self.current_chunk.name.clone();
+            self.locate_chunk_error(e, &chunk)
+        })?;
         self.container.seek(SeekFrom::Start(start_pos))?;
         Ok(())
     }
 
-    pub fn deserialize(&mut self) -> Result<()> {
+    pub fn deserialize(&mut self) -> ClovyResult<()> {
         macro_rules! deserialize_chunk {
             ($name: expr, $ctype: ty) => {
-                let value = <$ctype>::deserialize(self)?;
-                self.chunks.insert($name, value.into());
+                match <$ctype>::deserialize(self) {
+                    Ok(value) => {
+                        self.chunks.insert($name, value.into());
+                    }
+                    Err(e) if self.raw_fallback => {
+                        let e = ClovyError::from(self.locate_chunk_error(e, &$name));
+                        warn!("Keeping chunk {} as raw bytes: {}", $name, e);
+                        self.container.seek(SeekFrom::Start(self.current_chunk.start_offset))?;
+                        let value = RawChunk::deserialize(self).map_err(|e| self.locate_chunk_error(e, &$name))?;
+                        self.chunks.insert($name, value.into());
+                    }
+                    Err(e) => return Err(self.locate_chunk_error(e, &$name).into()),
+                }
             }
         }
+        // The string table is loaded ahead of everything else so other chunks can look strings up in it
+        let strg = BString::from("STRG");
+        if let Some(chunk) = self.chunk_data.get(&strg).cloned() {
+            self.current_chunk = chunk;
+            self.container.seek(SeekFrom::Start(
+                self.current_chunk.start_offset,
+            ))?;
+            info!("Deserializing chunk: {}", strg);
+            deserialize_chunk!(strg.clone(), ChunkSTRG);
+        }
         for chunk in self.chunk_order.clone() {
             if !self.chunk_data.contains_key(&chunk) {
-                return Err(Error::new(ErrorKind::NotFound, "Chunk not found"));
+                return Err(ClovyError::new(ErrorKind::NotFound, "Chunk not found").in_chunk(chunk, None));
             }
-            self.current_chunk = self.chunk_data.get(&chunk).expect("Chunk not found").clone();
+            if chunk == strg {
+                continue;
+            }
+            self.current_chunk = self.chunk_data[&chunk].clone();
             self.container.seek(SeekFrom::Start(
                 self.current_chunk.start_offset,
             ))?;
@@ -109,14 +140,36 @@ impl<T> Reader<T>
                 Ok("FEDS") => { deserialize_chunk!(chunk, ChunkFEDS); }
                 Ok("ACRV") => { deserialize_chunk!(chunk, ChunkACRV); }
                 Ok("SEQN") => { deserialize_chunk!(chunk, ChunkSEQN); }
+                Ok("ROOM") => { deserialize_chunk!(chunk, ChunkROOM); }
+                Ok("TPAG") => { deserialize_chunk!(chunk, ChunkTPAG); }
+                Ok("TXTR") => { deserialize_chunk!(chunk, ChunkTXTR); }
+                Ok("CODE") => { deserialize_chunk!(chunk, ChunkCODE); }
+                Ok("VARI") => { deserialize_chunk!(chunk, ChunkVARI); }
+                Ok("FUNC") => { deserialize_chunk!(chunk, ChunkFUNC); }
+                Ok("AUDO") => { deserialize_chunk!(chunk, ChunkAUDO); }
+                Ok("TGIN") => { deserialize_chunk!(chunk, ChunkTGIN); }
+                Ok("EMBI") => { deserialize_chunk!(chunk, ChunkEMBI); }
+                Ok("DAFL") => { deserialize_chunk!(chunk, ChunkDAFL); }
+                Ok("FEAT") => { deserialize_chunk!(chunk, ChunkFEAT); }
+                Ok("TAGS") => { deserialize_chunk!(chunk, ChunkTAGS); }
+                Ok("PSEM") => { deserialize_chunk!(chunk, ChunkPSEM); }
+                Ok("PSYS") => { deserialize_chunk!(chunk, ChunkPSYS); }
                 Err(e) => {
-                    return Err(Error::new(ErrorKind::InvalidData, e));
+                    return Err(ClovyError::new(ErrorKind::InvalidData, e.to_string()).in_chunk(chunk.clone(), Some(self.current_chunk.start_offset)));
                 }
                 _ => {
-                    error!("No deserializer for chunk: {}", chunk);
+                    warn!("No deserializer for chunk: {}, keeping its raw bytes", chunk);
+                    deserialize_chunk!(chunk, RawChunk);
                 }
             }
         }
+        // External texture pages can only be found once the texture groups are known
+        let directory = self.path.as_ref().and_then(|path| path.parent()).map(|path| path.to_path_buf());
+        if let (Some(directory), Some(groups)) = (directory, self.texture_groups().cloned()) {
+            if let Some(ChunkOutput::ChunkTxtr(textures)) = self.chunks.get_mut(b"TXTR".as_slice()) {
+                textures.load_external(&groups, &directory)?;
+            }
+        }
         Ok(())
     }
 
@@ -163,7 +216,7 @@ impl<T> Reader<T>
         }
         let return_to = self.container.stream_position()?;
         self.container.seek(SeekFrom::Start(ptr))?;
-        let result = P::deserialize(self);
+        let result = P::deserialize(self).map_err(|e| self.locate_error(e, type_name::<P>(), None, ptr));
         if return_after {
             self.container.seek(SeekFrom::Start(return_to))?;
         }
@@ -174,7 +227,33 @@ impl<T> Reader<T>
         let ptr = self.read_u32()?;
         self.read_pointer_object_ext::<P>(ptr as _, true)
     }
+
+    pub fn read_pointer_object_safe<P: Serialize>(&mut self) -> Result<Option<P>> {
+        let ptr = self.read_u32()?;
+        if ptr == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.read_pointer_object_ext::<P>(ptr as _, true)?))
+    }
     
+    // Reads the object at `offset` once, following reads of the same offset and type get the same handle
+    pub fn read_shared_at<P: Serialize + 'static>(&mut self, offset: u64) -> Result<Shared<P>> {
+        let key = (offset, TypeId::of::<P>());
+        if let Some((object, end)) = self.shared_objects.get(&key) {
+            let end = *end;
+            let object = object.downcast_ref::<Shared<P>>()
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Shared object has an unexpected type"))?
+                .clone();
+            self.container.seek(SeekFrom::Start(end))?;
+            return Ok(object);
+        }
+        self.container.seek(SeekFrom::Start(offset))?;
+        let object = Shared::new(P::deserialize(self)?);
+        let end = self.container.stream_position()?;
+        self.shared_objects.insert(key, (Rc::new(object.clone()), end));
+        Ok(object)
+    }
+
     pub fn read_bool(&mut self) -> Result<bool> {
         let mut buf = [0; 1];
         self.container.read_exact(&mut buf)?;
@@ -193,14 +272,108 @@ impl<T> Reader<T>
         Ok(buf)
     }
 
-    pub fn read_pointer_string(&mut self) -> Result<BString> {
-        let mut buf = [0; 4];
+    // Reads `length` bytes, failing before allocating anything if there aren't that many left
+    pub fn read_vec(&mut self, length: usize) -> Result<Vec<u8>> {
+        let position = self.container.stream_position()?;
+        let end = self.container.seek(SeekFrom::End(0))?;
+        self.container.seek(SeekFrom::Start(position))?;
+        if length as u64 > end.saturating_sub(position) {
+            return Err(Error::new(ErrorKind::UnexpectedEof, format!("{} bytes requested, only {} left", length, end.saturating_sub(position))));
+        }
+        let mut buf = vec![0; length];
         self.container.read_exact(&mut buf)?;
-        let pos = self.stream_position()?;
-        let offset = u32::from_le_bytes(buf) as u64;
-        if offset == 0 {
-            return Err(Error::new(ErrorKind::InvalidData, "Pointer points to <null> value."));
+        Ok(buf)
+    }
+
+    pub fn strings(&self) -> Option<&ChunkSTRG> {
+        match self.chunks.get(b"STRG".as_slice()) {
+            Some(ChunkOutput::ChunkStrg(chunk)) => Some(chunk),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> Option<&ChunkCODE> {
+        match self.chunks.get(b"CODE".as_slice()) {
+            Some(ChunkOutput::ChunkCode(chunk)) => Some(chunk),
+            _ => None,
+        }
+    }
+
+    pub fn variables(&self) -> Option<&ChunkVARI> {
+        match self.chunks.get(b"VARI".as_slice()) {
+            Some(ChunkOutput::ChunkVari(chunk)) => Some(chunk),
+            _ => None,
+        }
+    }
+
+    pub fn functions(&self) -> Option<&ChunkFUNC> {
+        match self.chunks.get(b"FUNC".as_slice()) {
+            Some(ChunkOutput::ChunkFunc(chunk)) => Some(chunk),
+            _ => None,
+        }
+    }
+
+    pub fn objects(&self) -> Option<&ChunkOBJT> {
+        match self.chunks.get(b"OBJT".as_slice()) {
+            Some(ChunkOutput::ChunkObjt(chunk)) => Some(chunk),
+            _ => None,
+        }
+    }
+
+    pub fn sounds(&self) -> Option<&ChunkSOND> {
+        match self.chunks.get(b"SOND".as_slice()) {
+            Some(ChunkOutput::ChunkSond(chunk)) => Some(chunk),
+            _ => None,
+        }
+    }
+
+    pub fn audio_groups(&self) -> Option<&ChunkAGRP> {
+        match self.chunks.get(b"AGRP".as_slice()) {
+            Some(ChunkOutput::ChunkAgrp(chunk)) => Some(chunk),
+            _ => None,
+        }
+    }
+
+    pub fn audio(&self) -> Option<&ChunkAUDO> {
+        match self.chunks.get(b"AUDO".as_slice()) {
+            Some(ChunkOutput::ChunkAudo(chunk)) => Some(chunk),
+            _ => None,
+        }
+    }
+
+    pub fn texture_groups(&self) -> Option<&ChunkTGIN> {
+        match self.chunks.get(b"TGIN".as_slice()) {
+            Some(ChunkOutput::ChunkTgin(chunk)) => Some(chunk),
+            _ => None,
         }
+    }
+
+    pub fn scripts(&self) -> Option<&ChunkSCPT> {
+        match self.chunks.get(b"SCPT".as_slice()) {
+            Some(ChunkOutput::ChunkScpt(chunk)) => Some(chunk),
+            _ => None,
+        }
+    }
+
+    // Index into the STRG table of the string whose characters start at `offset`
+    pub fn string_index(&self, offset: u64) -> Option<usize> {
+        self.strings().and_then(|strings| strings.index_of(offset))
+    }
+
+    // Resolves a string ID, as used by the bytecode, into its STRG entry
+    pub fn read_string_id(&mut self) -> Result<BString> {
+        let id = self.read_u32()? as usize;
+        self.strings()
+            .and_then(|strings| strings.get(id))
+            .cloned()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("String ID {} is out of range", id)))
+    }
+
+    fn read_string_at(&mut self, offset: u64) -> Result<BString> {
+        if let Some(string) = self.strings().and_then(|strings| strings.get_by_offset(offset)) {
+            return Ok(string.clone());
+        }
+        let pos = self.stream_position()?;
         self.container.seek(SeekFrom::Start(offset))?;
         let mut str = Vec::new();
         let mut i = self.container.read_u8()?;
@@ -212,23 +385,38 @@ impl<T> Reader<T>
         Ok(BString::new(str))
     }
 
+    pub fn read_pointer_string(&mut self) -> Result<BString> {
+        let offset = self.read_u32()? as u64;
+        if offset == 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "Pointer points to <null> value."));
+        }
+        self.read_string_at(offset)
+    }
+
     pub fn read_pointer_string_safe(&mut self) -> Result<BString> {
-        let mut buf = [0; 4];
-        self.container.read_exact(&mut buf)?;
-        let pos = self.stream_position()?;
-        let offset = u32::from_le_bytes(buf) as u64;
+        let offset = self.read_u32()? as u64;
         if offset == 0 {
             return Ok(BString::new(Vec::new()));
         }
-        self.container.seek(SeekFrom::Start(offset))?;
-        let mut str = Vec::new();
-        let mut i = self.container.read_u8()?;
-        while i != 0 {
-            str.push(i);
-            i = self.container.read_u8()?;
+        self.read_string_at(offset)
+    }
+
+    // Keeps null pointers apart from pointers to an empty string, so they're written back as null
+    pub fn read_pointer_string_option(&mut self) -> Result<Option<BString>> {
+        let offset = self.read_u32()? as u64;
+        if offset == 0 {
+            return Ok(None);
         }
-        self.container.seek(SeekFrom::Start(pos))?;
-        Ok(BString::new(str))
+        self.read_string_at(offset).map(Some)
+    }
+
+    // Same as read_pointer_string, also returning the index of the referenced STRG entry
+    pub fn read_pointer_string_indexed(&mut self) -> Result<(BString, Option<usize>)> {
+        let offset = self.read_u32()? as u64;
+        if offset == 0 {
+            return Ok((BString::new(Vec::new()), None));
+        }
+        Ok((self.read_string_at(offset)?, self.string_index(offset)))
     }
 
     pub fn read_u8(&mut self) -> Result<u8> { self.container.read_u8() }
@@ -0,0 +1,136 @@
+use crate::core::{chunks::code::ChunkCODE, reader::Reader, writer::Writer};
+use byteorder::WriteBytesExt;
+use integer_hasher::IntMap;
+use std::io::{Error, ErrorKind, Read, Result, Seek, Write};
+
+// Variables and functions are referenced through chains threaded through the bytecode, the operand
+// following each referencing instruction holds the offset to the next occurrence in its lower 27 bits
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reference {
+    pub address: u64, // Absolute offset of the referencing instruction
+    pub code_id: usize, // Code entry the instruction belongs to
+    pub offset: u32, // Offset of the instruction from the start of that code entry
+}
+
+// Where an instruction sits in the bytecode, which unlike its address holds up when the file is laid out again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CodeLocation {
+    pub code_id: usize, // Code entry owning the bytecode, the parent for child functions
+    pub offset: u32, // Offset of the instruction from the start of that bytecode
+}
+
+impl CodeLocation {
+    pub fn address(&self, code: &ChunkCODE) -> Option<u64> {
+        code.entries.values.get(self.code_id).map(|entry| entry.bytecode_address + self.offset as u64)
+    }
+}
+
+// Reads the address of the first occurrence of a chain in VARI or FUNC, which needs CODE to be loaded already
+pub fn read_first_occurrence<R>(reader: &mut Reader<R>, occurrences: u32) -> Result<Option<CodeLocation>>
+    where R: Read + Seek,
+{
+    let address = reader.read_i32()? as u32 as u64;
+    if occurrences == 0 {
+        return Ok(None);
+    }
+    // From GMS 2.3>= the address points to the operand instead of the instruction
+    let address = match reader.version_info.is_version_at_least(2, 3, 0, 0) {
+        true => address.checked_sub(4).ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Reference to operand at {:#x} has no instruction", address)))?,
+        false => address,
+    };
+    let code = reader.code().ok_or_else(|| Error::new(ErrorKind::InvalidData, "References are read before the CODE chunk"))?;
+    location(code, address)
+        .map(Some)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Reference at {:#x} is outside of any code entry", address)))
+}
+
+// Written as a pointer into the bytecode, so it follows CODE wherever it ends up
+pub fn write_first_occurrence<W>(writer: &mut Writer<W>, first: Option<CodeLocation>) -> Result<()>
+    where W: Write + WriteBytesExt + Seek,
+{
+    match first {
+        Some(first) if writer.version_info.is_version_at_least(2, 3, 0, 0) => writer.write_bytecode_pointer(first.code_id, first.offset + 4),
+        Some(first) => writer.write_bytecode_pointer(first.code_id, first.offset),
+        None => writer.write_i32(-1),
+    }
+}
+
+// Finds the code entry containing the instruction at `address`, preferring child functions over their parent
+pub fn locate(code: &ChunkCODE, address: u64) -> Option<Reference> {
+    let (root_id, root) = code.entries.values.iter().enumerate().find(|(_, entry)| {
+        entry.parent.is_none() && address >= entry.bytecode_address && address < entry.bytecode_address + entry.bytecode.len() as u64
+    })?;
+    let relative = (address - root.bytecode_address) as u32;
+    let (code_id, entry) = code.children(root_id)
+        .filter(|(_, child)| child.offset <= relative)
+        .max_by_key(|(_, child)| child.offset)
+        .unwrap_or((root_id, root));
+
+    Some(Reference {
+        address,
+        code_id,
+        offset: relative - entry.offset,
+    })
+}
+
+// Finds where the instruction at `address` sits in the bytecode of its root entry
+pub fn location(code: &ChunkCODE, address: u64) -> Option<CodeLocation> {
+    let reference = locate(code, address)?;
+    let code_id = code.entries[reference.code_id].parent.unwrap_or(reference.code_id);
+    Some(CodeLocation {
+        code_id,
+        offset: (address - code.entries[code_id].bytecode_address) as u32,
+    })
+}
+
+pub fn resolve_chain(code: &ChunkCODE, first: Option<CodeLocation>, occurrences: u32) -> Result<Vec<Reference>> {
+    let mut references = Vec::with_capacity(occurrences as usize);
+    let Some(first) = first else {
+        return Ok(references);
+    };
+    let mut address = first.address(code).ok_or_else(|| {
+        Error::new(ErrorKind::InvalidData, format!("Reference into code entry {} which does not exist", first.code_id))
+    })?;
+    for occurrence in 0..occurrences {
+        let reference = locate(code, address).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, format!("Reference at {:#x} is outside of any code entry", address))
+        })?;
+        references.push(reference);
+        if occurrence + 1 == occurrences {
+            break;
+        }
+        let root = code.root(reference.code_id);
+        let operand = (address - root.bytecode_address + 4) as usize;
+        let operand = root.bytecode.get(operand..operand + 4).ok_or_else(|| {
+            Error::new(ErrorKind::UnexpectedEof, format!("Reference at {:#x} has no operand", address))
+        })?;
+        let next = u32::from_le_bytes([operand[0], operand[1], operand[2], operand[3]]) & 0x07ffffff;
+        if next == 0 {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Reference chain ends early at {:#x}", address)));
+        }
+        address += next as u64;
+    }
+
+    Ok(references)
+}
+
+// Resolves every chain, given as (first occurrence, occurrences) pairs
+pub fn resolve_chains<I>(code: &ChunkCODE, chains: I) -> Result<Vec<Vec<Reference>>>
+    where I: IntoIterator<Item = (Option<CodeLocation>, u32)>,
+{
+    chains.into_iter()
+        .map(|(first, occurrences)| resolve_chain(code, first, occurrences))
+        .collect()
+}
+
+// Maps the address of every referencing instruction back to the index of the chain it belongs to
+pub fn reference_map(references: &[Vec<Reference>]) -> IntMap<u64, usize> {
+    let mut map = IntMap::default();
+    for (index, chain) in references.iter().enumerate() {
+        for reference in chain.iter() {
+            map.insert(reference.address, index);
+        }
+    }
+    map
+}
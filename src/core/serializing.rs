@@ -1,6 +1,6 @@
 use super::{writer::Writer, reader::Reader};
 use byteorder::WriteBytesExt;
-use std::{fmt::Write, io::{Read, Result, Seek}};
+use std::io::{Read, Result, Seek, Write};
 
 pub trait Serialize {
     fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
@@ -8,7 +8,13 @@ pub trait Serialize {
 
     fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
         where W: Write + WriteBytesExt + Seek;
-    
+
+    // Identity of the object for pointers pointing at it, handles to shared objects override this
+    fn address(chunk: &Self) -> usize
+        where Self: Sized,
+    {
+        chunk as *const Self as usize
+    }
 }
 
 pub trait FormatCheck {
@@ -0,0 +1,66 @@
+use crate::core::{reader::Reader, serializing::Serialize, writer::Writer};
+use byteorder::WriteBytesExt;
+use std::{cell::{Ref, RefCell, RefMut}, io::{Read, Result, Seek, Write}, rc::Rc};
+
+// Handle to a structure several owners point at. Reading the same offset twice hands out the same
+// handle, so edits through one owner are seen by all of them and the writer emits it only once.
+pub struct Shared<T>(Rc<RefCell<T>>);
+
+impl<T> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Self(Rc::new(RefCell::new(value)))
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.0.borrow()
+    }
+
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.0.borrow_mut()
+    }
+
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    // Number of owners holding this handle
+    pub fn owners(&self) -> usize {
+        Rc::strong_count(&self.0)
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Default for Shared<T>
+    where T: Default,
+{
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> Serialize for Shared<T>
+    where T: Serialize + 'static,
+{
+    fn deserialize<R>(reader: &mut Reader<R>) -> Result<Self>
+        where R: Read + Seek,
+    {
+        let offset = reader.stream_position()?;
+        reader.read_shared_at::<T>(offset)
+    }
+
+    fn serialize<W>(chunk: &Self, writer: &mut Writer<W>) -> Result<()>
+        where W: Write + WriteBytesExt + Seek,
+    {
+        T::serialize(&chunk.borrow(), writer)
+    }
+
+    // Every handle to the same structure is the same object for the writer
+    fn address(chunk: &Self) -> usize {
+        Rc::as_ptr(&chunk.0) as *const () as usize
+    }
+}
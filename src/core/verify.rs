@@ -0,0 +1,1433 @@
+use crate::core::{reader::Reader, writer::{Writer, WriteTrace}, error::{PathSegment, short_type_name}};
+use bstr::BString;
+use std::{fmt, fs, io::{Cursor, Error, ErrorKind, Result}, panic::Location, path::{Path, PathBuf}};
+
+// First byte where a data file and the file written back from it disagree
+#[derive(Debug, Clone)]
+pub struct Difference {
+    pub chunk: BString, // Chunk containing the difference, or "FORM" for the file layout itself
+    pub original_offset: u64, // Absolute offset in the original file
+    pub written_offset: u64, // Absolute offset in the written file
+    pub original: Option<u8>, // None when the original chunk ends there
+    pub written: Option<u8>, // None when the written chunk ends there
+    pub path: Vec<PathSegment>, // List elements holding the written byte, outermost first, such as Sprite[3].Keyframe[0]
+    pub element_offset: Option<u64>, // Where the innermost of them starts in the written file
+    pub source: Option<&'static Location<'static>>, // Line of the model's serialize that wrote the field holding the byte
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let byte = |value: Option<u8>| value.map(|value| format!("{:#04x}", value)).unwrap_or_else(|| "end of chunk".into());
+        write!(f, "{}: original {:#x} ({}) differs from written {:#x} ({})", self.chunk, self.original_offset, byte(self.original), self.written_offset, byte(self.written))?;
+        if let Some(offset) = self.element_offset {
+            let path = self.path.iter().map(|segment| segment.to_string()).collect::<Vec<_>>();
+            write!(f, " in {} at {:#x} (+{:#x})", path.join("."), offset, self.written_offset - offset)?;
+        }
+        if let Some(source) = self.source {
+            write!(f, ", written by {}", source)?;
+        }
+        Ok(())
+    }
+}
+
+struct ChunkSpan {
+    name: BString,
+    start: u64, // Right after the chunk header
+    end: u64,
+}
+
+fn chunk_spans(data: &[u8]) -> Result<Vec<ChunkSpan>> {
+    let read_u32 = |offset: usize| -> Result<u32> {
+        data.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, format!("Truncated chunk header at {:#x}", offset)))
+    };
+    if data.get(0..4) != Some(b"FORM".as_slice()) {
+        return Err(Error::new(ErrorKind::InvalidData, "Missing FORM header"));
+    }
+    let size = read_u32(4)? as usize + 8;
+    let mut spans = Vec::new();
+    let mut offset = 8;
+    while offset < size {
+        let name = data.get(offset..offset + 4)
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, format!("Truncated chunk header at {:#x}", offset)))?;
+        let length = read_u32(offset + 4)? as u64;
+        let start = offset as u64 + 8;
+        if start + length > data.len() as u64 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, format!("Chunk {} at {:#x} runs past the end of the file", BString::from(name), offset)));
+        }
+        spans.push(ChunkSpan {
+            name: BString::from(name),
+            start,
+            end: start + length,
+        });
+        offset = (start + length) as usize;
+    }
+
+    Ok(spans)
+}
+
+// Loads `data`, writes it back out and compares both, returning the first difference if any
+pub fn verify(data: &[u8], path: Option<PathBuf>) -> Result<Option<Difference>> {
+    let mut reader = Reader::new(Cursor::new(data), path.clone());
+    reader.deserialize_chunks()?;
+    reader.deserialize()?;
+
+    // No path, so the audio groups next to the data file are left alone
+    let mut writer = Writer::from_reader(Cursor::new(Vec::new()), &reader, None);
+    writer.trace = Some(WriteTrace::default());
+    writer.serialize()?;
+    let trace = writer.trace.take().unwrap_or_default();
+    let written = writer.container.into_inner();
+
+    let original_spans = chunk_spans(data)?;
+    let written_spans = chunk_spans(&written)?;
+    for index in 0..original_spans.len().max(written_spans.len()) {
+        let (original, written_span) = match (original_spans.get(index), written_spans.get(index)) {
+            (Some(original), Some(written_span)) if original.name == written_span.name => (original, written_span),
+            (original, written_span) => {
+                // Chunks are missing or out of order
+                return Ok(Some(Difference {
+                    chunk: BString::from("FORM"),
+                    original_offset: original.map(|span| span.start - 8).unwrap_or(data.len() as _),
+                    written_offset: written_span.map(|span| span.start - 8).unwrap_or(written.len() as _),
+                    original: original.map(|span| data[span.start as usize - 8]),
+                    written: written_span.map(|span| written[span.start as usize - 8]),
+                    path: Vec::new(),
+                    element_offset: None,
+                    source: None,
+                }));
+            }
+        };
+        let original_bytes = &data[original.start as usize..original.end as usize];
+        let written_bytes = &written[written_span.start as usize..written_span.end as usize];
+        let Some(position) = original_bytes.iter().zip(written_bytes.iter()).position(|(a, b)| a != b)
+            .or_else(|| (original_bytes.len() != written_bytes.len()).then(|| original_bytes.len().min(written_bytes.len()))) else {
+            continue;
+        };
+        let written_offset = written_span.start + position as u64;
+        let mut elements = trace.elements.iter()
+            .filter(|element| element.start <= written_offset && written_offset < element.end)
+            .collect::<Vec<_>>();
+        elements.sort_by_key(|element| (element.start, u64::MAX - element.end));
+        let source = trace.writes.range(written_span.start..=written_offset)
+            .next_back()
+            .map(|(_, location)| *location);
+        return Ok(Some(Difference {
+            chunk: original.name.clone(),
+            original_offset: original.start + position as u64,
+            written_offset,
+            original: original_bytes.get(position).copied(),
+            written: written_bytes.get(position).copied(),
+            path: elements.iter()
+                .map(|element| PathSegment {
+                    name: short_type_name(element.type_name),
+                    index: Some(element.index),
+                    field: false,
+                })
+                .collect(),
+            element_offset: elements.last().map(|element| element.start),
+            source,
+        }));
+    }
+
+    Ok(None)
+}
+
+pub fn verify_file(path: &Path) -> Result<Option<Difference>> {
+    let data = fs::read(path)?;
+    verify(&data, Some(path.to_path_buf()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::core::{
+        chunks::{
+            ChunkOutput, gen8::{ChunkGEN8, InfoFlags}, optn::{ChunkOPTN, OptionsFlags}, strg::ChunkSTRG, sond::ChunkSOND, agrp::ChunkAGRP, sprt::ChunkSPRT, bgnd::ChunkBGND,
+            path::ChunkPATH, scpt::ChunkSCPT, glob::ChunkGLOB, shdr::ChunkSHDR, font::ChunkFONT, tmln::ChunkTMLN, objt::ChunkOBJT, acrv::ChunkACRV, tpag::ChunkTPAG,
+            txtr::ChunkTXTR, audo::ChunkAUDO, seqn::ChunkSEQN, room::ChunkROOM, code::ChunkCODE, vari::ChunkVARI, func::ChunkFUNC, raw::RawChunk,
+            lang::ChunkLANG, extn::ChunkEXTN, feds::ChunkFEDS, tgin::ChunkTGIN, embi::ChunkEMBI, dafl::ChunkDAFL, feat::ChunkFEAT, tags::ChunkTAGS,
+            psem::ChunkPSEM, psys::ChunkPSYS,
+        },
+        models::{
+            option::Constant, sound::{Sound, AudioEntryFlags}, audio_group::AudioGroup, sprite::{Sprite, SequenceReference, NineSlice, SepMaskType}, background::Background,
+            path::{Path as GMPath, Point}, script::Script, shader::{Shader, ShaderType}, font::{Font, Glyph, Kerning}, timeline::{Timeline, TimelineMoment},
+            object::{Object, Event, Action}, animation_curve::{AnimationCurve, Channel, Point as CurvePoint}, texture_page_item::TexturePageItem,
+            embedded_texture::EmbeddedTexture, embedded_audio::EmbeddedAudio, code_entry::CodeEntry, variable::{Variable, InstanceType},
+            function::{Function, CodeLocals, LocalVariable},
+            sequence::{Sequence, Track, TrackKeyframes, KeyframeList, Keyframe, ResourceData, RealKeyframes, RealData, CurveData, OwnedResources},
+            room::{Room, RoomEntryFlags, View, GameObject, Layer, LayerType, LayerData, LayerInstances, LayerTiles},
+            language::Language, extension::{Extension, ExtensionFile, ExtensionFunction, ExtensionOption, ExtensionKind, ExtensionValueType, OptionKind},
+            filter_effect::FilterEffect, texture_group_info::{TextureGroupInfo, TextureGroupLoadType}, embedded_image::EmbeddedImage,
+            asset_tags::AssetTags, particle_system::{ParticleEmitter, ParticleSystem, EmitMode, ParticleTexture},
+        },
+        lists::GMPointerList, shared::Shared, asm, disasm::Disassembler, references, GMVersionInfo,
+    };
+    use std::collections::HashMap;
+
+    // Writes a data file out of models built in code
+    pub(crate) fn fixture(version_info: GMVersionInfo, chunks: Vec<(&str, ChunkOutput)>) -> Vec<u8> {
+        let mut writer = Writer::new(Cursor::new(Vec::new()), None);
+        writer.version_info = version_info;
+        for (name, chunk) in chunks {
+            writer.chunk_order.push(name.into());
+            writer.chunks.insert(name.into(), chunk);
+        }
+        writer.serialize().expect("Unable to write the fixture");
+        writer.container.into_inner()
+    }
+
+    pub(crate) fn version(major: i32, minor: i32, release: i32, build: i32, format_id: i8) -> GMVersionInfo {
+        let mut version_info = GMVersionInfo {
+            format_id,
+            room_object_pre_create: format_id >= 16,
+            ..Default::default()
+        };
+        version_info.set_version(major, minor, release, build);
+        version_info.align_chunks_to_16 = version_info.is_version_at_least(2, 2, 2, 0);
+        version_info
+    }
+
+    pub(crate) fn gen8(version_info: &GMVersionInfo) -> ChunkGEN8 {
+        ChunkGEN8 {
+            format_id: version_info.format_id,
+            filename: "fixture".into(),
+            config: "Default".into(),
+            last_object_id: 100000,
+            last_tile_id: 10000000,
+            game_id: 1337,
+            game_name: "Fixture".into(),
+            major: version_info.major,
+            minor: version_info.minor,
+            release: version_info.release,
+            build: version_info.build,
+            default_window_width: 640,
+            default_window_height: 480,
+            info: InfoFlags::Interpolate | InfoFlags::ShowCursor,
+            timestamp: 1700000000,
+            display_name: "Fixture Game".into(),
+            room_order: vec![0],
+            gms2_random_uid: if version_info.major >= 2 { vec![1, 2, 3, 4, 5] } else { Vec::new() },
+            gms2_fps: 60.0,
+            gms2_game_guid: if version_info.major >= 2 { (0..16).collect() } else { Vec::new() },
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn optn() -> ChunkOPTN {
+        let mut chunk = ChunkOPTN {
+            unknown: 0x0000_0002_8000_0000, // The low half flags the bit-flag layout
+            options: OptionsFlags::ShowCursor | OptionsFlags::UseFastCollision,
+            scale: -1,
+            window_color: 0xff000000,
+            color_depth: 32,
+            resolution: 0,
+            frequency: 60,
+            ..Default::default()
+        };
+        chunk.constants.push(Constant {
+            name: "@@SleepMargin".into(),
+            value: "10".into(),
+        });
+        chunk
+    }
+
+    fn assert_round_trip(data: &[u8]) {
+        if let Some(difference) = verify(data, None).expect("Unable to verify the fixture") {
+            panic!("{}", difference);
+        }
+    }
+
+    #[test]
+    fn round_trip_minimal() {
+        let version_info = version(2, 0, 0, 0, 17);
+        let data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("STRG", ChunkSTRG::default().into()),
+        ]);
+        assert_round_trip(&data);
+    }
+
+    fn action(code_id: i32) -> Action {
+        Action {
+            lib_id: 1,
+            id: 603,
+            kind: 7,
+            exe_type: 2,
+            code_id,
+            argument_count: 1,
+            who: -1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn round_trip_gms2() {
+        let version_info = version(2, 3, 2, 0, 17);
+        let items = (0..3)
+            .map(|index| Shared::new(TexturePageItem {
+                source_x: index * 16,
+                source_width: 16,
+                source_height: 16,
+                target_width: 16,
+                target_height: 16,
+                bounding_width: 16,
+                bounding_height: 16,
+                ..Default::default()
+            }))
+            .collect::<Vec<_>>();
+
+        let mut sond = ChunkSOND::default();
+        sond.sounds.push(Sound {
+            name: "snd_jump".into(),
+            flags: AudioEntryFlags::IsEmbedded | AudioEntryFlags::Regular,
+            kind: ".wav".into(),
+            file: "snd_jump.wav".into(),
+            volume: 1.0,
+            pitch: 1.0,
+            audio_id: 0,
+            ..Default::default()
+        });
+        let mut agrp = ChunkAGRP::default();
+        agrp.audio_groups.push(AudioGroup {
+            name: "audiogroup_default".into(),
+        });
+
+        let mut sprt = ChunkSPRT::default();
+        sprt.sprites.push(Sprite {
+            name: "spr_player".into(),
+            width: 16,
+            height: 16,
+            margin_right: 15,
+            margin_bottom: 15,
+            special_or_gms2: true,
+            special_version: 3,
+            gms2_playback_speed: 15.0,
+            gms2_3_sequence: Some(SequenceReference {
+                sequence: Sequence {
+                    name: "spr_player".into(),
+                    playback_speed: 15.0,
+                    length: 2.0,
+                    volume: 1.0,
+                    ..Default::default()
+                },
+            }),
+            gms2_3_2_nine_slice: Some(NineSlice {
+                right: 4,
+                bottom: 4,
+                tile_modes: Default::default(),
+                ..Default::default()
+            }),
+            texture_items: vec![Some(items[0].clone()), Some(items[1].clone())],
+            collision_masks: vec![vec![0xff; 32]],
+            ..Default::default()
+        });
+
+        let mut bgnd = ChunkBGND::default();
+        bgnd.backgrounds.push(Background {
+            name: "bg_tiles".into(),
+            texture_item: Some(items[2].clone()),
+            tile_unknown1: 2,
+            tile_width: 8,
+            tile_height: 8,
+            tile_columns: 2,
+            tile_frame_length: 66666,
+            tiles: vec![vec![0], vec![1]],
+            ..Default::default()
+        });
+
+        let mut path = ChunkPATH::default();
+        let mut points = GMPath {
+            name: "pth_patrol".into(),
+            closed: true,
+            precision: 4,
+            ..Default::default()
+        };
+        points.points.push(Point { x: 0.0, y: 0.0, speed: 100.0 });
+        points.points.push(Point { x: 64.0, y: 32.0, speed: 50.0 });
+        path.paths.push(points);
+
+        let mut scpt = ChunkSCPT::default();
+        scpt.scripts.push(Script {
+            name: "scr_move".into(),
+            code_id: 0,
+            ..Default::default()
+        });
+        let glob = ChunkGLOB {
+            global_init_entries: vec![1],
+        };
+
+        let mut shdr = ChunkSHDR::default();
+        shdr.shaders.push(Shader {
+            name: "shd_flash".into(),
+            kind: ShaderType::GlslEs,
+            glsl_es_vertex: "void main() {}".into(),
+            glsl_es_fragment: "void main() {}".into(),
+            hlsl11_vertex_buffer: vec![1, 2, 3, 4, 5],
+            vertex_attributes: vec!["in_Position".into(), "in_Colour".into()],
+            version: 2,
+            ..Default::default()
+        });
+
+        let mut font = ChunkFONT::default();
+        let mut glyphs = GMPointerList::default();
+        glyphs.push(Glyph {
+            character: 'A' as u16,
+            width: 8,
+            height: 12,
+            shift: 9,
+            kerning: vec![Kerning { other: 'V' as i16, amount: -1 }],
+            ..Default::default()
+        });
+        font.fonts.push(Font {
+            name: "fnt_main".into(),
+            display_name: "Arial".into(),
+            size: 12,
+            size_float: 12.0,
+            range_start: 32,
+            range_end: 127,
+            texture_item: items[2].clone(),
+            scale_x: 1.0,
+            scale_y: 1.0,
+            ascender_offset: 2,
+            ascender: 11,
+            glyphs,
+            ..Default::default()
+        });
+
+        let mut tmln = ChunkTMLN::default();
+        let mut actions = GMPointerList::default();
+        actions.push(action(1));
+        tmln.timelines.push(Timeline {
+            name: "tl_intro".into(),
+            moments: vec![TimelineMoment { time: 30, actions }],
+        });
+
+        let mut objt = ChunkOBJT::default();
+        let mut events = GMPointerList::<GMPointerList<Event>>::default();
+        for event_type in 0..15 {
+            let mut subtypes = GMPointerList::default();
+            if event_type == 0 {
+                let mut actions = GMPointerList::default();
+                actions.push(action(0));
+                subtypes.push(Event { subtype: 0, actions });
+            }
+            events.push(subtypes);
+        }
+        objt.objects.push(Object {
+            name: "obj_player".into(),
+            sprite_id: 0,
+            visible: true,
+            parent_object_id: -100,
+            mask_sprite_id: -1,
+            events,
+            ..Default::default()
+        });
+
+        let mut acrv = ChunkACRV {
+            version: 1,
+            ..Default::default()
+        };
+        let mut channel = Channel {
+            name: "curve1".into(),
+            iterations: 16,
+            ..Default::default()
+        };
+        channel.points.push(CurvePoint { x: 0.0, value: 0.0, bezier_points: [0.0; 4] });
+        channel.points.push(CurvePoint { x: 1.0, value: 1.0, bezier_points: [0.0; 4] });
+        let mut curve = AnimationCurve {
+            name: "ac_ease".into(),
+            ..Default::default()
+        };
+        curve.channels.push(channel);
+        acrv.animation_curves.push(curve);
+
+        let mut tpag = ChunkTPAG::default();
+        for item in items.iter() {
+            tpag.texture_page_items.push(item.clone());
+        }
+        let mut txtr = ChunkTXTR::default();
+        txtr.textures.push(EmbeddedTexture {
+            data: b"\x89PNG\r\n\x1a\n\0\0\0\0IEND\xaeB`\x82".to_vec(),
+            ..Default::default()
+        });
+        let mut audo = ChunkAUDO::default();
+        audo.audio.push(EmbeddedAudio {
+            data: b"RIFF\x04\0\0\0WAVE".to_vec(),
+        });
+
+        let data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("SOND", sond.into()),
+            ("AGRP", agrp.into()),
+            ("SPRT", sprt.into()),
+            ("BGND", bgnd.into()),
+            ("PATH", path.into()),
+            ("SCPT", scpt.into()),
+            ("GLOB", glob.into()),
+            ("SHDR", shdr.into()),
+            ("FONT", font.into()),
+            ("TMLN", tmln.into()),
+            ("OBJT", objt.into()),
+            ("ACRV", acrv.into()),
+            ("TPAG", tpag.into()),
+            ("STRG", ChunkSTRG::default().into()),
+            ("TXTR", txtr.into()),
+            ("AUDO", audo.into()),
+        ]);
+        assert_round_trip(&data);
+    }
+
+    #[test]
+    fn round_trip_gms2_3() {
+        let version_info = version(2, 3, 2, 0, 17);
+
+        let mut curve = AnimationCurve {
+            name: "".into(),
+            ..Default::default()
+        };
+        let mut channel = Channel {
+            name: "x".into(),
+            iterations: 16,
+            ..Default::default()
+        };
+        channel.points.push(CurvePoint { x: 0.0, value: 0.0, bezier_points: [0.0, 0.0, 0.25, 0.5] });
+        curve.channels.push(channel);
+        let mut real = RealKeyframes::default();
+        real.list.push(Keyframe {
+            key: 0.0,
+            length: 1.0,
+            channels: vec![(0, RealData { value: 32.0, curve: CurveData { animation_curve_id: Some(0), ..Default::default() } })],
+            ..Default::default()
+        });
+        let mut position = Track {
+            model_name: "GMRealTrack".into(),
+            name: "position".into(),
+            built_in_name: 14,
+            keyframes: TrackKeyframes::Real(real),
+            owned_resources: vec![OwnedResources::AnimCurve(curve)],
+            ..Default::default()
+        };
+        position.tags.push(3);
+        let mut instances = KeyframeList::default();
+        instances.keyframes.push(Keyframe {
+            key: 0.0,
+            length: 60.0,
+            stretch: true,
+            channels: vec![(0, ResourceData { resource_id: 0 })],
+            ..Default::default()
+        });
+        let mut sequence = Sequence {
+            name: "seq_intro".into(),
+            playback_speed: 60.0,
+            length: 60.0,
+            volume: 1.0,
+            ..Default::default()
+        };
+        sequence.tracks.push(Track {
+            model_name: "GMInstanceTrack".into(),
+            name: "obj_player".into(),
+            tracks: vec![position],
+            keyframes: TrackKeyframes::Instance(instances),
+            ..Default::default()
+        });
+        sequence.function_ids.insert(0, "seq_intro_moment".into());
+        let mut seqn = ChunkSEQN {
+            version: 1,
+            ..Default::default()
+        };
+        seqn.sequences.push(sequence);
+
+        let mut room = Room {
+            name: "rm_start".into(),
+            width: 320,
+            height: 240,
+            speed: 60,
+            creation_code_id: -1,
+            flags: RoomEntryFlags::IsGMS2 | RoomEntryFlags::IsGMS2_3,
+            meters_per_pixel: 0.1,
+            sequences: vec![0],
+            ..Default::default()
+        };
+        for index in 0..8 {
+            room.views.push(View {
+                enabled: index == 0,
+                view_width: 320,
+                view_height: 240,
+                port_width: 320,
+                port_height: 240,
+                border_x: 32,
+                border_y: 32,
+                object_id: -1,
+                ..Default::default()
+            });
+        }
+        for (x, instance_id) in [(16, 100001), (48, 100002)] {
+            room.game_objects.push(GameObject {
+                x,
+                y: 16,
+                object_id: 0,
+                instance_id,
+                creation_code_id: -1,
+                scale_x: 1.0,
+                scale_y: 1.0,
+                image_speed: 1.0,
+                color: 0xffffffff,
+                pre_create_code_id: -1,
+                ..Default::default()
+            });
+        }
+        room.layers.push(Layer {
+            name: "Instances".into(),
+            id: 1,
+            kind: LayerType::Instances,
+            visible: true,
+            data: LayerData::Instances(LayerInstances {
+                instance_ids: vec![100001, 100002],
+            }),
+            ..Default::default()
+        });
+        room.layers.push(Layer {
+            name: "Tiles".into(),
+            id: 2,
+            kind: LayerType::Tiles,
+            depth: 100,
+            visible: true,
+            data: LayerData::Tiles(LayerTiles {
+                background_id: 0,
+                tiles_x: 2,
+                tiles_y: 2,
+                tiles: vec![vec![1, 0], vec![0x8000_0000, 2]],
+            }),
+            ..Default::default()
+        });
+        let mut rooms = ChunkROOM::default();
+        rooms.rooms.push(room);
+
+        // pushi.e 1, popz.e, exit.i, shared by the script and its child function
+        let bytecode = vec![0x01, 0x00, 0x0f, 0x84, 0x00, 0x00, 0x0f, 0x9e, 0x00, 0x00, 0x02, 0x9d];
+        let mut code = ChunkCODE::default();
+        code.entries.push(CodeEntry {
+            name: "gml_GlobalScript_scr_move".into(),
+            length: bytecode.len() as _,
+            bytecode: bytecode.clone(),
+            ..Default::default()
+        });
+        code.entries.push(CodeEntry {
+            name: "gml_Script_scr_move".into(),
+            length: bytecode.len() as _,
+            arguments_count: 1,
+            locals_count: 1,
+            offset: 4,
+            parent: Some(0),
+            ..Default::default()
+        });
+        let vari = ChunkVARI {
+            variables: vec![
+                Variable {
+                    name: "x".into(),
+                    instance_type: InstanceType::Self_,
+                    var_id: 0,
+                    ..Default::default()
+                },
+                Variable {
+                    name: "arguments".into(),
+                    instance_type: InstanceType::Local,
+                    ..Default::default()
+                },
+            ],
+            instance_var_count: 1,
+            instance_var_count_again: 1,
+            max_local_var_count: 1,
+        };
+        let mut func = ChunkFUNC::default();
+        func.functions.push(Function {
+            name: "show_debug_message".into(),
+            ..Default::default()
+        });
+        func.code_locals.push(CodeLocals {
+            name: "gml_GlobalScript_scr_move".into(),
+            locals: vec![LocalVariable { index: 0, name: "arguments".into() }],
+        });
+
+        let data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("SEQN", seqn.into()),
+            ("ROOM", rooms.into()),
+            ("CODE", code.into()),
+            ("VARI", vari.into()),
+            ("FUNC", func.into()),
+            ("STRG", ChunkSTRG::default().into()),
+        ]);
+        assert_round_trip(&data);
+    }
+
+    #[test]
+    fn round_trip_gms2023() {
+        let version_info = version(2023, 8, 0, 0, 17);
+        let mut lang = ChunkLANG {
+            unknown1: 1,
+            language_count: 2,
+            entry_count: 2,
+            ..Default::default()
+        };
+        lang.entry_ids.push("greeting".into());
+        lang.entry_ids.push("farewell".into());
+        for (name, region, entries) in [("English", "en", ["Hello", "Goodbye"]), ("French", "fr", ["Bonjour", "Au revoir"])] {
+            let mut language = Language {
+                name: name.into(),
+                region: region.into(),
+                ..Default::default()
+            };
+            for entry in entries {
+                language.entries.push(entry.into());
+            }
+            lang.languages.push(language);
+        }
+
+        let mut file = ExtensionFile {
+            filename: "steam.dll".into(),
+            final_function: "steam_final".into(),
+            initial_function: "steam_init".into(),
+            kind: ExtensionKind::Dll,
+            ..Default::default()
+        };
+        file.functions.push(ExtensionFunction {
+            name: "steam_get_user".into(),
+            id: 1,
+            kind: 11,
+            return_type: ExtensionValueType::String,
+            external_name: "SteamGetUser".into(),
+            argument_types: vec![ExtensionValueType::Double, ExtensionValueType::String],
+        });
+        let mut extension = Extension {
+            folder_name: "".into(),
+            name: "Steamworks".into(),
+            version: "1.0.0".into(),
+            class_name: "YYSteamworks".into(),
+            guid: Some([7; 16]),
+            ..Default::default()
+        };
+        extension.files.push(file);
+        extension.options.push(ExtensionOption {
+            name: "debug".into(),
+            value: "False".into(),
+            kind: OptionKind::Boolean,
+        });
+        let mut extn = ChunkEXTN::default();
+        extn.extensions.push(extension);
+
+        let mut feds = ChunkFEDS {
+            version: 1,
+            ..Default::default()
+        };
+        feds.filter_effect.push(FilterEffect {
+            name: "_filter_vignette".into(),
+            value: "{}".into(),
+        });
+
+        let mut tgin = ChunkTGIN {
+            version: 1,
+            ..Default::default()
+        };
+        tgin.groups.push(TextureGroupInfo {
+            name: "Default".into(),
+            directory: "".into(),
+            extension: ".yytex".into(),
+            load_type: TextureGroupLoadType::InFile,
+            texture_pages: vec![0],
+            sprites: vec![0, 1],
+            fonts: vec![0],
+            ..Default::default()
+        });
+
+        let item = Shared::new(TexturePageItem {
+            source_width: 8,
+            source_height: 8,
+            target_width: 8,
+            target_height: 8,
+            bounding_width: 8,
+            bounding_height: 8,
+            ..Default::default()
+        });
+        let mut tpag = ChunkTPAG::default();
+        tpag.texture_page_items.push(item.clone());
+        let mut embi = ChunkEMBI::default();
+        embi.images.push(EmbeddedImage {
+            name: "icon".into(),
+            texture_item: Some(item),
+        });
+
+        let mut tags = ChunkTAGS {
+            tags: vec!["enemy".into(), "boss".into()],
+            ..Default::default()
+        };
+        tags.asset_tags.push(AssetTags {
+            asset_id: 3,
+            tags: vec!["enemy".into(), "boss".into()],
+        });
+
+        let mut psem = ChunkPSEM::default();
+        psem.emitters.push(ParticleEmitter {
+            name: "pe_sparks".into(),
+            enabled: true,
+            mode: EmitMode::Burst,
+            emit_count: 12.0,
+            delay_max: 0.5,
+            region_width: 32.0,
+            region_height: 16.0,
+            sprite_id: -1,
+            texture: ParticleTexture::Spark,
+            start_color: 0xffffffff,
+            mid_color: 0xff00ffff,
+            end_color: 0x0000ffff,
+            lifetime_min: 10.0,
+            lifetime_max: 20.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            size_min_x: 0.5,
+            size_max_x: 1.0,
+            size_min_y: 0.5,
+            size_max_y: 1.0,
+            speed_min: 2.0,
+            speed_max: 4.0,
+            direction_max: 360.0,
+            spawn_on_death_id: -1,
+            spawn_on_update_id: -1,
+            ..Default::default()
+        });
+        let mut psys = ChunkPSYS::default();
+        psys.systems.push(ParticleSystem {
+            name: "ps_sparks".into(),
+            origin_x: 16,
+            origin_y: 8,
+            global_space_particles: true,
+            emitters: vec![0],
+            ..Default::default()
+        });
+
+        let data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("LANG", lang.into()),
+            ("EXTN", extn.into()),
+            ("FEDS", feds.into()),
+            ("TPAG", tpag.into()),
+            ("TGIN", tgin.into()),
+            ("EMBI", embi.into()),
+            ("DAFL", ChunkDAFL::default().into()),
+            ("FEAT", ChunkFEAT { features: vec!["Spine".into(), "Physics".into()] }.into()),
+            ("TAGS", tags.into()),
+            ("PSEM", psem.into()),
+            ("PSYS", psys.into()),
+            ("STRG", ChunkSTRG::default().into()),
+        ]);
+        assert_round_trip(&data);
+    }
+
+    #[test]
+    fn round_trip_gms2024_6() {
+        let version_info = version(2024, 6, 0, 0, 17);
+        let mut sprite = Sprite {
+            name: "spr_coin".into(),
+            width: 16,
+            height: 16,
+            margin_left: 4,
+            margin_right: 11,
+            margin_bottom: 9,
+            margin_top: 2,
+            sep_masks: SepMaskType::Precise,
+            special_or_gms2: true,
+            special_version: 1,
+            collision_masks: vec![Vec::new()],
+            ..Default::default()
+        };
+        // The mask only covers the 8x8 bounding box, rather than the whole 16x16 sprite
+        assert_eq!(sprite.mask_size(&version_info), 8);
+        assert_eq!(sprite.mask_size(&version(2023, 8, 0, 0, 17)), 32);
+        sprite.set_mask_pixel(&version_info, 0, 4, 2, true);
+        sprite.set_mask_pixel(&version_info, 0, 11, 9, true);
+        sprite.set_mask_pixel(&version_info, 0, 12, 9, true);
+        assert_eq!(sprite.collision_masks[0], vec![0x80, 0, 0, 0, 0, 0, 0, 0x01]);
+
+        let mut sprt = ChunkSPRT::default();
+        sprt.sprites.push(sprite);
+        let data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("SPRT", sprt.into()),
+            ("STRG", ChunkSTRG::default().into()),
+        ]);
+        assert_round_trip(&data);
+
+        let mut reader = Reader::new(Cursor::new(data.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        reader.deserialize().unwrap();
+        let Some(ChunkOutput::ChunkSprt(sprt)) = reader.chunks.get(b"SPRT".as_slice()) else {
+            panic!("SPRT went missing");
+        };
+        let sprite = &sprt.sprites[0];
+        assert_eq!(sprite.collision_masks[0].len(), 8);
+        assert!(sprite.mask_pixel(&version_info, 0, 4, 2));
+        assert!(!sprite.mask_pixel(&version_info, 0, 3, 2));
+        assert!(sprite.mask_pixel(&version_info, 0, 11, 9));
+        assert!(!sprite.mask_pixel(&version_info, 0, 12, 9));
+    }
+
+    #[test]
+    fn round_trip_padded_strings() {
+        let version_info = version(2, 0, 0, 0, 17);
+        let mut strg = ChunkSTRG {
+            alignment: Some(128),
+            ..Default::default()
+        };
+        strg.push("pad".into());
+        assert_eq!(strg.offsets, vec![None]);
+        assert!(strg.index_by_offset.is_empty());
+        let data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("STRG", strg.into()),
+        ]);
+        assert_eq!(data.len() % 128, 0);
+        assert_round_trip(&data);
+
+        let mut reader = Reader::new(Cursor::new(data.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        reader.deserialize().unwrap();
+        let Some(ChunkOutput::ChunkStrg(strg)) = reader.chunks.get(b"STRG".as_slice()) else {
+            panic!("STRG went missing");
+        };
+        assert_eq!(strg.alignment, Some(128));
+        let offset = strg.offsets[0].expect("Strings that are read have an offset");
+        assert_eq!(strg.get_by_offset(offset).map(|string| string.as_slice()), Some(b"pad".as_slice()));
+    }
+
+    #[test]
+    fn round_trip_raw_chunks() {
+        let version_info = version(2022, 1, 0, 0, 17);
+        let data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("ZZZZ", RawChunk { data: b"From a newer GameMaker".to_vec() }.into()),
+            ("STRG", ChunkSTRG::default().into()),
+        ]);
+        assert_round_trip(&data);
+
+        // A script list pointing past the end of the file only goes through when kept raw
+        let mut scpt = ChunkSCPT::default();
+        scpt.scripts.push(Script {
+            name: "scr_broken".into(),
+            ..Default::default()
+        });
+        let mut data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("SCPT", scpt.into()),
+            ("STRG", ChunkSTRG::default().into()),
+        ]);
+        let span = chunk_spans(&data).unwrap().into_iter().find(|span| span.name == "SCPT").unwrap();
+        let pointer = span.start as usize + 4;
+        data[pointer..pointer + 4].copy_from_slice(&0x7fff_fff0u32.to_le_bytes());
+        let mut reader = Reader::new(Cursor::new(data.as_slice()), None);
+        reader.raw_fallback = true;
+        reader.deserialize_chunks().unwrap();
+        reader.deserialize().unwrap();
+        assert!(matches!(reader.chunks.get(b"SCPT".as_slice()), Some(ChunkOutput::RawChunk(_))));
+        let mut writer = Writer::from_reader(Cursor::new(Vec::new()), &reader, None);
+        writer.serialize().unwrap();
+        assert!(writer.container.into_inner() == data);
+    }
+
+    #[test]
+    fn reports_differences() {
+        let version_info = version(2, 0, 0, 0, 17);
+        let mut data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("STRG", ChunkSTRG::default().into()),
+        ]);
+        // String lengths are stored, so the reader skips the terminator and the writer puts it back
+        let terminator = data.windows(8).position(|window| window == b"Fixture\0").unwrap() + 7;
+        data[terminator] = b'!';
+        let difference = verify(&data, None).unwrap().expect("The difference went unnoticed");
+        assert_eq!(difference.chunk, "STRG");
+        assert_eq!(difference.original_offset, terminator as u64);
+        assert_eq!(difference.written, Some(0));
+    }
+
+    // A GameMaker 1.4 file with a single path, laid out by hand so it doesn't depend on the writer being right
+    fn hand_written() -> Vec<u8> {
+        let words = |values: &[u32]| values.iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<_>>();
+        let strg = 216; // Start of the STRG contents
+        let (filename, config, name, path_name) = (strg + 24, strg + 36, strg + 48, strg + 60);
+
+        let mut gen8 = vec![0, 14, 0, 0];
+        gen8.extend(words(&[filename, config, 100000, 10000000, 1337]));
+        gen8.extend([0; 16]); // Legacy GUID
+        gen8.extend(words(&[name, 1, 4, 9999, 0, 640, 480, 0, 0]));
+        gen8.extend([0; 16]); // License MD5
+        gen8.extend(words(&[1700000000, 0, name, 0, 0, 0, 0, 0, 0, 0]));
+
+        let mut path = words(&[1, 164, path_name, 1, 0, 4, 2]);
+        for value in [0.0f32, 0.0, 100.0, 32.0, 16.0, 100.0] {
+            path.extend(value.to_le_bytes());
+        }
+
+        let mut strings = words(&[4, strg + 20, strg + 32, strg + 44, strg + 56]);
+        for string in ["fixture", "Default", "Fixture", "pth_loop"] {
+            strings.extend(words(&[string.len() as u32]));
+            strings.extend(string.as_bytes());
+            strings.push(0);
+        }
+
+        let mut data = b"FORM".to_vec();
+        data.extend(words(&[0]));
+        for (name, contents) in [("GEN8", gen8), ("PATH", path), ("STRG", strings)] {
+            data.extend(name.as_bytes());
+            data.extend(words(&[contents.len() as u32]));
+            data.extend(contents);
+        }
+        let size = (data.len() - 8) as u32;
+        data[4..8].copy_from_slice(&size.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn round_trip_hand_written() {
+        let data = hand_written();
+        assert_eq!(chunk_spans(&data).unwrap().iter().map(|span| span.start).collect::<Vec<_>>(), vec![16, 156, 216]);
+        assert_round_trip(&data);
+    }
+
+    #[test]
+    fn attributes_differences_to_fields() {
+        // Any non-zero wide bool reads as true, which then gets written back as 1
+        let mut data = hand_written();
+        data[168] = 2;
+        let difference = verify(&data, None).unwrap().expect("The difference went unnoticed");
+        assert_eq!(difference.chunk, "PATH");
+        assert_eq!(difference.original_offset, 168);
+        assert_eq!((difference.original, difference.written), (Some(2), Some(1)));
+        assert_eq!(difference.path, vec![PathSegment {
+            name: "Path".into(),
+            index: Some(0),
+            field: false,
+        }]);
+        assert_eq!(difference.element_offset, Some(164));
+
+        let source = difference.source.expect("The field went unnoticed");
+        assert!(source.file().ends_with("path.rs"));
+        let line = include_str!("models/path.rs").lines().nth(source.line() as usize - 1).unwrap();
+        assert!(line.contains("chunk.smooth"), "Attributed to {}", line.trim());
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let data = hand_written();
+        for length in [4, 12, 100, data.len() - 1] {
+            assert!(chunk_spans(&data[..length]).is_err());
+            assert!(verify(&data[..length], None).is_err());
+        }
+    }
+
+    #[test]
+    fn locates_read_errors() {
+        let version_info = version(2, 0, 0, 0, 17);
+        let mut scpt = ChunkSCPT::default();
+        for name in ["scr_first", "scr_second"] {
+            scpt.scripts.push(Script {
+                name: name.into(),
+                ..Default::default()
+            });
+        }
+        let mut data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("SCPT", scpt.into()),
+            ("STRG", ChunkSTRG::default().into()),
+        ]);
+        // Point the second script past the end of the file
+        let span = chunk_spans(&data).unwrap().into_iter().find(|span| span.name == "SCPT").unwrap();
+        let pointer = span.start as usize + 8;
+        data[pointer..pointer + 4].copy_from_slice(&0x7fff_fff0u32.to_le_bytes());
+
+        let mut reader = Reader::new(Cursor::new(data.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        let Err(error) = reader.deserialize() else {
+            panic!("The bad pointer went unnoticed");
+        };
+        assert_eq!(error.chunk.as_ref().map(|chunk| chunk.as_slice()), Some(b"SCPT".as_slice()));
+        assert_eq!(error.index, Some(1));
+        assert_eq!(error.to_string(), format!("failed to fill whole buffer in SCPT at {:#x}, resource 1 (Script[1])", 0x7fff_fff0u32));
+
+        // Errors inside a field are located down to the element of the list it holds
+        let mut path = ChunkPATH::default();
+        let mut points = GMPath {
+            name: "pth_broken".into(),
+            ..Default::default()
+        };
+        points.points.push(Point { x: 0.0, y: 0.0, speed: 100.0 });
+        path.paths.push(points);
+        let mut data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("PATH", path.into()),
+            ("STRG", ChunkSTRG::default().into()),
+        ]);
+        let span = chunk_spans(&data).unwrap().into_iter().find(|span| span.name == "PATH").unwrap();
+        let path_ptr = u32::from_le_bytes(data[span.start as usize + 4..span.start as usize + 8].try_into().unwrap()) as usize;
+        // Claim far more points than there are bytes left
+        data[path_ptr + 16..path_ptr + 20].copy_from_slice(&0x0100_0000u32.to_le_bytes());
+        let point = (data.len() - (path_ptr + 20)) / 12;
+
+        let mut reader = Reader::new(Cursor::new(data.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        let Err(error) = reader.deserialize() else {
+            panic!("The bad point count went unnoticed");
+        };
+        assert_eq!(error.index, Some(0));
+        assert_eq!(error.offset, Some((path_ptr + 20 + point * 12) as u64));
+        assert!(error.to_string().ends_with(&format!("resource 0 (Path[0].points[{}])", point)), "{}", error);
+    }
+
+    // Assembles `text` into the only code entry of a new file, returning the file written afterwards
+    pub(crate) fn assembled(version_info: GMVersionInfo, text: &str) -> Vec<u8> {
+        let mut code = ChunkCODE::default();
+        code.entries.push(CodeEntry {
+            name: "gml_GlobalScript_scr_move".into(),
+            ..Default::default()
+        });
+        // Every code entry has its locals listed, even without any, or the file passes for 2024.8
+        let mut func = ChunkFUNC::default();
+        func.code_locals.push(CodeLocals {
+            name: "gml_GlobalScript_scr_move".into(),
+            locals: Vec::new(),
+        });
+        let data = fixture(version_info.clone(), vec![
+            ("GEN8", gen8(&version_info).into()),
+            ("OPTN", optn().into()),
+            ("CODE", code.into()),
+            ("VARI", ChunkVARI::default().into()),
+            ("FUNC", func.into()),
+            ("STRG", ChunkSTRG::default().into()),
+        ]);
+        let mut reader = Reader::new(Cursor::new(data.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        reader.deserialize().unwrap();
+        asm::assemble(&mut reader, 0, text).unwrap();
+        let mut writer = Writer::from_reader(Cursor::new(Vec::new()), &reader, None);
+        writer.serialize().unwrap();
+        writer.container.into_inner()
+    }
+
+    #[test]
+    fn round_trip_assembled_gms1() {
+        let text = "push.v self.x\npush.v global.score\ncall.i show_debug_message(argc=2)\npopz.v\npushi.e 1\npop.v.i self.x\nexit.i\n";
+        let written = assembled(version(1, 4, 9999, 0, 14), text);
+        assert_round_trip(&written);
+
+        let mut reader = Reader::new(Cursor::new(written.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        reader.deserialize().unwrap();
+        let code = reader.code().unwrap();
+        let variables = reader.variables().unwrap().references(code).unwrap();
+        let functions = reader.functions().unwrap().references(code).unwrap();
+        assert_eq!(variables.iter().map(|references| references.len()).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(functions.iter().map(|references| references.len()).collect::<Vec<_>>(), vec![1]);
+    }
+
+    // Disassembling every entry and assembling the text back has to give the exact same file
+    fn assert_disassembly_round_trip(version_info: GMVersionInfo, text: &str) {
+        let written = assembled(version_info, text);
+        let mut reader = Reader::new(Cursor::new(written.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        reader.deserialize().unwrap();
+        let roots = reader.code().unwrap().entries.values.iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.parent.is_none())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        for index in roots {
+            let disassembly = Disassembler::new(&reader).unwrap().disassemble(index).unwrap();
+            asm::assemble(&mut reader, index, &disassembly).unwrap();
+        }
+        let mut writer = Writer::from_reader(Cursor::new(Vec::new()), &reader, None);
+        writer.serialize().unwrap();
+        assert!(writer.container.into_inner() == written, "Reassembling changed the file");
+    }
+
+    const LOOP: &str = "push.s \"Counting\"\ncall.i show_debug_message(argc=1)\npopz.v\npushi.e 0\npop.v.i self.x\n\n:[1]\npush.v self.x\npushi.e 10\ncmp.i.v LT\nbf [2]\npush.v self.x\npushi.e 1\nadd.i.v\npop.v.v self.x\nb [1]\n\n:[2]\n";
+
+    #[test]
+    fn disassembly_round_trip_gms1() {
+        assert_disassembly_round_trip(version(1, 4, 9999, 0, 14), &format!("{}exit.i\n", LOOP));
+    }
+
+    #[test]
+    fn disassembly_round_trip_gms2_3() {
+        let child = "b [3]\n\n> gml_Script_scr_reset (locals=0, argc=0)\npushi.e 0\npop.v.i global.score\nexit.i\n\n:[3]\nexit.i\n";
+        assert_disassembly_round_trip(version(2, 3, 0, 0, 17), &format!("{}{}", LOOP, child));
+    }
+
+    #[test]
+    fn disassembly_round_trip_raw_bytes() {
+        // Latin-1 text isn't valid UTF-8, and has to come back byte for byte
+        let text = "push.s \"caf\\xe9 \\\\x\"\ncall.i show_debug_message(argc=1)\npopz.v\nexit.i\n";
+        assert_disassembly_round_trip(version(2, 0, 0, 0, 17), text);
+
+        let written = assembled(version(2, 0, 0, 0, 17), text);
+        let mut reader = Reader::new(Cursor::new(written.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        reader.deserialize().unwrap();
+        assert!(reader.strings().unwrap().find(b"caf\xe9 \\x").is_some());
+        let disassembly = Disassembler::new(&reader).unwrap().disassemble(0).unwrap();
+        assert!(disassembly.contains("\"caf\\xe9 \\\\x\""), "{}", disassembly);
+    }
+
+    #[test]
+    fn assembles_new_child_function() {
+        // The new child grows the pointer table, moving every bytecode address after it
+        let text = "push.v self.x\npop.v.v self.x\n\n> gml_Script_scr_new (locals=0, argc=0)\npush.v self.x\ncall.i show_debug_message(argc=1)\npopz.v\nexit.i\n";
+        let written = assembled(version(2, 3, 0, 0, 17), text);
+        assert_round_trip(&written);
+
+        let mut reader = Reader::new(Cursor::new(written.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        reader.deserialize().unwrap();
+        let code = reader.code().unwrap();
+        assert_eq!(code.entries.len(), 2);
+        let variables = reader.variables().unwrap().references(code).unwrap();
+        let functions = reader.functions().unwrap().references(code).unwrap();
+        let locations = |references: &[references::Reference]| references.iter()
+            .map(|reference| (reference.code_id, reference.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(locations(&variables[0]), vec![(0, 0), (0, 8), (1, 0)]);
+        assert_eq!(locations(&functions[0]), vec![(1, 8)]);
+    }
+
+    // Lays a data file out byte by byte, pointers get filled in once every label has been placed
+    struct HandAssembler {
+        data: Vec<u8>,
+        labels: HashMap<&'static str, u32>,
+        pointers: Vec<(usize, &'static str, u32, bool)>, // Where each pointer goes, its label, an addend and whether it's relative
+    }
+
+    impl HandAssembler {
+        fn new() -> Self {
+            Self { data: b"FORM\0\0\0\0".to_vec(), labels: HashMap::new(), pointers: Vec::new() }
+        }
+
+        fn label(&mut self, name: &'static str) -> &mut Self {
+            assert!(self.labels.insert(name, self.data.len() as u32).is_none(), "Label {} placed twice", name);
+            self
+        }
+
+        fn bytes(&mut self, values: &[u8]) -> &mut Self {
+            self.data.extend(values);
+            self
+        }
+
+        fn shorts(&mut self, values: &[i16]) -> &mut Self {
+            self.data.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+            self
+        }
+
+        fn words(&mut self, values: &[i32]) -> &mut Self {
+            self.data.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+            self
+        }
+
+        fn floats(&mut self, values: &[f32]) -> &mut Self {
+            self.data.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+            self
+        }
+
+        fn pointer(&mut self, label: &'static str, addend: u32, relative: bool) -> &mut Self {
+            self.pointers.push((self.data.len(), label, addend, relative));
+            self.words(&[0])
+        }
+
+        fn ptr(&mut self, label: &'static str) -> &mut Self {
+            self.pointer(label, 0, false)
+        }
+
+        // Relative to the pointer itself, like the bytecode address of code entries
+        fn rel(&mut self, label: &'static str) -> &mut Self {
+            self.pointer(label, 0, true)
+        }
+
+        // Pointer strings point past the length, straight at the characters
+        fn string_ptr(&mut self, string: &'static str) -> &mut Self {
+            self.pointer(string, 4, false)
+        }
+
+        fn string(&mut self, string: &'static str) -> &mut Self {
+            while !self.data.len().is_multiple_of(4) {
+                self.data.push(0);
+            }
+            self.label(string).words(&[string.len() as i32]).bytes(string.as_bytes()).bytes(&[0])
+        }
+
+        fn chunk(&mut self, name: &str, body: impl FnOnce(&mut Self)) -> &mut Self {
+            self.bytes(name.as_bytes()).words(&[0]);
+            let start = self.data.len();
+            body(self);
+            let length = (self.data.len() - start) as u32;
+            self.data[start - 4..start].copy_from_slice(&length.to_le_bytes());
+            self
+        }
+
+        fn finish(&mut self) -> Vec<u8> {
+            for (position, label, addend, relative) in self.pointers.iter() {
+                let target = self.labels.get(label).unwrap_or_else(|| panic!("Label {} was never placed", label)) + addend;
+                let value = if *relative { target.wrapping_sub(*position as u32) } else { target };
+                self.data[*position..*position + 4].copy_from_slice(&value.to_le_bytes());
+            }
+            let size = (self.data.len() - 8) as u32;
+            self.data[4..8].copy_from_slice(&size.to_le_bytes());
+            self.data.clone()
+        }
+    }
+
+    const STRINGS: [&str; 11] = [
+        "fixture", "Default", "Fixture", "Fixture Game", "spr_player", "rm_start", "Instances",
+        "gml_Object_obj_player_Create_0", "x", "show_debug_message", "arguments",
+    ];
+
+    // A GMS2 file with bytecode 17, assembled without going through Clovy at all
+    fn hand_written_gms2() -> Vec<u8> {
+        let mut file = HandAssembler::new();
+        file.chunk("GEN8", |gen8| {
+            gen8.bytes(&[0, 17, 0, 0]).string_ptr("fixture").string_ptr("Default").words(&[100000, 10000000, 1337])
+                .bytes(&[0; 16]) // Legacy GUID
+                .string_ptr("Fixture").words(&[2, 0, 0, 0, 1366, 768, 0x20, 0])
+                .bytes(&[0; 16]) // License MD5
+                .words(&[1700000000, 0]).string_ptr("Fixture Game").words(&[0, 0, 0, 0, 0])
+                .words(&[6502, 1, 0]) // Debugger port and room order
+                .words(&[1, 0, 2, 0, 3, 0, 4, 0, 5, 0]).floats(&[60.0]).words(&[1])
+                .bytes(&(0..16).collect::<Vec<_>>());
+        });
+        file.chunk("SPRT", |sprt| {
+            sprt.words(&[1]).ptr("sprite");
+            sprt.label("sprite").string_ptr("spr_player").words(&[16, 16, 0, 15, 15, 0, 1, 0, 1, 0, 1, 8, 8])
+                .words(&[-1, 1, 0]).floats(&[15.0]).words(&[0]) // Special version 1, normal sprite
+                .words(&[1]).ptr("item")
+                .words(&[1]).bytes(&[0xf0, 0x0f].repeat(16));
+        });
+        file.chunk("ROOM", |room| {
+            room.words(&[1]).ptr("room");
+            room.label("room").string_ptr("rm_start").words(&[0, 1366, 768, 30, 0, 0, 1, -1, 0x20001])
+                .ptr("backgrounds").ptr("views").ptr("objects").ptr("tiles")
+                .words(&[0, 0, 0, 1366, 768]).floats(&[0.0, 10.0, 0.1])
+                .ptr("layers");
+            room.label("backgrounds").words(&[0]);
+            room.label("views").words(&[1]).ptr("view");
+            room.label("view").words(&[1, 0, 0, 1366, 768, 0, 0, 1366, 768, 32, 32, -1, -1, -1]);
+            room.label("objects").words(&[1]).ptr("object");
+            room.label("object").words(&[64, 96, 0, 100001, -1]).floats(&[1.0, 1.0]).words(&[-1]).floats(&[0.0]).words(&[-1]);
+            room.label("tiles").words(&[0]);
+            room.label("layers").words(&[1]).ptr("layer");
+            room.label("layer").string_ptr("Instances").words(&[1, 2, 0]).floats(&[0.0; 4]).words(&[1])
+                .words(&[1, 100001]);
+        });
+        file.chunk("TPAG", |tpag| {
+            tpag.words(&[1]).ptr("item");
+            tpag.label("item").shorts(&[0, 0, 16, 16, 0, 0, 16, 16, 16, 16, 0]);
+        });
+        file.chunk("CODE", |code| {
+            code.words(&[1]).ptr("entry");
+            // push.v self.x, call.i show_debug_message(argc=1), popz.v, exit.i
+            // The last reference in a chain holds the string id of the name instead of the distance to the next one
+            code.label("bytecode").bytes(&[0xff, 0xff, 0x05, 0xc0, 8, 0x00, 0x00, 0xa0])
+                .label("call").bytes(&[0x01, 0x00, 0x02, 0xd9, 9, 0x00, 0x00, 0x00])
+                .bytes(&[0x00, 0x00, 0x05, 0x9e, 0x00, 0x00, 0x02, 0x9d]);
+            code.label("entry").string_ptr("gml_Object_obj_player_Create_0").words(&[24]).shorts(&[1, 0]).rel("bytecode").words(&[0]);
+        });
+        file.chunk("VARI", |vari| {
+            vari.words(&[1, 1, 1])
+                .string_ptr("x").words(&[-1, 0, 1]).ptr("bytecode")
+                .string_ptr("arguments").words(&[-7, 0, 0, -1]);
+        });
+        file.chunk("FUNC", |func| {
+            func.words(&[1]).string_ptr("show_debug_message").words(&[1]).ptr("call");
+            func.words(&[1, 1]).string_ptr("gml_Object_obj_player_Create_0").words(&[0]).string_ptr("arguments");
+        });
+        file.chunk("STRG", |strg| {
+            strg.words(&[STRINGS.len() as i32]);
+            for string in STRINGS {
+                strg.ptr(string);
+            }
+            for string in STRINGS {
+                strg.string(string);
+            }
+        });
+        file.finish()
+    }
+
+    #[test]
+    fn round_trip_hand_written_gms2() {
+        let data = hand_written_gms2();
+        assert_eq!(chunk_spans(&data).unwrap().iter().map(|span| span.start).collect::<Vec<_>>(), vec![16, 224, 360, 640, 678, 738, 798, 842]);
+        assert_round_trip(&data);
+
+        let mut reader = Reader::new(Cursor::new(data.as_slice()), None);
+        reader.deserialize_chunks().unwrap();
+        assert_eq!(reader.version_report.detected, (2, 0, 0, 0));
+        assert!(reader.version_report.evidence.is_empty(), "{}", reader.version_report);
+        reader.deserialize().unwrap();
+
+        let Some(ChunkOutput::ChunkGen8(gen8)) = reader.chunks.get(b"GEN8".as_slice()) else { panic!("GEN8 is missing") };
+        assert_eq!((gen8.format_id, gen8.major, gen8.minor, gen8.release, gen8.build), (17, 2, 0, 0, 0));
+        assert_eq!((gen8.game_name.as_slice(), gen8.display_name.as_slice()), (b"Fixture".as_slice(), b"Fixture Game".as_slice()));
+        assert_eq!((gen8.debugger_port, gen8.room_order.as_slice(), gen8.gms2_fps), (6502, [0].as_slice(), 60.0));
+        assert_eq!(gen8.gms2_random_uid, vec![1, 2, 3, 4, 5]);
+        assert!(gen8.gms2_allow_statistics);
+
+        let Some(ChunkOutput::ChunkTpag(tpag)) = reader.chunks.get(b"TPAG".as_slice()) else { panic!("TPAG is missing") };
+        let item = tpag.texture_page_items[0].clone();
+        assert_eq!((item.borrow().source_width, item.borrow().bounding_height, item.borrow().texture_page_id), (16, 16, 0));
+
+        let Some(ChunkOutput::ChunkSprt(sprt)) = reader.chunks.get(b"SPRT".as_slice()) else { panic!("SPRT is missing") };
+        let sprite = &sprt.sprites[0];
+        assert_eq!((sprite.name.as_slice(), sprite.width, sprite.height, sprite.margin_right, sprite.margin_bottom), (b"spr_player".as_slice(), 16, 16, 15, 15));
+        assert_eq!((sprite.special_version, sprite.gms2_playback_speed, sprite.sep_masks), (1, 15.0, SepMaskType::Precise));
+        assert!(sprite.texture_items[0].as_ref().is_some_and(|texture| texture.borrow().source_height == 16));
+        let pixels = [(0, 0), (4, 0), (12, 15), (8, 15)].map(|(x, y)| sprite.mask_pixel(&reader.version_info, 0, x, y));
+        assert_eq!(pixels, [true, false, true, false]);
+
+        let Some(ChunkOutput::ChunkRoom(rooms)) = reader.chunks.get(b"ROOM".as_slice()) else { panic!("ROOM is missing") };
+        let room = &rooms.rooms[0];
+        assert_eq!((room.name.as_slice(), room.caption.as_ref(), room.flags), (b"rm_start".as_slice(), None, RoomEntryFlags::EnableViews | RoomEntryFlags::IsGMS2));
+        assert_eq!((room.backgrounds.len(), room.views[0].port_width, room.views[0].border_x), (0, 1366, 32));
+        assert_eq!((room.game_objects[0].x, room.game_objects[0].instance_id, room.game_objects[0].pre_create_code_id), (64, 100001, -1));
+        assert_eq!((room.gravity_y, room.meters_per_pixel), (10.0, 0.1));
+        let LayerData::Instances(instances) = &room.layers[0].data else { panic!("Not an instance layer") };
+        assert_eq!((room.layers[0].name.as_slice(), room.layers[0].kind), (b"Instances".as_slice(), LayerType::Instances));
+        assert_eq!(instances.instance_ids, vec![100001]);
+
+        let strings = reader.strings().unwrap();
+        assert_eq!(strings.strings, STRINGS.map(BString::from).to_vec());
+        let code = reader.code().unwrap();
+        let entry = &code.entries[0];
+        assert_eq!((entry.name.as_slice(), entry.length, entry.locals_count, entry.arguments_count), (b"gml_Object_obj_player_Create_0".as_slice(), 24, 1, 0));
+        assert_eq!(entry.bytecode, data[686..710]);
+        let variables = reader.variables().unwrap();
+        assert_eq!((variables.variables[0].instance_type, variables.variables[1].instance_type), (InstanceType::Self_, InstanceType::Local));
+        let functions = reader.functions().unwrap();
+        assert_eq!(functions.code_locals[0].locals[0].name.as_slice(), b"arguments");
+        let locations = |references: &[references::Reference]| references.iter()
+            .map(|reference| (reference.address, reference.code_id, reference.offset))
+            .collect::<Vec<_>>();
+        let references = variables.references(code).unwrap();
+        assert_eq!((locations(&references[0]), locations(&references[1])), (vec![(686, 0, 0)], Vec::new()));
+        assert_eq!(locations(&functions.references(code).unwrap()[0]), vec![(694, 0, 8)]);
+
+        let disassembly = Disassembler::new(&reader).unwrap().disassemble(0).unwrap();
+        assert_eq!(disassembly, ".localvar 0 arguments\n:[0]\npush.v self.x\ncall.i show_debug_message(argc=1)\npopz.v\nexit.i\n");
+    }
+}
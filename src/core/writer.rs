@@ -1,7 +1,29 @@
+use crate::core::chunks::{raw::RawChunk, gen8::ChunkGEN8, ChunkOutput, optn::ChunkOPTN, lang::ChunkLANG, extn::ChunkEXTN, sond::ChunkSOND, agrp::ChunkAGRP, sprt::ChunkSPRT, bgnd::ChunkBGND, path::ChunkPATH, scpt::ChunkSCPT, glob::ChunkGLOB, shdr::ChunkSHDR, font::ChunkFONT, tmln::ChunkTMLN, objt::ChunkOBJT, feds::ChunkFEDS, acrv::ChunkACRV, seqn::ChunkSEQN, room::ChunkROOM, tpag::ChunkTPAG, txtr::ChunkTXTR, strg::ChunkSTRG, code::ChunkCODE, vari::ChunkVARI, func::ChunkFUNC, audo::ChunkAUDO, tgin::ChunkTGIN, embi::ChunkEMBI, dafl::ChunkDAFL, feat::ChunkFEAT, tags::ChunkTAGS, psem::ChunkPSEM, psys::ChunkPSYS};
 use bstr::BString;
 use byteorder::{LittleEndian, WriteBytesExt};
-use std::{collections::HashMap, io::{Result, Seek, SeekFrom, Write, Read}, path::PathBuf};
-use super::{GMVersionInfo, GlobalData, reader::Reader, chunks::ChunkOutput};
+use std::{any::type_name, collections::{BTreeMap, HashMap}, io::{Error, ErrorKind, Result, Seek, SeekFrom, Write, Read}, panic::Location, path::PathBuf};
+use tracing::info;
+use super::{GMVersionInfo, GlobalData, reader::Reader, serializing::Serialize, error::ClovyResult};
+
+// Address and type name of an object, so a struct and its first field don't collide
+type ObjectKey = (usize, &'static str);
+
+// Bytecode is keyed by the index of the code entry owning it instead, since the models pointing into it only know that
+const BYTECODE: &str = "bytecode";
+
+// What wrote each part of the file, only recorded when asked for since verifying is the one user
+#[derive(Default)]
+pub struct WriteTrace {
+    pub writes: BTreeMap<u64, &'static Location<'static>>, // Line of the model that first wrote at each offset
+    pub elements: Vec<TracedElement>,
+}
+
+pub struct TracedElement {
+    pub type_name: &'static str,
+    pub index: usize, // Position in the list holding it
+    pub start: u64,
+    pub end: u64, // Where the element's own bytes stop, anything it points to lives elsewhere
+}
 
 pub struct Writer<T>
 where
@@ -11,9 +33,13 @@ where
     pub version_info: GMVersionInfo,
     pub chunks: HashMap<BString, ChunkOutput>,
     pub chunk_order: Vec<BString>,
-    pub serialize_strings: HashMap<BString, Vec<u64>>,
+    pub serialize_strings: HashMap<BString, Vec<u64>>, // Placeholders of every string pointer, patched once STRG is written
+    pub string_offsets: HashMap<BString, u64>, // Offset of each string's characters in the written STRG
+    pub pointer_placeholders: HashMap<ObjectKey, Vec<(u64, u64)>>, // Pointers to objects and how far into them, patched once everything is written
+    pub object_offsets: HashMap<ObjectKey, u64>, // Where each pointed object ended up
     pub global_data: GlobalData,
     pub path: Option<PathBuf>,
+    pub trace: Option<WriteTrace>,
 }
 
 impl<T> Writer<T>
@@ -27,8 +53,12 @@ where
             chunks: HashMap::new(),
             chunk_order: Vec::new(),
             serialize_strings: HashMap::new(),
+            string_offsets: HashMap::new(),
+            pointer_placeholders: HashMap::new(),
+            object_offsets: HashMap::new(),
             global_data: GlobalData::default(),
             path,
+            trace: None,
         }
     }
 
@@ -41,9 +71,208 @@ where
             chunks: reader.chunks.clone(),
             chunk_order: reader.chunk_order.clone(),
             serialize_strings: HashMap::new(),
+            string_offsets: HashMap::new(),
+            pointer_placeholders: HashMap::new(),
+            object_offsets: HashMap::new(),
             global_data: reader.global_data.clone(),
             path,
+            trace: None,
+        }
+    }
+
+    // Writes the whole data file, the opposite of Reader::deserialize_chunks and Reader::deserialize
+    pub fn serialize(&mut self) -> ClovyResult<()> {
+        self.serialize_strings.clear();
+        self.string_offsets.clear();
+        self.pointer_placeholders.clear();
+        self.object_offsets.clear();
+        if let Some(trace) = self.trace.as_mut() {
+            *trace = WriteTrace::default();
         }
+        self.write_bytes(b"FORM")?;
+        self.write_u32(0)?; // Patched once every chunk is written
+
+        // Chunks are taken out while writing so they can borrow the writer
+        let chunks = std::mem::take(&mut self.chunks);
+        let result = self.serialize_chunks(&chunks);
+        self.chunks = chunks;
+        result?;
+
+        let end = self.stream_position()?;
+        self.seek(SeekFrom::Start(4))?;
+        self.write_u32((end - 8) as _)?;
+        self.seek(SeekFrom::Start(end))?;
+
+        self.patch_strings()?;
+        self.patch_pointers()?;
+        self.container.flush()?;
+        Ok(())
+    }
+
+    fn serialize_chunks(&mut self, chunks: &HashMap<BString, ChunkOutput>) -> Result<()> {
+        macro_rules! serialize_chunk {
+            ($value: expr, $ctype: ty, $name: expr) => {
+                <$ctype>::serialize($value, self).map_err(|e| self.locate_chunk_error(e, $name))?;
+            }
+        }
+        let chunk_order = self.chunk_order.clone();
+        for (index, name) in chunk_order.iter().enumerate() {
+            let chunk = chunks.get(name).ok_or_else(|| Error::new(ErrorKind::NotFound, format!("Chunk not found: {}", name)))?;
+            if name.len() != 4 {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("Invalid chunk name: {}", name)));
+            }
+            info!("Serializing chunk: {}", name);
+            self.write_bytes(name)?;
+            let length_offset = self.stream_position()?;
+            self.write_u32(0)?;
+            let start = self.stream_position()?;
+            match chunk {
+                ChunkOutput::ChunkGen8(value) => { serialize_chunk!(value, ChunkGEN8, name); }
+                ChunkOutput::ChunkOptn(value) => { serialize_chunk!(value, ChunkOPTN, name); }
+                ChunkOutput::ChunkLang(value) => { serialize_chunk!(value, ChunkLANG, name); }
+                ChunkOutput::ChunkExtn(value) => { serialize_chunk!(value, ChunkEXTN, name); }
+                ChunkOutput::ChunkSond(value) => { serialize_chunk!(value, ChunkSOND, name); }
+                ChunkOutput::ChunkAgrp(value) => { serialize_chunk!(value, ChunkAGRP, name); }
+                ChunkOutput::ChunkSprt(value) => { serialize_chunk!(value, ChunkSPRT, name); }
+                ChunkOutput::ChunkBgnd(value) => { serialize_chunk!(value, ChunkBGND, name); }
+                ChunkOutput::ChunkPath(value) => { serialize_chunk!(value, ChunkPATH, name); }
+                ChunkOutput::ChunkScpt(value) => { serialize_chunk!(value, ChunkSCPT, name); }
+                ChunkOutput::ChunkGlob(value) => { serialize_chunk!(value, ChunkGLOB, name); }
+                ChunkOutput::ChunkShdr(value) => { serialize_chunk!(value, ChunkSHDR, name); }
+                ChunkOutput::ChunkFont(value) => { serialize_chunk!(value, ChunkFONT, name); }
+                ChunkOutput::ChunkTmln(value) => { serialize_chunk!(value, ChunkTMLN, name); }
+                ChunkOutput::ChunkObjt(value) => { serialize_chunk!(value, ChunkOBJT, name); }
+                ChunkOutput::ChunkFeds(value) => { serialize_chunk!(value, ChunkFEDS, name); }
+                ChunkOutput::ChunkAcrv(value) => { serialize_chunk!(value, ChunkACRV, name); }
+                ChunkOutput::ChunkSeqn(value) => { serialize_chunk!(value, ChunkSEQN, name); }
+                ChunkOutput::ChunkRoom(value) => { serialize_chunk!(value, ChunkROOM, name); }
+                ChunkOutput::ChunkTpag(value) => { serialize_chunk!(value, ChunkTPAG, name); }
+                ChunkOutput::ChunkTxtr(value) => { serialize_chunk!(value, ChunkTXTR, name); }
+                ChunkOutput::ChunkStrg(value) => { self.serialize_string_table(value).map_err(|e| self.locate_chunk_error(e, name))?; }
+                ChunkOutput::ChunkCode(value) => { serialize_chunk!(value, ChunkCODE, name); }
+                ChunkOutput::ChunkVari(value) => { serialize_chunk!(value, ChunkVARI, name); }
+                ChunkOutput::ChunkFunc(value) => { serialize_chunk!(value, ChunkFUNC, name); }
+                ChunkOutput::ChunkAudo(value) => { serialize_chunk!(value, ChunkAUDO, name); }
+                ChunkOutput::ChunkTgin(value) => { serialize_chunk!(value, ChunkTGIN, name); }
+                ChunkOutput::ChunkEmbi(value) => { serialize_chunk!(value, ChunkEMBI, name); }
+                ChunkOutput::ChunkDafl(value) => { serialize_chunk!(value, ChunkDAFL, name); }
+                ChunkOutput::ChunkFeat(value) => { serialize_chunk!(value, ChunkFEAT, name); }
+                ChunkOutput::ChunkTags(value) => { serialize_chunk!(value, ChunkTAGS, name); }
+                ChunkOutput::ChunkPsem(value) => { serialize_chunk!(value, ChunkPSEM, name); }
+                ChunkOutput::ChunkPsys(value) => { serialize_chunk!(value, ChunkPSYS, name); }
+                ChunkOutput::RawChunk(value) => { serialize_chunk!(value, RawChunk, name); }
+            }
+            // Every chunk but the last one is padded, and the padding counts towards its length
+            if self.version_info.align_chunks_to_16 && index + 1 < chunk_order.len() {
+                self.pad_check_byte(16, 0)?;
+            }
+            let end = self.stream_position()?;
+            self.seek(SeekFrom::Start(length_offset))?;
+            self.write_u32((end - start) as _)?;
+            self.seek(SeekFrom::Start(end))?;
+        }
+
+        Ok(())
+    }
+
+    // Strings referenced by the chunks before STRG that aren't in the table yet get appended to it
+    fn serialize_string_table(&mut self, table: &ChunkSTRG) -> Result<()> {
+        let mut missing = self.serialize_strings.iter()
+            .filter(|(string, _)| table.find(string).is_none())
+            .map(|(string, offsets)| (offsets.iter().min().copied().unwrap_or_default(), string.clone()))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return ChunkSTRG::serialize(table, self);
+        }
+        missing.sort();
+        let mut table = table.clone();
+        for (_, string) in missing {
+            table.push(string);
+        }
+        ChunkSTRG::serialize(&table, self)
+    }
+
+    fn patch_strings(&mut self) -> Result<()> {
+        let end = self.stream_position()?;
+        for (string, placeholders) in std::mem::take(&mut self.serialize_strings) {
+            let offset = *self.string_offsets.get(&string)
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("String {:?} is referenced after STRG was written", string)))?;
+            for placeholder in placeholders {
+                self.seek(SeekFrom::Start(placeholder))?;
+                self.write_u32(offset as _)?;
+            }
+        }
+        self.seek(SeekFrom::Start(end))?;
+
+        Ok(())
+    }
+
+    fn patch_pointers(&mut self) -> Result<()> {
+        let end = self.stream_position()?;
+        let mut unresolved = Vec::new();
+        for (key, placeholders) in std::mem::take(&mut self.pointer_placeholders) {
+            let Some(offset) = self.object_offsets.get(&key).copied() else {
+                unresolved.push(key.1);
+                continue;
+            };
+            for (placeholder, addend) in placeholders {
+                self.seek(SeekFrom::Start(placeholder))?;
+                self.write_u32((offset + addend) as _)?;
+            }
+        }
+        self.seek(SeekFrom::Start(end))?;
+        if !unresolved.is_empty() {
+            unresolved.sort();
+            unresolved.dedup();
+            return Err(Error::new(ErrorKind::InvalidData, format!("Pointers to objects that were never written: {}", unresolved.join(", "))));
+        }
+
+        Ok(())
+    }
+
+    fn object_key<K: ?Sized>(value: &K) -> ObjectKey {
+        (value as *const K as *const () as usize, type_name::<K>())
+    }
+
+    // Marks `value` as written at the current position, resolving every pointer to it
+    pub fn register_pointer<K: ?Sized>(&mut self, value: &K) -> Result<()> {
+        let offset = self.stream_position()?;
+        self.object_offsets.entry(Self::object_key(value)).or_insert(offset);
+        Ok(())
+    }
+
+    pub fn register_pointer_object<K: Serialize>(&mut self, value: &K) -> Result<()> {
+        let offset = self.stream_position()?;
+        self.object_offsets.entry((K::address(value), type_name::<K>())).or_insert(offset);
+        Ok(())
+    }
+
+    // Marks the bytecode owned by code entry `code_id` as written at `offset`
+    pub fn register_bytecode(&mut self, code_id: usize, offset: u64) {
+        self.object_offsets.entry((code_id, BYTECODE)).or_insert(offset);
+    }
+
+    // Records the caller as the writer of whatever goes at the current position
+    #[track_caller]
+    fn trace_write(&mut self) -> Result<()> {
+        if let Some(trace) = self.trace.as_mut() {
+            let offset = self.container.stream_position()?;
+            trace.writes.entry(offset).or_insert(Location::caller());
+        }
+        Ok(())
+    }
+
+    pub fn trace_element(&mut self, type_name: &'static str, index: usize, start: u64) -> Result<()> {
+        if let Some(trace) = self.trace.as_mut() {
+            let end = self.container.stream_position()?;
+            trace.elements.push(TracedElement {
+                type_name,
+                index,
+                start,
+                end,
+            });
+        }
+        Ok(())
     }
 
     pub fn stream_position(&mut self) -> Result<u64> {
@@ -70,6 +299,7 @@ where
         Ok(())
     }
 
+    #[track_caller]
     pub fn pad_check_byte(&mut self, alignment: i64, byte: u8) -> Result<()> {
         while self.stream_position()? as i64 % alignment != 0 {
             self.write_u8(byte)?;
@@ -77,87 +307,150 @@ where
         Ok(())
     }
 
+    #[track_caller]
     pub fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.trace_write()?;
         self.container.write_all(buf)
     }
 
-    pub fn write_pointer<K>(&mut self, _value: K) -> Result<()> {
-        todo!("Not Implemented");
+    // Pointer to `value`, which gets written (and registered) somewhere else
+    #[track_caller]
+    pub fn write_pointer<K: ?Sized>(&mut self, value: &K) -> Result<()> {
+        let placeholder = self.stream_position()?;
+        self.pointer_placeholders.entry(Self::object_key(value)).or_default().push((placeholder, 0));
+        self.write_u32(0)
     }
 
+    #[track_caller]
     pub fn write_bool(&mut self, value: bool) -> Result<()> {
+        self.trace_write()?;
         self.container.write_u8(value as u8)?;
         Ok(())
     }
 
+    #[track_caller]
     pub fn write_wide_bool(&mut self, value: bool) -> Result<()> {
+        self.trace_write()?;
         self.container.write_u32::<LittleEndian>(value as u32)?;
         Ok(())
     }
 
+    #[track_caller]
     pub fn write_bytes(&mut self, value: &[u8]) -> Result<()> {
+        self.trace_write()?;
         self.container.write_all(value)?;
         Ok(())
     }
 
+    #[track_caller]
     pub fn write_pointer_string(&mut self, string: &BString) -> Result<()> {
-        if !self.serialize_strings.contains_key(string) {
-            self.serialize_strings.insert(string.clone(), Vec::new());
-        }
-        self.serialize_strings.get_mut(string).unwrap().push(self.container.stream_position()?);
-        self.container.write_u32::<LittleEndian>(0).unwrap();
+        let offset = self.container.stream_position()?;
+        self.serialize_strings.entry(string.clone()).or_default().push(offset);
+        self.trace_write()?;
+        self.container.write_u32::<LittleEndian>(0)?;
         Ok(())
     }
 
-    pub fn write_pointer_object<K>(&mut self, _value: K) -> Result<()> {
-        todo!("Not Implemented");
+    #[track_caller]
+    pub fn write_pointer_string_option(&mut self, string: Option<&BString>) -> Result<()> {
+        match string {
+            Some(string) => self.write_pointer_string(string),
+            None => self.write_u32(0),
+        }
+    }
+
+    #[track_caller]
+    pub fn write_pointer_object<K: Serialize>(&mut self, value: &K) -> Result<()> {
+        let placeholder = self.stream_position()?;
+        self.pointer_placeholders.entry((K::address(value), type_name::<K>())).or_default().push((placeholder, 0));
+        self.write_u32(0)
+    }
+
+    // Absolute address of the byte at `offset` into the bytecode owned by code entry `code_id`, wherever CODE puts it
+    #[track_caller]
+    pub fn write_bytecode_pointer(&mut self, code_id: usize, offset: u32) -> Result<()> {
+        let placeholder = self.stream_position()?;
+        self.pointer_placeholders.entry((code_id, BYTECODE)).or_default().push((placeholder, offset as u64));
+        self.write_u32(0)
+    }
+
+    #[track_caller]
+    pub fn write_pointer_object_safe<K: Serialize>(&mut self, value: &Option<K>) -> Result<()> {
+        match value {
+            Some(value) => self.write_pointer_object(value),
+            None => self.write_u32(0),
+        }
     }
 
+    #[track_caller]
     pub fn write_u8(&mut self, value: u8) -> Result<()> {
+        self.trace_write()?;
         self.container.write_u8(value)
     }
 
+    #[track_caller]
     pub fn write_u16(&mut self, value: u16) -> Result<()> {
+        self.trace_write()?;
         self.container.write_u16::<LittleEndian>(value)
     }
 
+    #[track_caller]
     pub fn write_u32(&mut self, value: u32) -> Result<()> {
+        self.trace_write()?;
         self.container.write_u32::<LittleEndian>(value)
     }
 
+    #[track_caller]
     pub fn write_u64(&mut self, value: u64) -> Result<()> {
+        self.trace_write()?;
         self.container.write_u64::<LittleEndian>(value)
     }
 
+    #[track_caller]
     pub fn write_u128(&mut self, value: u128) -> Result<()> {
+        self.trace_write()?;
         self.container.write_u128::<LittleEndian>(value)
     }
 
+    #[track_caller]
     pub fn write_i8(&mut self, value: i8) -> Result<()> {
+        self.trace_write()?;
         self.container.write_i8(value)
     }
 
+    #[track_caller]
     pub fn write_i16(&mut self, value: i16) -> Result<()> {
+        self.trace_write()?;
         self.container.write_i16::<LittleEndian>(value)
     }
 
+    #[track_caller]
     pub fn write_i32(&mut self, value: i32) -> Result<()> {
+        self.trace_write()?;
         self.container.write_i32::<LittleEndian>(value)
     }
 
+    #[track_caller]
     pub fn write_i64(&mut self, value: i64) -> Result<()> {
+        self.trace_write()?;
         self.container.write_i64::<LittleEndian>(value)
     }
 
+    #[track_caller]
     pub fn write_i128(&mut self, value: i128) -> Result<()> {
+        self.trace_write()?;
         self.container.write_i128::<LittleEndian>(value)
     }
 
+    #[track_caller]
     pub fn write_f32(&mut self, value: f32) -> Result<()> {
+        self.trace_write()?;
         self.container.write_f32::<LittleEndian>(value)
     }
 
+    #[track_caller]
     pub fn write_f64(&mut self, value: f64) -> Result<()> {
+        self.trace_write()?;
         self.container.write_f64::<LittleEndian>(value)
     }
 }
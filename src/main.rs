@@ -1,15 +1,17 @@
 use core::reader::Reader;
 use std::{fs::File, io::BufReader};
+use tracing::info;
 
 pub mod core;
 
-fn main() {
+fn main() -> color_eyre::Result<()> {
     tracing_subscriber::fmt().init();
-    color_eyre::install().unwrap();
+    color_eyre::install()?;
 
-    let f = BufReader::new(File::open("data-uty.win").expect("Unable to open the data.win file"));
+    let f = BufReader::new(File::open("data-uty.win")?);
     let mut r = Reader::new(f, Some("data-uty.win".into()));
-    r.deserialize_chunks().unwrap();
-    r.version_info.set_version(2023, 4, 0, 0);
-    r.deserialize().unwrap();
+    r.deserialize_chunks()?;
+    info!("{}", r.version_report);
+    r.deserialize()?;
+    Ok(())
 }